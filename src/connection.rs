@@ -1,12 +1,15 @@
+use std::{thread, time::Duration};
+
 use anyhow::{bail, Error};
 use clap::Args;
+use log::warn;
 use odbc_api::{
     environment, escape_attribute_value, handles::OutputStringBuffer, Connection,
     ConnectionOptions, DriverCompleteOption,
 };
 
 /// Command line arguments used to establish a connection with the ODBC data source
-#[derive(Args)]
+#[derive(Args, Clone)]
 pub struct ConnectOpts {
     #[arg(long, conflicts_with = "dsn")]
     /// Prompts the user for missing information from the connection string. Only supported on
@@ -30,18 +33,53 @@ pub struct ConnectOpts {
     /// password is going to be appended at the end of it as the `PWD` attribute.
     #[arg(long, short = 'p', env = "ODBC_PASSWORD", hide_env_values = true)]
     password: Option<String>,
+    /// Number of seconds to wait for the login to complete. `0` waits indefinitely. If not
+    /// specified the default of the driver is used.
+    #[arg(long, conflicts_with = "prompt")]
+    login_timeout: Option<u32>,
+    /// Number of seconds to wait for a statement to execute, before it is canceled. `0` waits
+    /// indefinitely, which is also the default. Not all drivers support this, e.g. PostgreSQL and
+    /// Microsoft SQL Server do, but SQLite and MariaDB do not.
+    #[arg(long)]
+    query_timeout: Option<usize>,
+}
+
+impl ConnectOpts {
+    /// Timeout passed on to the statements executed against the data source.
+    pub fn query_timeout_sec(&self) -> Option<usize> {
+        self.query_timeout
+    }
+}
+
+/// Sets the text format of Oracle's `TIMESTAMP WITH TIME ZONE` to one we can parse into an
+/// instant. The default depends on the locale of the session, e.g. `DD-MON-RR HH.MI.SSXFF AM TZR`.
+const ORACLE_TIMESTAMP_TZ_FORMAT: &str =
+    "ALTER SESSION SET NLS_TIMESTAMP_TZ_FORMAT = 'YYYY-MM-DD HH24:MI:SS.FF TZR'";
+
+/// Open a connection for fetching results into files. Like [`open_connection`], but configures
+/// the session so all values can be converted, e.g. Oracle's `TIMESTAMP WITH TIME ZONE`.
+pub fn open_query_connection<'e>(opt: &ConnectOpts) -> Result<Connection<'e>, Error> {
+    let conn = open_connection(opt)?;
+    if conn.database_management_system_name()? == "Oracle" {
+        conn.execute(ORACLE_TIMESTAMP_TZ_FORMAT, (), None)?;
+    }
+    Ok(conn)
 }
 
 /// Open a database connection using the options provided on the command line.
 pub fn open_connection<'e>(opt: &ConnectOpts) -> Result<Connection<'e>, Error> {
     let odbc_env = environment().expect("Enviornment must already be initialized in main.");
+    let options = ConnectionOptions {
+        login_timeout_sec: opt.login_timeout,
+        ..ConnectionOptions::default()
+    };
     // If a data source name has been given, try connecting with that.
     if let Some(dsn) = opt.dsn.as_deref() {
         let conn = odbc_env.connect(
             dsn,
             opt.user.as_deref().unwrap_or(""),
             opt.password.as_deref().unwrap_or(""),
-            ConnectionOptions::default(),
+            options,
         )?;
         return Ok(conn);
     }
@@ -79,10 +117,104 @@ pub fn open_connection<'e>(opt: &ConnectOpts) -> Result<Connection<'e>, Error> {
         DriverCompleteOption::NoPrompt
     };
 
-    // We are not interested in the completed connection string, beyond creating a connection, so
-    // we pass an empty buffer.
-    let mut completed_connection_string = OutputStringBuffer::empty();
-
-    let conn = odbc_env.driver_connect(&cs, &mut completed_connection_string, driver_completion)?;
+    let conn = if opt.prompt {
+        // We are not interested in the completed connection string, beyond creating a connection,
+        // so we pass an empty buffer.
+        let mut completed_connection_string = OutputStringBuffer::empty();
+        odbc_env.driver_connect(&cs, &mut completed_connection_string, driver_completion)?
+    } else {
+        odbc_env.connect_with_connection_string(&cs, options)?
+    };
     Ok(conn)
 }
+
+/// Invokes `f` until it succeeds, fails with an error which is not transient, or `retries` is
+/// exhausted. The time waited before each retry starts at `backoff` and doubles with each attempt.
+pub fn with_retries<T>(
+    retries: u32,
+    backoff: Duration,
+    mut f: impl FnMut() -> Result<T, Error>,
+) -> Result<T, Error> {
+    let mut attempt = 0;
+    loop {
+        match f() {
+            Err(error) if attempt < retries && is_transient(&error) => {
+                let wait = backoff.saturating_mul(2u32.saturating_pow(attempt));
+                attempt += 1;
+                warn!(attempt, retries, wait:? = wait; "Transient error, retrying: {error:#}");
+                thread::sleep(wait);
+            }
+            result => return result,
+        }
+    }
+}
+
+/// `true` if the error is likely to go away by reconnecting and trying again. These are errors
+/// with SQLSTATE class `08` (connection exception) and timeouts (`HYT00` and `HYT01`).
+pub fn is_transient(error: &Error) -> bool {
+    error.chain().any(|cause| {
+        let Some(odbc_api::Error::Diagnostics { record, .. }) = cause.downcast_ref() else {
+            return false;
+        };
+        let state = &record.state.0;
+        state.starts_with(b"08") || state == b"HYT00" || state == b"HYT01"
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::Cell, time::Duration};
+
+    use anyhow::{anyhow, Context};
+    use odbc_api::handles::{Record, State};
+
+    use super::{is_transient, with_retries};
+
+    fn odbc_error(state: &[u8; 5]) -> anyhow::Error {
+        let record = Record {
+            state: State(*state),
+            ..Record::default()
+        };
+        odbc_api::Error::Diagnostics {
+            record,
+            function: "SQLExecDirect",
+        }
+        .into()
+    }
+
+    #[test]
+    fn connection_failures_and_timeouts_are_transient() {
+        assert!(is_transient(&odbc_error(b"08S01")));
+        assert!(is_transient(&odbc_error(b"HYT00")));
+        assert!(is_transient(
+            &Err::<(), _>(odbc_error(b"08001"))
+                .context("Opening connection")
+                .unwrap_err()
+        ));
+        assert!(!is_transient(&odbc_error(b"42S02")));
+        assert!(!is_transient(&anyhow!("Invalid integer parameter")));
+    }
+
+    #[test]
+    fn retry_only_transient_errors() {
+        let attempts = Cell::new(0);
+        let result = with_retries(3, Duration::ZERO, || {
+            attempts.set(attempts.get() + 1);
+            if attempts.get() < 3 {
+                Err(odbc_error(b"08S01"))
+            } else {
+                Ok(42)
+            }
+        });
+        assert_eq!(42, result.unwrap());
+        assert_eq!(3, attempts.get());
+
+        attempts.set(0);
+        let result = with_retries(3, Duration::ZERO, || -> Result<(), _> {
+            attempts.set(attempts.get() + 1);
+            Err(odbc_error(b"42S02"))
+        });
+        assert!(result.is_err());
+        assert_eq!(1, attempts.get());
+    }
+}
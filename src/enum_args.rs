@@ -3,6 +3,7 @@ use clap::ValueEnum;
 use parquet::{
     basic::{BrotliLevel, Compression, Encoding, GzipLevel, ZstdLevel},
     errors::ParquetError,
+    file::properties::EnabledStatistics,
 };
 
 #[derive(Debug, Clone, Copy, ValueEnum)]
@@ -69,6 +70,18 @@ impl CompressionVariants {
     }
 }
 
+/// Format of the files written by the `query` subcommand.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    Parquet,
+    /// Comma separated values with a header line.
+    Csv,
+    /// JSON Lines, i.e. one JSON object per row.
+    Jsonl,
+    /// Arrow IPC. The file format (Feather V2) for files, the stream format for standard out.
+    Arrow,
+}
+
 pub fn encoding_from_str(source: &str) -> Result<Encoding, Error> {
     let encoding = match source {
         "plain" => Encoding::PLAIN,
@@ -95,3 +108,249 @@ pub fn column_encoding_from_str(source: &str) -> Result<(String, Encoding), Erro
     let (name, encoding) = source.split_at(pos);
     Ok((name.to_owned(), encoding_from_str(&encoding[1..])?))
 }
+
+/// Splits `COLUMN_NAME:VALUE` at the last colon, so column names may contain colons themselves.
+fn split_column_setting<'a>(source: &'a str, format: &str) -> Result<(String, &'a str), Error> {
+    let pos = source
+        .rfind(':')
+        .ok_or_else(|| anyhow!("Column setting must be passed in format: '{format}'"))?;
+    let (name, value) = source.split_at(pos);
+    Ok((name.to_owned(), &value[1..]))
+}
+
+/// Parses e.g. `description:zstd(9)` or `id:snappy`.
+pub fn column_compression_from_str(source: &str) -> Result<(String, Compression), Error> {
+    let (name, compression) = split_column_setting(source, "COLUMN_NAME:COMPRESSION[(LEVEL)]")?;
+    let (variant, level) = match compression.split_once('(') {
+        Some((variant, level)) => {
+            let level = level
+                .strip_suffix(')')
+                .ok_or_else(|| anyhow!("Compression level must be enclosed in parentheses."))?;
+            (variant, Some(level.parse()?))
+        }
+        None => (compression, None),
+    };
+    let variant = CompressionVariants::from_str(variant, true)
+        .map_err(|_| anyhow!("Sorry, I do not know a column compression called '{variant}'."))?;
+    Ok((name, variant.to_compression(level)?))
+}
+
+/// Parses e.g. `id:off` or `country:on`.
+pub fn column_dictionary_from_str(source: &str) -> Result<(String, bool), Error> {
+    let (name, enabled) = split_column_setting(source, "COLUMN_NAME:on|off")?;
+    let enabled = match enabled {
+        "on" => true,
+        "off" => false,
+        _ => bail!("Dictionary encoding must be either 'on' or 'off', not '{enabled}'."),
+    };
+    Ok((name, enabled))
+}
+
+/// Parses e.g. `description:none` or `id:page`.
+pub fn column_statistics_from_str(source: &str) -> Result<(String, EnabledStatistics), Error> {
+    let (name, statistics) = split_column_setting(source, "COLUMN_NAME:none|chunk|page")?;
+    let statistics = match statistics {
+        "none" => EnabledStatistics::None,
+        "chunk" => EnabledStatistics::Chunk,
+        "page" => EnabledStatistics::Page,
+        _ => {
+            bail!("Statistics level must be one of 'none', 'chunk' or 'page', not '{statistics}'.")
+        }
+    };
+    Ok((name, statistics))
+}
+
+/// Parses custom key value metadata, e.g. `source=crm`.
+pub fn key_value_from_str(source: &str) -> Result<(String, String), Error> {
+    let (key, value) = source
+        .split_once('=')
+        .filter(|(key, _value)| !key.is_empty())
+        .ok_or_else(|| anyhow!("Metadata must be passed in format: 'KEY=VALUE'"))?;
+    Ok((key.to_owned(), value.to_owned()))
+}
+
+/// Bloom filter for a column, parsed from `COLUMN[:FPP[:NDV]]`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BloomFilterArgument {
+    pub column: String,
+    /// False positive probability. Uses the parquet default if not specified.
+    pub fpp: Option<f64>,
+    /// Expected number of distinct values. Uses the parquet default if not specified.
+    pub ndv: Option<u64>,
+}
+
+/// Parses e.g. `customer_id`, `customer_id:0.01` or `customer_id:0.01:100000`. Like for the other
+/// column settings the values are split off at the last colon, so column names may contain colons,
+/// too. Trailing parts are regarded as values as long as they start with a digit or `.`.
+pub fn bloom_filter_from_str(source: &str) -> Result<BloomFilterArgument, Error> {
+    const FORMAT: &str = "COLUMN_NAME[:FPP[:NDV]]";
+    let mut column = source.to_owned();
+    let mut values = Vec::new();
+    while values.len() < 2 {
+        let Ok((name, value)) = split_column_setting(&column, FORMAT) else {
+            break;
+        };
+        if !value.starts_with(|c: char| c.is_ascii_digit() || c == '.') {
+            break;
+        }
+        values.insert(0, value.to_owned());
+        column = name;
+    }
+    if column.is_empty() {
+        bail!("Bloom filter must be passed in format: '{FORMAT}'")
+    }
+    let fpp = values
+        .first()
+        .map(|fpp| {
+            fpp.parse::<f64>()
+                .ok()
+                .filter(|fpp| *fpp > 0. && *fpp < 1.)
+                .ok_or_else(|| {
+                    anyhow!("False positive probability must be between 0 and 1, not '{fpp}'.")
+                })
+        })
+        .transpose()?;
+    let ndv = values
+        .get(1)
+        .map(|ndv| {
+            ndv.parse::<u64>()
+                .map_err(|_| anyhow!("Number of distinct values must be an integer, not '{ndv}'."))
+        })
+        .transpose()?;
+    Ok(BloomFilterArgument { column, fpp, ndv })
+}
+
+/// Unit used for all timestamp columns, regardless of their precision.
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum TimestampUnitArgument {
+    Millis,
+    Micros,
+    Nanos,
+}
+
+/// Maximum element length of text and binary columns, parsed from `LENGTH` or
+/// `COLUMN_NAME:LENGTH`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnLengthLimitArgument {
+    /// `None` for the global limit, which applies to all columns without a limit of their own.
+    pub column: Option<String>,
+    pub length: usize,
+}
+
+pub fn column_length_limit_from_str(source: &str) -> Result<ColumnLengthLimitArgument, Error> {
+    if let Ok(length) = source.parse() {
+        return Ok(ColumnLengthLimitArgument {
+            column: None,
+            length,
+        });
+    }
+    let (column, length) = split_column_setting(source, "[COLUMN_NAME:]LENGTH")?;
+    let length = length
+        .parse()
+        .map_err(|_| anyhow!("Column length limit must be an integer, not '{length}'."))?;
+    Ok(ColumnLengthLimitArgument {
+        column: Some(column),
+        length,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use parquet::basic::{Compression, ZstdLevel};
+
+    use super::{
+        bloom_filter_from_str, column_compression_from_str, column_dictionary_from_str,
+        column_length_limit_from_str, BloomFilterArgument, ColumnLengthLimitArgument,
+    };
+
+    #[test]
+    fn parse_column_length_limit() {
+        assert_eq!(
+            ColumnLengthLimitArgument {
+                column: None,
+                length: 4096
+            },
+            column_length_limit_from_str("4096").unwrap()
+        );
+        assert_eq!(
+            ColumnLengthLimitArgument {
+                column: Some("description".to_owned()),
+                length: 1_000_000
+            },
+            column_length_limit_from_str("description:1000000").unwrap()
+        );
+        assert!(column_length_limit_from_str("description:large").is_err());
+        assert!(column_length_limit_from_str("large").is_err());
+    }
+
+    #[test]
+    fn parse_bloom_filter() {
+        assert_eq!(
+            BloomFilterArgument {
+                column: "customer_id".to_owned(),
+                fpp: None,
+                ndv: None
+            },
+            bloom_filter_from_str("customer_id").unwrap()
+        );
+        assert_eq!(
+            BloomFilterArgument {
+                column: "customer_id".to_owned(),
+                fpp: Some(0.01),
+                ndv: Some(100_000)
+            },
+            bloom_filter_from_str("customer_id:0.01:100000").unwrap()
+        );
+        assert!(bloom_filter_from_str("customer_id:2").is_err());
+        assert!(bloom_filter_from_str("customer_id:0.01:1e5").is_err());
+        assert!(bloom_filter_from_str("").is_err());
+        assert!(bloom_filter_from_str(":0.01").is_err());
+    }
+
+    #[test]
+    fn parse_bloom_filter_for_column_name_with_colon() {
+        assert_eq!(
+            BloomFilterArgument {
+                column: "crm:customer_id".to_owned(),
+                fpp: None,
+                ndv: None
+            },
+            bloom_filter_from_str("crm:customer_id").unwrap()
+        );
+        assert_eq!(
+            BloomFilterArgument {
+                column: "crm:customer_id".to_owned(),
+                fpp: Some(0.01),
+                ndv: Some(1000)
+            },
+            bloom_filter_from_str("crm:customer_id:0.01:1000").unwrap()
+        );
+    }
+
+    #[test]
+    fn parse_column_compression() {
+        assert_eq!(
+            (
+                "description".to_owned(),
+                Compression::ZSTD(ZstdLevel::try_new(9).unwrap())
+            ),
+            column_compression_from_str("description:zstd(9)").unwrap()
+        );
+        assert_eq!(
+            ("a:b".to_owned(), Compression::SNAPPY),
+            column_compression_from_str("a:b:snappy").unwrap()
+        );
+        assert!(column_compression_from_str("id:zstd(9").is_err());
+        assert!(column_compression_from_str("id:unknown").is_err());
+        assert!(column_compression_from_str("zstd").is_err());
+    }
+
+    #[test]
+    fn parse_column_dictionary() {
+        assert_eq!(
+            ("id".to_owned(), false),
+            column_dictionary_from_str("id:off").unwrap()
+        );
+        assert!(column_dictionary_from_str("id:maybe").is_err());
+    }
+}
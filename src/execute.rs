@@ -26,7 +26,10 @@ pub fn execute(exec_opt: &ExecOpt) -> Result<(), Error> {
     let schema_desc = parquet_metadata.file_metadata().schema_descr();
 
     let mapping = IndexMapping::from_named_parameters(placeholder_names_by_position, schema_desc)?;
-    let statement = odbc_conn.prepare(&statement_text)?;
+    let mut statement = odbc_conn.prepare(&statement_text)?;
+    if let Some(timeout) = connect_opts.query_timeout_sec() {
+        statement.set_query_timeout_sec(timeout)?;
+    }
 
     let parquet_column_descs_in_order_of_column_bufs: Vec<_> = mapping
         .parquet_indices_in_order_of_column_buffers()
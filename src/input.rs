@@ -5,13 +5,16 @@ use std::{
     collections::HashMap,
     fs::File,
     io::Write,
+    iter::repeat_n,
     marker::PhantomData,
+    num::NonZeroUsize,
     ops::{Add, DivAssign, MulAssign},
 };
 
 use anyhow::{anyhow, bail, Error};
 use chrono::{DateTime, Datelike, Duration, NaiveDate, Timelike};
 use log::debug;
+use num_bigint::{BigInt, Sign};
 use num_traits::{FromPrimitive, PrimInt, Signed, ToPrimitive};
 use odbc_api::{
     buffers::{
@@ -20,11 +23,11 @@ use odbc_api::{
     },
     handles::StatementImpl,
     parameter::WithDataType,
-    sys::{Date, Timestamp},
+    sys::{Date, SqlDataType, Timestamp},
     BindParamDesc, Bit, ColumnarBulkInserter, InputParameterMapping, Utf16String,
 };
 use parquet::{
-    basic::{ConvertedType, Type as PhysicalType},
+    basic::{ConvertedType, LogicalType, Type as PhysicalType},
     column::reader::ColumnReader,
     data_type::{
         AsBytes, BoolType, ByteArrayType, DataType, DoubleType, FixedLenByteArrayType, FloatType,
@@ -34,7 +37,10 @@ use parquet::{
     schema::types::{ColumnDescriptor, SchemaDescriptor},
 };
 
-use crate::parquet_buffer::{BufferedDataType, ParquetBuffer};
+use crate::{
+    parquet_buffer::{BufferedDataType, ParquetBuffer},
+    query::UUID_TEXT_LENGTH,
+};
 
 /// Message we emmit if we hit a code path we expected to be unreachable.
 const BUG: &str = "This is not supposed to happen. Please open a Bug at \
@@ -130,8 +136,10 @@ impl IndexMapping {
         let num_columns = schema_desc.num_columns();
         let parquet_index_by_name: HashMap<String, usize> = (0..num_columns)
             .map(|index_pq| {
-                let desc = schema_desc.column(index_pq);
-                (desc.name().to_owned(), index_pq)
+                // Use the name of the root field, so lists are referenced by their name rather
+                // than the name of their leaf.
+                let root = schema_desc.get_column_root(index_pq);
+                (root.name().to_owned(), index_pq)
             })
             .collect();
 
@@ -184,6 +192,9 @@ pub fn parquet_type_to_odbc_param_desc(
 ) -> Result<(BindParamDesc, Box<FnParquetToOdbcCol>), Error> {
     // Column name. Used in error messages.
     let name = col_desc.self_type().name();
+    if col_desc.max_rep_level() > 0 {
+        return list_to_odbc_param_desc(col_desc, use_utf16);
+    }
     if !col_desc.self_type().is_primitive() {
         bail!(
             "Sorry, this tool is only able to insert primitive types. Column '{}' is not a \
@@ -418,16 +429,8 @@ pub fn parquet_type_to_odbc_param_desc(
                 ),
                 ConvertedType::DECIMAL => {
                     let precision: usize = col_desc.type_precision().try_into().unwrap();
-                    // 128 * log(2) = 38.~
-                    if precision > 38 {
-                        bail!(
-                            "Inserting decimals with more than 38 digits is currently not \
-                            supported. Please raise an issue at \
-                            https://github.com/pacman82/odbc2parquet/issues."
-                        )
-                    }
                     let scale: usize = col_desc.type_scale().try_into().unwrap();
-                    let param_desc = BindParamDesc::decimal_as_text(precision as u8, scale as i8);
+                    let param_desc = decimal_as_text_param_desc(precision, scale);
                     let BufferDesc::Text { max_str_len } = param_desc.buffer_desc else {
                         unreachable!()
                     };
@@ -435,9 +438,13 @@ pub fn parquet_type_to_odbc_param_desc(
                         param_desc,
                         ByteArrayType::map_to_text(
                             move |bytes, index, odbc_buf| {
-                                let n = i128_from_be_slice(bytes.as_bytes());
                                 let text = odbc_buf.set_mut(index, max_str_len);
-                                write_integer_as_decimal(n, precision, scale, text);
+                                write_twos_complement_as_decimal(
+                                    bytes.as_bytes(),
+                                    precision,
+                                    scale,
+                                    text,
+                                );
                                 Ok(())
                             },
                             nullable,
@@ -450,6 +457,18 @@ pub fn parquet_type_to_odbc_param_desc(
         PhysicalType::FIXED_LEN_BYTE_ARRAY => {
             let max_bytes = col_desc.type_length().try_into().unwrap();
             match lt {
+                // UUID has no converted type, so we need to look at the logical type to identify it.
+                ConvertedType::NONE if col_desc.logical_type_ref() == Some(&LogicalType::Uuid) => (
+                    uuid_as_text_param_desc(),
+                    FixedLenByteArrayType::map_to_text(
+                        |bytes, index, odbc_buf| {
+                            let text = odbc_buf.set_mut(index, UUID_TEXT_LENGTH);
+                            write_as_uuid(bytes.as_bytes(), text);
+                            Ok(())
+                        },
+                        nullable,
+                    ),
+                ),
                 ConvertedType::NONE => (
                     BindParamDesc::binary(max_bytes),
                     FixedLenByteArrayType::map_to_binary(
@@ -462,16 +481,8 @@ pub fn parquet_type_to_odbc_param_desc(
                 ),
                 ConvertedType::DECIMAL => {
                     let precision: usize = col_desc.type_precision().try_into().unwrap();
-                    // 128 * log(2) = 38.~
-                    if precision > 38 {
-                        bail!(
-                            "Inserting decimals with more than 38 digits is currently not \
-                            supported. Please raise an issue at \
-                            https://github.com/pacman82/odbc2parquet/issues."
-                        )
-                    }
                     let scale: usize = col_desc.type_scale().try_into().unwrap();
-                    let param_desc = BindParamDesc::decimal_as_text(precision as u8, scale as i8);
+                    let param_desc = decimal_as_text_param_desc(precision, scale);
                     let BufferDesc::Text { max_str_len } = param_desc.buffer_desc else {
                         unreachable!()
                     };
@@ -479,9 +490,13 @@ pub fn parquet_type_to_odbc_param_desc(
                         param_desc,
                         FixedLenByteArrayType::map_to_text(
                             move |bytes, index, odbc_buf| {
-                                let n = i128_from_be_slice(bytes.as_bytes());
                                 let text = odbc_buf.set_mut(index, max_str_len);
-                                write_integer_as_decimal(n, precision, scale, text);
+                                write_twos_complement_as_decimal(
+                                    bytes.as_bytes(),
+                                    precision,
+                                    scale,
+                                    text,
+                                );
                                 Ok(())
                             },
                             nullable,
@@ -501,6 +516,123 @@ pub fn parquet_type_to_odbc_param_desc(
     Ok((desc, parquet_to_odbc))
 }
 
+/// Lists, like the ones the query subcommand creates for PostgreSQL arrays, are inserted using the
+/// text representation of an array literal. E.g. `{1,NULL,3}`.
+fn list_to_odbc_param_desc(
+    col_desc: &ColumnDescriptor,
+    use_utf16: bool,
+) -> Result<(BindParamDesc, Box<FnParquetToOdbcCol>), Error> {
+    let path = col_desc.path().parts();
+    // Only the three level layout is supported: <list> (LIST) -> repeated list -> element
+    if col_desc.max_rep_level() != 1 || path.len() != 3 {
+        bail!(
+            "Sorry, this tool is only able to insert primitive types and lists of primitive types. \
+            Column '{}' is neither.",
+            path.join(".")
+        );
+    }
+    let max_def_level = col_desc.max_def_level();
+    let element_is_optional = col_desc.self_type().is_optional();
+
+    let to_odbc = match (col_desc.physical_type(), col_desc.converted_type()) {
+        (PhysicalType::BOOLEAN, _) => BoolType::map_list_to_text(
+            |&b, literal| literal.push_str(if b { "true" } else { "false" }),
+            max_def_level,
+            element_is_optional,
+            use_utf16,
+        ),
+        (
+            PhysicalType::INT32,
+            ConvertedType::NONE
+            | ConvertedType::INT_8
+            | ConvertedType::INT_16
+            | ConvertedType::INT_32,
+        ) => Int32Type::map_list_to_text(
+            |n, literal| literal.push_str(&n.to_string()),
+            max_def_level,
+            element_is_optional,
+            use_utf16,
+        ),
+        (PhysicalType::INT64, ConvertedType::NONE | ConvertedType::INT_64) => {
+            Int64Type::map_list_to_text(
+                |n, literal| literal.push_str(&n.to_string()),
+                max_def_level,
+                element_is_optional,
+                use_utf16,
+            )
+        }
+        (PhysicalType::FLOAT, _) => FloatType::map_list_to_text(
+            |n, literal| literal.push_str(&n.to_string()),
+            max_def_level,
+            element_is_optional,
+            use_utf16,
+        ),
+        (PhysicalType::DOUBLE, _) => DoubleType::map_list_to_text(
+            |n, literal| literal.push_str(&n.to_string()),
+            max_def_level,
+            element_is_optional,
+            use_utf16,
+        ),
+        (PhysicalType::BYTE_ARRAY, ConvertedType::UTF8) => ByteArrayType::map_list_to_text(
+            |text, literal| {
+                let text = text
+                    .as_utf8()
+                    .expect("Invalid UTF-8 sequence in parquet file.");
+                push_quoted_array_element(text, literal)
+            },
+            max_def_level,
+            element_is_optional,
+            use_utf16,
+        ),
+        (pt, lt) => bail!(
+            "Sorry, inserting lists with elements of type {pt:?} {lt:?} is not supported. Column \
+            '{}'.",
+            path[0]
+        ),
+    };
+    // Start small. We rebind the buffer as we encounter larger values in the file.
+    let max_str_len = 1;
+    let desc = if use_utf16 {
+        BindParamDesc::wide_text(max_str_len)
+    } else {
+        BindParamDesc::text(max_str_len)
+    };
+    Ok((desc, to_odbc))
+}
+
+/// Appends the array literal for `elements` to `literal`. `NULL` elements are written as unquoted
+/// `NULL`.
+fn write_array_literal<T>(
+    elements: &[Option<T>],
+    write_element: &impl Fn(&T, &mut String),
+    literal: &mut String,
+) {
+    literal.push('{');
+    for (index, element) in elements.iter().enumerate() {
+        if index != 0 {
+            literal.push(',');
+        }
+        match element {
+            Some(element) => write_element(element, literal),
+            None => literal.push_str("NULL"),
+        }
+    }
+    literal.push('}');
+}
+
+/// Text elements are always quoted, so we do not need to worry about separators, whitespace or
+/// elements spelling `NULL`.
+fn push_quoted_array_element(text: &str, literal: &mut String) {
+    literal.push('"');
+    for c in text.chars() {
+        if matches!(c, '"' | '\\') {
+            literal.push('\\');
+        }
+        literal.push(c);
+    }
+    literal.push('"');
+}
+
 /// We extend the parquet `DataType` to start of our builder pattern. These builders constructs the
 /// functors we use to transfer data from Parquet to ODBC.
 trait InpubBuilderStart: DataType + Sized {
@@ -678,6 +810,57 @@ trait InpubBuilderStart: DataType + Sized {
         }
     }
 
+    /// Transfers a column with a three level `LIST` layout as text, using `f` to append the
+    /// representation of each element to the array literal.
+    fn map_list_to_text<F>(
+        f: F,
+        max_def_level: i16,
+        element_is_optional: bool,
+        use_utf16: bool,
+    ) -> Box<FnParquetToOdbcCol>
+    where
+        F: Fn(&Self::T, &mut String) + 'static,
+        Self::T: BufferedDataType + Clone,
+    {
+        Box::new(
+            move |num_rows: usize,
+                  pb: &mut ParquetBuffer,
+                  column_reader: ColumnReader,
+                  column_writer: BoxColumBufferRefMut<'_>| {
+                let mut cr = Self::get_column_reader(column_reader).expect(BUG);
+                let lists = pb.read_list(&mut cr, num_rows, max_def_level, element_is_optional)?;
+                let mut literal = String::new();
+                if use_utf16 {
+                    let mut cw = WText::unwrap_writer_optional(column_writer);
+                    for (index, list) in lists.iter().enumerate() {
+                        if let Some(elements) = list {
+                            literal.clear();
+                            write_array_literal(elements, &f, &mut literal);
+                            let value = Utf16String::from_str(&literal);
+                            cw.ensure_max_element_length(value.len(), index)?;
+                            cw.set_cell(index, Some(value.as_slice()));
+                        } else {
+                            cw.set_cell(index, None);
+                        }
+                    }
+                } else {
+                    let mut cw = Text::unwrap_writer_optional(column_writer);
+                    for (index, list) in lists.iter().enumerate() {
+                        if let Some(elements) = list {
+                            literal.clear();
+                            write_array_literal(elements, &f, &mut literal);
+                            cw.ensure_max_element_length(literal.len(), index)?;
+                            cw.set_cell(index, Some(literal.as_bytes()));
+                        } else {
+                            cw.set_cell(index, None);
+                        }
+                    }
+                }
+                Ok(())
+            },
+        )
+    }
+
     fn map_to<Odt>() -> ParquetToOdbcBuilder<Self, Odt> {
         ParquetToOdbcBuilder {
             pdt: PhantomData,
@@ -792,6 +975,84 @@ fn write_as_time_us(mut microseconds_since_midnight: i64, mut text: &mut [u8]) {
     .unwrap()
 }
 
+/// UUIDs are bound as text, but we tell the driver the relational type is `SQL_GUID`. Some
+/// databases (e.g. PostgreSQL) would otherwise refuse to insert `VARCHAR` into a UUID column.
+fn uuid_as_text_param_desc() -> BindParamDesc {
+    BindParamDesc {
+        buffer_desc: BufferDesc::Text {
+            max_str_len: UUID_TEXT_LENGTH,
+        },
+        data_type: odbc_api::DataType::Other {
+            data_type: SqlDataType::EXT_GUID,
+            column_size: NonZeroUsize::new(UUID_TEXT_LENGTH),
+            decimal_digits: 0,
+        },
+    }
+}
+
+/// Writes the 16 bytes of a UUID in its canonical text representation.
+fn write_as_uuid(bytes: &[u8], mut text: &mut [u8]) {
+    for (index, byte) in bytes.iter().enumerate() {
+        if matches!(index, 4 | 6 | 8 | 10) {
+            write!(text, "-").unwrap();
+        }
+        write!(text, "{byte:02x}").unwrap();
+    }
+}
+
+/// Decimals stored as byte arrays are bound as text. Unlike [`BindParamDesc::decimal_as_text`] this
+/// is not limited to a precision of 255 digits (e.g. PostgreSQL `NUMERIC` allows up to 1000).
+fn decimal_as_text_param_desc(precision: usize, scale: usize) -> BindParamDesc {
+    // Precision digits + sign, plus radix character (`.`) if there is a fraction
+    let max_str_len = if scale == 0 {
+        precision + 1
+    } else {
+        precision + 2
+    };
+    BindParamDesc {
+        buffer_desc: BufferDesc::Text { max_str_len },
+        data_type: odbc_api::DataType::Decimal {
+            precision,
+            scale: scale.try_into().unwrap(),
+        },
+    }
+}
+
+/// Writes the text representation of a decimal stored as big endian two's complement. Decimals
+/// with more than 38 digits do not fit into an `i128` and are formatted as arbitrary precision
+/// integers.
+fn write_twos_complement_as_decimal(bytes: &[u8], precision: usize, scale: usize, text: &mut [u8]) {
+    // 128 * log(2) = 38.~
+    if precision <= 38 {
+        let n = i128_from_be_slice(bytes);
+        write_integer_as_decimal(n, precision, scale, text);
+    } else {
+        let n = BigInt::from_signed_bytes_be(bytes);
+        write_big_integer_as_decimal(&n, precision, scale, text);
+    }
+}
+
+/// Same as [`write_integer_as_decimal`], but for arbitrary precision integers.
+fn write_big_integer_as_decimal(n: &BigInt, precision: usize, scale: usize, text: &mut [u8]) {
+    text[0] = if n.sign() == Sign::Minus { b'-' } else { b'+' };
+    let digits = n.magnitude().to_str_radix(10);
+    let digits = digits.as_bytes();
+    // Like `write_integer_as_decimal` we only print the `precision` least significant digits.
+    let digits = &digits[digits.len().saturating_sub(precision)..];
+    let num_zeroes = precision - digits.len();
+    let mut digits = repeat_n(&b'0', num_zeroes).chain(digits);
+    // Number of digits + one decimal separator (`.`)
+    let str_len = if scale == 0 { precision } else { precision + 1 };
+    for index in 0..str_len {
+        // +1 offset to make space for sign character
+        text[index + 1] = if scale != 0 && index == precision - scale {
+            b'.'
+        } else {
+            *digits.next().unwrap()
+        };
+    }
+}
+
 fn write_integer_as_decimal<I>(mut n: I, precision: usize, scale: usize, text: &mut [u8])
 where
     I: PrimInt + FromPrimitive + DivAssign + ToPrimitive + Signed + MulAssign,
@@ -907,7 +1168,15 @@ impl_odbc_data_type!(Timestamp, Timestamp);
 
 #[cfg(test)]
 mod tests {
-    use super::{i128_from_be_slice, write_integer_as_decimal};
+    use std::sync::Arc;
+
+    use parquet::schema::{parser::parse_message_type, types::SchemaDescriptor};
+
+    use super::{
+        i128_from_be_slice, list_to_odbc_param_desc, push_quoted_array_element,
+        write_array_literal, write_as_uuid, write_integer_as_decimal,
+        write_twos_complement_as_decimal,
+    };
 
     #[test]
     fn format_i32_to_decimal() {
@@ -939,6 +1208,32 @@ mod tests {
         assert_eq!("-01234567.80", std::str::from_utf8(&out[..]).unwrap());
     }
 
+    #[test]
+    fn format_twos_complement_with_more_than_38_digits_to_decimal() {
+        // 10^40 + 1
+        let n: num_bigint::BigInt = "10000000000000000000000000000000000000001".parse().unwrap();
+        let bytes = n.to_signed_bytes_be();
+        let mut out = [0; 43];
+        write_twos_complement_as_decimal(&bytes, 41, 0, &mut out[..42]);
+        assert_eq!(
+            "+10000000000000000000000000000000000000001",
+            std::str::from_utf8(&out[..42]).unwrap()
+        );
+        write_twos_complement_as_decimal(&bytes, 41, 2, &mut out);
+        assert_eq!(
+            "+100000000000000000000000000000000000000.01",
+            std::str::from_utf8(&out).unwrap()
+        );
+
+        let bytes = (-n).to_signed_bytes_be();
+        let mut out = [0; 44];
+        write_twos_complement_as_decimal(&bytes, 42, 2, &mut out);
+        assert_eq!(
+            "-0100000000000000000000000000000000000000.01",
+            std::str::from_utf8(&out).unwrap()
+        );
+    }
+
     #[test]
     fn i128_from_bytes() {
         assert_eq!(
@@ -949,4 +1244,58 @@ mod tests {
         assert_eq!(-1, i128_from_be_slice(&[255u8; 16][..]));
         assert_eq!(-1, i128_from_be_slice(&[255u8][..]));
     }
+
+    #[test]
+    fn format_uuid() {
+        let bytes = [
+            0x6f, 0x96, 0x19, 0xff, 0x8b, 0x86, 0xd0, 0x11, 0xb4, 0x2d, 0x00, 0xc0, 0x4f, 0xc9,
+            0x64, 0xff,
+        ];
+        let mut out = [0; 36];
+        write_as_uuid(&bytes, &mut out);
+        assert_eq!(
+            "6f9619ff-8b86-d011-b42d-00c04fc964ff",
+            std::str::from_utf8(&out[..]).unwrap()
+        );
+    }
+
+    #[test]
+    fn format_array_literal() {
+        let mut literal = String::new();
+        write_array_literal(
+            &[Some(1), None, Some(3)],
+            &|n: &i32, literal: &mut String| literal.push_str(&n.to_string()),
+            &mut literal,
+        );
+        assert_eq!("{1,NULL,3}", literal);
+
+        let mut literal = String::new();
+        write_array_literal(
+            &[Some("a b"), Some(r#"c"d\"#), Some("NULL")],
+            &|text: &&str, literal: &mut String| push_quoted_array_element(text, literal),
+            &mut literal,
+        );
+        assert_eq!(r#"{"a b","c\"d\\","NULL"}"#, literal);
+    }
+
+    #[test]
+    fn reject_lists_of_dates() {
+        let schema = parse_message_type(
+            "message schema {
+                optional group a (LIST) {
+                    repeated group list {
+                        optional int32 element (DATE);
+                    }
+                }
+            }",
+        )
+        .unwrap();
+        let schema = SchemaDescriptor::new(Arc::new(schema));
+
+        let Err(error) = list_to_odbc_param_desc(&schema.column(0), false) else {
+            panic!("Lists of dates must not be inserted as integers.")
+        };
+
+        assert!(error.to_string().contains("not supported"));
+    }
 }
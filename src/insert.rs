@@ -29,9 +29,9 @@ pub fn insert(insert_opt: &InsertOpt) -> Result<(), Error> {
     let num_columns = schema_desc.num_columns();
 
     let column_descriptions: Vec<_> = (0..num_columns).map(|i| schema_desc.column(i)).collect();
-    let column_names: Vec<&str> = column_descriptions
-        .iter()
-        .map(|col_desc| col_desc.name())
+    // Name of the root field. Differs from the leaf name for lists.
+    let column_names: Vec<&str> = (0..num_columns)
+        .map(|i| schema_desc.get_column_root(i).name())
         .collect();
     let mut odbc_buf_desc = Vec::new();
     let mut copy_col_fns = Vec::new();
@@ -42,7 +42,10 @@ pub fn insert(insert_opt: &InsertOpt) -> Result<(), Error> {
         copy_col_fns.push(odbc_to_parquet);
     }
     let insert_statement = insert_statement_text(table, &column_names);
-    let statement = odbc_conn.prepare(&insert_statement)?;
+    let mut statement = odbc_conn.prepare(&insert_statement)?;
+    if let Some(timeout) = connect_opts.query_timeout_sec() {
+        statement.set_query_timeout_sec(timeout)?;
+    }
 
     let odbc_inserter = statement.into_column_inserter(1, odbc_buf_desc)?;
 
@@ -7,14 +7,23 @@ mod logging;
 mod parquet_buffer;
 mod query;
 
-use crate::enum_args::{column_encoding_from_str, EncodingArgument};
+use crate::enum_args::{
+    bloom_filter_from_str, column_compression_from_str, column_dictionary_from_str,
+    column_encoding_from_str, column_length_limit_from_str, column_statistics_from_str,
+    key_value_from_str, BloomFilterArgument, ColumnLengthLimitArgument, EncodingArgument,
+    OutputFormat, TimestampUnitArgument,
+};
 use anyhow::{bail, Error};
 use bytesize::ByteSize;
 use connection::ConnectOpts;
 use enum_args::CompressionVariants;
 use io_arg::IoArg;
 use odbc_api::environment;
-use parquet::basic::Encoding;
+use parquet::{
+    basic::{Compression, Encoding},
+    file::properties::EnabledStatistics,
+};
+use query::ColumnLengthLimits;
 use std::path::PathBuf;
 
 use clap::{ArgAction, Args, CommandFactory, Parser};
@@ -51,7 +60,7 @@ enum Command {
     /// Query a data source and write the result as parquet.
     Query {
         #[clap(flatten)]
-        query_opt: QueryOpt,
+        query_opt: Box<QueryOpt>,
     },
     /// List available drivers and their attributes.
     ListDrivers,
@@ -75,9 +84,14 @@ enum Command {
         #[clap(flatten)]
         exec_opt: ExecOpt,
     },
+    /// Write each table of the data source into its own file, using a single connection.
+    Dump {
+        #[clap(flatten)]
+        dump_opt: Box<DumpOpt>,
+    },
 }
 
-#[derive(Args)]
+#[derive(Args, Clone)]
 pub struct QueryOpt {
     #[clap(flatten)]
     connect_opts: ConnectOpts,
@@ -154,8 +168,33 @@ pub struct QueryOpt {
     /// letters do I expect in this column, rather than to care about whether the command is
     /// executed on Linux or Windows. The encoding of the column on the Database does not matter for
     /// this setting or determining buffer sizes.
-    #[arg(long, default_value = "4096")]
-    column_length_limit: usize,
+    ///
+    /// Pass `COLUMN:LENGTH` to set the limit of an individual column, e.g. `description:1000000`.
+    /// The option can be repeated. Columns without a limit of their own use the global one, which
+    /// is 4096 unless given without a column name.
+    #[arg(long, value_parser=column_length_limit_from_str, action = ArgAction::Append)]
+    column_length_limit: Vec<ColumnLengthLimitArgument>,
+    /// Text and binary columns, whose maximum length is unknown or larger than this, are fetched
+    /// row by row with `SQLGetData` instead of a bound fetch buffer. Their values are retrieved in
+    /// chunks, so they are never truncated, no matter how large, e.g. documents in `VARCHAR(MAX)`
+    /// or `BLOB` columns. The length is in characters for text and in bytes for binary columns.
+    /// Drivers can not mix bound buffers and `SQLGetData`, so once any column exceeds the
+    /// threshold, all columns of the result set are fetched row by row, including small ones like
+    /// integers. This costs a lot of throughput compared to fetching in bulk and disables
+    /// concurrent fetching, so pick a threshold only the truly large columns exceed. Batches still
+    /// respect the batch size limits.
+    #[arg(long)]
+    lob_threshold: Option<usize>,
+    /// Format of the output. `csv` writes comma separated values with a header line, `jsonl` one
+    /// JSON object per row. Text formats render the same converted values written into parquet
+    /// files, e.g. timestamps with time zone in UTC and Postgres arrays as lists. Batch sizes and
+    /// file splitting work the same as for parquet. Options specific to parquet, like
+    /// compression, encodings, bloom filters or metadata, are ignored for text formats. `arrow`
+    /// writes Arrow IPC, using the file format (Feather V2) for files and the stream format for
+    /// standard out. Each batch becomes one record batch, with the same column types the Arrow
+    /// schema hint of parquet files (see `--arrow-schema`) would describe.
+    #[arg(long, value_enum, default_value = "parquet")]
+    format: OutputFormat,
     /// Default compression used by the parquet file writer.
     #[arg(long, value_enum, default_value = "zstd")]
     column_compression_default: CompressionVariants,
@@ -194,6 +233,62 @@ pub struct QueryOpt {
         action = ArgAction::Append
     )]
     parquet_column_encoding: Vec<(String, Encoding)>,
+    /// Overwrite the `--column-compression-default` for individual columns. You can pass multiple
+    /// values in format `COLUMN:COMPRESSION` or `COLUMN:COMPRESSION(LEVEL)`. E.g.
+    /// `description:zstd(9)` or `id:snappy`.
+    #[arg(
+        long,
+        value_parser=column_compression_from_str,
+        action = ArgAction::Append
+    )]
+    parquet_column_compression: Vec<(String, Compression)>,
+    /// Enable or disable dictionary encoding for individual columns. You can pass multiple values
+    /// in format `COLUMN:on` or `COLUMN:off`. Dictionary encoding is enabled by default.
+    #[arg(
+        long,
+        value_parser=column_dictionary_from_str,
+        action = ArgAction::Append
+    )]
+    parquet_column_dictionary: Vec<(String, bool)>,
+    /// Level of statistics written for individual columns. You can pass multiple values in format
+    /// `COLUMN:LEVEL`. `LEVEL` must be one of: `none`, `chunk` (statistics for each column chunk)
+    /// or `page` (statistics for each column chunk and page). Default is `page`.
+    #[arg(
+        long,
+        value_parser=column_statistics_from_str,
+        action = ArgAction::Append
+    )]
+    parquet_column_statistics: Vec<(String, EnabledStatistics)>,
+    /// Write a bloom filter for a column, allowing readers to skip row groups which do not
+    /// contain a value they look for. You can pass multiple values in format
+    /// `COLUMN[:FPP[:NDV]]`. `FPP` is the false positive probability (default 0.05) and `NDV` the
+    /// maximum expected number of distinct values in a row group (default 1048576). The filter is
+    /// sized for `NDV` values and shrunk after the row group has been written. E.g.
+    /// `--bloom-filter customer_id:0.01`. Column names may contain colons, as long as the part
+    /// after the last colon does not start with a digit.
+    #[arg(
+        long,
+        value_parser=bloom_filter_from_str,
+        action = ArgAction::Append
+    )]
+    bloom_filter: Vec<BloomFilterArgument>,
+    /// Custom key value metadata written into the footer of each output file in format
+    /// `KEY=VALUE`. Can be passed multiple times. In addition to these, each file contains the
+    /// query text, its parameters, the name of the database management system, the version of
+    /// odbc2parquet, start and end time of the extraction and the number of rows in the file. The
+    /// keys of these are prefixed with `odbc2parquet.`.
+    #[arg(
+        long,
+        value_parser=key_value_from_str,
+        action = ArgAction::Append
+    )]
+    metadata: Vec<(String, String)>,
+    /// Store an Arrow schema (`ARROW:schema`) in the key value metadata of each output file. Arrow
+    /// based readers like pyarrow or polars use it to recover the exact types of the columns. E.g.
+    /// without it they widen 8 and 16 Bit integers to 32 Bit, and read timestamps with time zone
+    /// without their UTC semantics.
+    #[clap(long)]
+    arrow_schema: bool,
     /// Tells the odbc2parquet, that the ODBC driver does not support binding 64-Bit integers (aka
     /// S_C_BIGINT in ODBC speak). This will cause the odbc2parquet to query large integers as text
     /// instead and convert them to 64-Bit integers itself. Setting this flag will not affect the
@@ -208,6 +303,19 @@ pub struct QueryOpt {
     /// as text.
     #[clap(long)]
     avoid_decimal: bool,
+    /// Unit of all timestamp columns in the output. By default the unit is chosen based on the
+    /// precision reported by the driver, i.e. milliseconds for up to three fractional digits,
+    /// microseconds for up to six and nanoseconds above. Some readers, like older versions of
+    /// Spark, do not support nanoseconds. Units set for individual columns with
+    /// `--schema-overrides` take precedence.
+    #[arg(long, value_enum, conflicts_with = "timestamp_int96")]
+    timestamp_unit: Option<TimestampUnitArgument>,
+    /// Write timestamps using the deprecated `INT96` physical type with nanoseconds precision.
+    /// Required by older versions of Spark, Hive and Impala. Columns are not annotated with a
+    /// logical type, so readers can not tell naive timestamps from those with time zone. The
+    /// Arrow output does not support this.
+    #[clap(long)]
+    timestamp_int96: bool,
     /// In case fetch results gets split into multiple files a suffix with a number will be appended
     /// to each file name. Default suffix length is 2 leading to suffixes like e.g. `_03`. In case
     /// you would expect thousands of files in your output you may want to set this to say `4` so
@@ -220,7 +328,107 @@ pub struct QueryOpt {
     /// result set is empty you can set this flag.
     #[clap(long)]
     no_empty_file: bool,
-    /// Name of the output parquet file. Use `-` to indicate that the output should be written to
+    /// Write a JSON manifest listing each produced file with its number of rows, size in bytes,
+    /// number of row groups and SHA-256 checksum, as well as the totals over all files. It is
+    /// written only after all files have been completed successfully, so its presence marks a
+    /// complete export.
+    #[arg(long)]
+    manifest: Option<PathBuf>,
+    /// Write a hive style partitioned dataset, instead of a single file. Takes a comma separated
+    /// list of column names, e.g. `--partition-by region,year`. In this case `output` is the
+    /// directory of the dataset. Rows are routed into files like
+    /// `output/region=EU/year=2024/part-01.parquet` depending on the values of the partition
+    /// columns. The partition columns themselves are not written into the files, as their values
+    /// are encoded in the directory names. `NULL` values are routed into the partition
+    /// `__HIVE_DEFAULT_PARTITION__`. File size limits are applied to each partition separately.
+    #[arg(long, value_delimiter = ',')]
+    partition_by: Vec<String>,
+    /// Extract the result in parallel over several connections. Takes the name of an integer, date
+    /// or timestamp column of the result set. Its minimum and maximum values are queried first,
+    /// then the query is split into `--parallelism` sub-queries, each fetching a range of the
+    /// values of this column. Each sub-query is executed on its own connection and writes its own
+    /// numbered output file, e.g. `out_01.par`, `out_02.par`, ... Rows with `NULL` in the split
+    /// column are written into the first file. Ranges have equal width, so evenly distributed
+    /// values (like an auto incremented id) lead to evenly sized files. The column name is quoted,
+    /// so it must match the case of the column in the result set. The query is wrapped in a
+    /// derived table, so Microsoft SQL Server rejects queries with `ORDER BY` (unless combined
+    /// with `TOP` or `OFFSET`).
+    #[arg(long, conflicts_with = "partition_by")]
+    split_by: Option<String>,
+    /// Maximum number of sub-queries, and therefore connections, used in case `--split-by` is
+    /// specified.
+    #[arg(long, default_value = "4", value_parser = clap::value_parser!(u16).range(1..))]
+    parallelism: u16,
+    /// Only fetch rows which are new since the last successful run. Takes the name of a column of
+    /// the result set, which grows with each new row, e.g. a timestamp of the last update or an
+    /// auto incremented id. After a successful run, its maximum value is persisted in the
+    /// `--state-file`. Subsequent runs only fetch rows with values larger than this watermark. The
+    /// watermark is bound as an additional parameter after all positional parameters. The column
+    /// name is quoted, so it must match the case of the column in the result set. The query is
+    /// wrapped in a derived table, so Microsoft SQL Server rejects queries with `ORDER BY` (unless
+    /// combined with `TOP` or `OFFSET`).
+    #[arg(long, requires = "state_file")]
+    incremental_column: Option<String>,
+    /// JSON file remembering the watermark of `--incremental-column` between runs. If it does not
+    /// exist, all rows are fetched. It is only updated after the output has been written
+    /// completely, so a failed run is repeated the next time.
+    #[arg(long, requires = "incremental_column")]
+    state_file: Option<PathBuf>,
+    /// Write every result set returned by the statement, e.g. by a stored procedure or a batch of
+    /// several `SELECT` statements. Each result set is written to its own numbered file, e.g.
+    /// `out_01.par`, `out_02.par`, ... Result sets without columns (like row counts) are skipped.
+    /// Without this flag only the first result set is written.
+    #[arg(long, conflicts_with_all = ["split_by", "incremental_column"])]
+    all_result_sets: bool,
+    /// Parquet file with one row of parameters per execution. The query is prepared once and then
+    /// executed for each row. The results of all executions are appended to the same output. Like
+    /// for `exec`, placeholders can name the column bound to them, e.g. `?customer_id?`. If they
+    /// do not, the columns of the file are bound to the placeholders in order. Rows of
+    /// consecutive executions are combined into batches of up to the batch size, so each row
+    /// group may hold the results of many executions.
+    #[arg(
+        long,
+        conflicts_with_all = [
+            "parameters",
+            "split_by",
+            "all_result_sets",
+            "incremental_column",
+            "dry_run",
+        ]
+    )]
+    parameters_from: Option<PathBuf>,
+    /// Number of times the export is restarted, if it fails with a transient error. These are
+    /// connection failures (SQLSTATE class `08`) and timeouts (`HYT00`, `HYT01`). Each attempt
+    /// establishes a new connection and writes the output from scratch. Files completed by a
+    /// failed attempt are overwritten by the next one, as it writes files with the same names.
+    /// Should the next attempt write fewer files, e.g. because rows have been deleted in the
+    /// meantime, the surplus files of the failed attempt are left in place. Output to stdout can
+    /// not be retried.
+    #[arg(long, default_value = "0")]
+    retries: u32,
+    /// Seconds to wait before the first retry. The time waited doubles with each further attempt.
+    #[arg(long, default_value = "1")]
+    retry_backoff: u64,
+    /// TOML file pinning the output types of individual columns, rather than inferring them from
+    /// the types reported by the ODBC driver. Useful if the driver misreports a type, e.g. a
+    /// `NUMERIC` without precision or a `VARCHAR(0)`. Each table is named after a column of the
+    /// result set and may contain the keys: `type` (one of `boolean`, `int8`, `int16`, `int32`,
+    /// `int64`, `float`, `double`, `decimal`, `date`, `time`, `timestamp`, `text`, `binary` or
+    /// `uuid`), `nullable`, `length` (text and binary), `precision` and `scale` (decimal) or `unit`
+    /// (`ms`, `us` or `ns` for time and timestamp). E.g.:
+    ///
+    /// [amount]
+    /// type = "decimal"
+    /// precision = 10
+    /// scale = 2
+    #[arg(long)]
+    schema_overrides: Option<PathBuf>,
+    /// Only prepare the query and print the inferred parquet schema, the ODBC buffer used to fetch
+    /// each column, the memory required per row and the resulting batch size. No data is fetched
+    /// and no output is written. Useful to diagnose memory or type mapping problems.
+    #[arg(long)]
+    dry_run: bool,
+    /// Name of the output file. Use `-` to indicate that the output should be written to
     /// standard out instead. This option does nothing if the output is written to standard out.
     output: IoArg,
     /// Query executed against the ODBC data source. Question marks (`?`) can be used as
@@ -230,10 +438,91 @@ pub struct QueryOpt {
     /// considered the query.
     query: String,
     /// For each placeholder question mark (`?`) in the query text one parameter must be passed at
-    /// the end of the command line.
+    /// the end of the command line. Parameters are bound as text, unless prefixed with a type:
+    /// `int:42`, `float:0.5`, `decimal:12.50`, `date:2024-01-01` or `ts:2024-01-01 12:30:00.5`.
+    /// `null` binds `NULL`. Use `text:` to bind text which would otherwise be interpreted as one of
+    /// these, e.g. `text:null`.
     parameters: Vec<String>,
 }
 
+#[derive(Args)]
+pub struct DumpOpt {
+    #[clap(flatten)]
+    connect_opts: ConnectOpts,
+    /// Search pattern for the catalog names of the tables to dump. Default is to dump tables of
+    /// any catalog. An empty string (`--catalog ""`) selects tables without a catalog.
+    #[arg(long)]
+    catalog: Option<String>,
+    /// Search pattern for the schema names of the tables to dump. `%` matches any sequence of
+    /// characters, `_` any single character. E.g. `--schema dbo`. Default is to dump tables of any
+    /// schema. An empty string (`--schema ""`) selects tables without a schema.
+    #[arg(long)]
+    schema: Option<String>,
+    /// Search pattern for the names of the tables to dump. `%` matches any sequence of characters,
+    /// `_` any single character. E.g. `--table sales_%`. Default is to dump all tables.
+    #[arg(long, default_value = "%")]
+    table: String,
+    /// Comma separated list of table types to dump, e.g. `TABLE,VIEW`. Which types exist depends
+    /// on the data source.
+    #[arg(long, default_value = "TABLE")]
+    table_type: String,
+    /// Size of a single batch in rows. See `query --batch-size-row`.
+    #[arg(long)]
+    batch_size_row: Option<usize>,
+    /// Limits the size of a single batch in memory. See `query --batch-size-memory`.
+    #[arg(long)]
+    batch_size_memory: Option<ByteSize>,
+    /// Only allocate one fetch buffer, trading speed for memory. See `query
+    /// --sequential-fetching`.
+    #[arg(long)]
+    sequential_fetching: bool,
+    /// Limit of the transfer buffer size for an individual variadic sized column. See `query
+    /// --column-length-limit`.
+    #[arg(long, default_value_t = ColumnLengthLimits::DEFAULT)]
+    column_length_limit: usize,
+    /// Fetch text and binary columns larger than this row by row. See `query --lob-threshold`.
+    #[arg(long)]
+    lob_threshold: Option<usize>,
+    /// Format of the output files. The file extension is chosen accordingly, e.g.
+    /// `dbo.Sales.csv`.
+    #[arg(long, value_enum, default_value = "parquet")]
+    format: OutputFormat,
+    /// Default compression used by the parquet file writer.
+    #[arg(long, value_enum, default_value = "zstd")]
+    column_compression_default: CompressionVariants,
+    /// Explicit compression level for the `gzip`, `zstd` and `brotli` compression variants.
+    #[arg(long)]
+    column_compression_level_default: Option<u32>,
+    /// Encoding used for character data requested from the data source. See `query --encoding`.
+    #[arg(long, value_enum, default_value = "Auto", ignore_case = true)]
+    encoding: EncodingArgument,
+    /// Map `BINARY` SQL columns to `BYTE_ARRAY` instead of `FIXED_LEN_BYTE_ARRAY`.
+    #[clap(long)]
+    prefer_varbinary: bool,
+    /// Query large integers as text, for drivers which do not support binding 64-Bit integers.
+    #[clap(long)]
+    driver_does_not_support_64bit_integers: bool,
+    /// Avoid the logical type DECIMAL in the produced output. See `query --avoid-decimal`.
+    #[clap(long)]
+    avoid_decimal: bool,
+    /// Unit of all timestamp columns in the output. See `query --timestamp-unit`.
+    #[arg(long, value_enum, conflicts_with = "timestamp_int96")]
+    timestamp_unit: Option<TimestampUnitArgument>,
+    /// Write timestamps using the deprecated `INT96` physical type. See `query --timestamp-int96`.
+    #[clap(long)]
+    timestamp_int96: bool,
+    /// Store an Arrow schema (`ARROW:schema`) in the key value metadata of each output file.
+    #[clap(long)]
+    arrow_schema: bool,
+    /// Do not create a file for tables without rows.
+    #[clap(long)]
+    no_empty_file: bool,
+    /// Directory the files are written into. It is created if it does not exist. Each table is
+    /// written to `<catalog>.<schema>.<table>.parquet`. Parts the data source does not report are
+    /// left out, e.g. `<catalog>.<table>.parquet` for data sources without schemas.
+    output_dir: PathBuf,
+}
+
 #[derive(Args)]
 pub struct InsertOpt {
     #[clap(flatten)]
@@ -297,6 +586,9 @@ impl Cli {
     /// clap.
     pub fn perform_extra_validation(&self) -> Result<(), Error> {
         if let Command::Query { query_opt } = &self.command {
+            if query_opt.format != OutputFormat::Parquet && !query_opt.partition_by.is_empty() {
+                bail!("partition-by is only supported for the parquet format.")
+            }
             if !query_opt.output.is_file() {
                 if query_opt.file_size_threshold.is_some() {
                     bail!("file-size-threshold conflicts with specifying stdout ('-') as output.")
@@ -304,6 +596,21 @@ impl Cli {
                 if query_opt.row_groups_per_file != 0 {
                     bail!("row-groups-per-file conflicts with specifying stdout ('-') as output.")
                 }
+                if !query_opt.partition_by.is_empty() {
+                    bail!("partition-by conflicts with specifying stdout ('-') as output.")
+                }
+                if query_opt.split_by.is_some() {
+                    bail!("split-by conflicts with specifying stdout ('-') as output.")
+                }
+                if query_opt.all_result_sets {
+                    bail!("all-result-sets conflicts with specifying stdout ('-') as output.")
+                }
+                if query_opt.manifest.is_some() {
+                    bail!("manifest conflicts with specifying stdout ('-') as output.")
+                }
+                if query_opt.retries != 0 {
+                    bail!("retries conflicts with specifying stdout ('-') as output.")
+                }
             }
         }
         Ok(())
@@ -333,7 +640,7 @@ fn main() -> Result<(), Error> {
 
     match opt.command {
         Command::Query { query_opt } => {
-            query::query(query_opt)?;
+            query::query(*query_opt)?;
         }
         Command::Insert { insert_opt } => {
             insert::insert(&insert_opt)?;
@@ -368,6 +675,9 @@ fn main() -> Result<(), Error> {
         Command::Exec { exec_opt } => {
             execute::execute(&exec_opt)?;
         }
+        Command::Dump { dump_opt } => {
+            query::dump(*dump_opt)?;
+        }
     }
 
     Ok(())
@@ -1,21 +1,31 @@
-use anyhow::Error;
+use anyhow::{anyhow, bail, Error};
 use parquet::{
     column::{reader::ColumnReaderImpl, writer::ColumnWriterImpl},
-    data_type::{ByteArray, DataType, FixedLenByteArray, FixedLenByteArrayType},
+    data_type::{ByteArray, DataType, FixedLenByteArray, FixedLenByteArrayType, Int96},
 };
-use std::mem::size_of;
+use std::mem::{size_of, take};
+
+/// A list which may be `NULL`, containing elements which may be `NULL`.
+pub type OptionalList<T> = Option<Vec<Option<T>>>;
 
 /// Holds preallocated buffers for every possible physical parquet type. This way we do not need to
 /// reallocate them.
 pub struct ParquetBuffer {
     pub values_i32: Vec<i32>,
     pub values_i64: Vec<i64>,
+    pub values_i96: Vec<Int96>,
     pub values_f32: Vec<f32>,
     pub values_f64: Vec<f64>,
     pub values_bytes_array: Vec<ByteArray>,
     pub values_fixed_bytes_array: Vec<FixedLenByteArray>,
     pub values_bool: Vec<bool>,
     pub def_levels: Vec<i16>,
+    /// Repetition levels are only needed for list columns. Contrary to the other buffers, its
+    /// length is not tied to the number of rows.
+    pub rep_levels: Vec<i16>,
+    /// Sorted indices of the rows of the current batch, which are written into parquet. `None` if
+    /// all rows are written. Allows routing the rows of one batch into different partitions.
+    row_selection: Option<Vec<usize>>,
 }
 
 impl ParquetBuffer {
@@ -23,6 +33,7 @@ impl ParquetBuffer {
     /// estimate good batch sizes.
     pub const MEMORY_USAGE_BYTES_PER_ROW: usize = size_of::<i32>()
         + size_of::<i64>()
+        + size_of::<Int96>()
         + size_of::<f32>()
         + size_of::<f64>()
         + size_of::<ByteArray>()
@@ -34,19 +45,34 @@ impl ParquetBuffer {
         ParquetBuffer {
             values_i32: Vec::with_capacity(batch_size),
             values_i64: Vec::with_capacity(batch_size),
+            values_i96: Vec::with_capacity(batch_size),
             values_f32: Vec::with_capacity(batch_size),
             values_f64: Vec::with_capacity(batch_size),
             values_bytes_array: Vec::with_capacity(batch_size),
             values_fixed_bytes_array: Vec::with_capacity(batch_size),
             values_bool: Vec::with_capacity(batch_size),
             def_levels: Vec::with_capacity(batch_size),
+            rep_levels: Vec::new(),
+            row_selection: None,
         }
     }
 
     pub fn set_num_rows_fetched(&mut self, num_rows: usize) {
+        self.row_selection = None;
+        self.resize(num_rows);
+    }
+
+    /// Only write the rows with the given indices of the current batch. Indices must be sorted.
+    pub fn select_rows(&mut self, rows: Vec<usize>) {
+        self.resize(rows.len());
+        self.row_selection = Some(rows);
+    }
+
+    fn resize(&mut self, num_rows: usize) {
         self.def_levels.resize(num_rows, 0);
         self.values_i32.resize(num_rows, 0);
         self.values_i64.resize(num_rows, 0);
+        self.values_i96.resize(num_rows, Int96::new());
         self.values_f32.resize(num_rows, 0.);
         self.values_f64.resize(num_rows, 0.);
         self.values_bytes_array.resize(num_rows, ByteArray::new());
@@ -80,6 +106,7 @@ impl ParquetBuffer {
         T: DataType,
         T::T: BufferedDataType,
     {
+        let source = self.selected(source);
         let (values, def_levels) = T::T::mut_buf(self);
         let mut values_index = 0;
         for (item, definition_level) in source.zip(&mut def_levels.iter_mut()) {
@@ -95,6 +122,46 @@ impl ParquetBuffer {
         Ok(())
     }
 
+    /// Write values of a required column. The values are passed through to the column writer
+    /// without copying them, unless only some of the rows are selected.
+    pub fn write_required<T>(
+        &mut self,
+        cw: &mut ColumnWriterImpl<T>,
+        source: &[T::T],
+    ) -> Result<(), Error>
+    where
+        T: DataType,
+        T::T: BufferedDataType + Clone,
+    {
+        if self.row_selection.is_none() {
+            cw.write_batch(source, None, None)?;
+            return Ok(());
+        }
+        let source = self.selected(source.iter());
+        let (values, _def_levels) = T::T::mut_buf(self);
+        values.clear();
+        values.extend(source.cloned());
+        cw.write_batch(values, None, None)?;
+        Ok(())
+    }
+
+    /// Filters the items of a batch down to the selected rows.
+    fn selected<I>(&self, source: I) -> impl Iterator<Item = I::Item>
+    where
+        I: Iterator,
+    {
+        let mut selection = self
+            .row_selection
+            .clone()
+            .map(|rows| rows.into_iter().peekable());
+        source
+            .enumerate()
+            .filter_map(move |(row, item)| match selection.as_mut() {
+                None => Some(item),
+                Some(rows) => rows.next_if_eq(&row).map(|_| item),
+            })
+    }
+
     /// Write to a parquet buffer using an iterator over optional source items. A default
     /// transformation, defined via the `IntoPhysical` trait is used to transform the items into
     /// buffer elements.
@@ -125,6 +192,127 @@ impl ParquetBuffer {
         self.write_optional_any_fallible(cw, source.map(Ok), |s| s)
     }
 
+    /// Write a column of lists, e.g. a database array, into a parquet column with a three level
+    /// `LIST` layout. The outer option indicates a `NULL` list, the inner options `NULL` elements.
+    ///
+    /// * `is_optional`: `true` if the list itself (rather than its elements) may be `NULL`.
+    pub fn write_list<T>(
+        &mut self,
+        cw: &mut ColumnWriterImpl<T>,
+        source: impl Iterator<Item = Result<OptionalList<T::T>, Error>>,
+        is_optional: bool,
+    ) -> Result<(), Error>
+    where
+        T: DataType,
+        T::T: BufferedDataType + Default + Clone,
+    {
+        // Definition levels: Each optional ancestor of a value and the repeated `list` group add
+        // one level.
+        let empty_list = is_optional as i16;
+        let null_element = empty_list + 1;
+        let element = null_element + 1;
+
+        let source = self.selected(source);
+        let mut rep_levels = take(&mut self.rep_levels);
+        rep_levels.clear();
+        let (values, def_levels) = T::T::mut_buf(self);
+        let num_rows = def_levels.len();
+        values.clear();
+        def_levels.clear();
+        for item in source {
+            match item? {
+                None => {
+                    if !is_optional {
+                        bail!("Found NULL in a list column which is not nullable.");
+                    }
+                    def_levels.push(0);
+                    rep_levels.push(0);
+                }
+                Some(list) if list.is_empty() => {
+                    def_levels.push(empty_list);
+                    rep_levels.push(0);
+                }
+                Some(list) => {
+                    for (index, value) in list.into_iter().enumerate() {
+                        if let Some(value) = value {
+                            values.push(value);
+                            def_levels.push(element);
+                        } else {
+                            def_levels.push(null_element);
+                        }
+                        rep_levels.push(if index == 0 { 0 } else { 1 });
+                    }
+                }
+            }
+        }
+        cw.write_batch(values, Some(def_levels), Some(&rep_levels))?;
+        // Other columns rely on values and definition levels having one element per row.
+        values.resize(num_rows, T::T::default());
+        def_levels.resize(num_rows, 0);
+        self.rep_levels = rep_levels;
+        Ok(())
+    }
+
+    /// Read `num_rows` lists from a column with a three level `LIST` layout. Counterpart to
+    /// [`Self::write_list`].
+    ///
+    /// * `max_def_level`: Maximum definition level of the leaf column. Together with the
+    ///   repetition of the leaf it tells us whether the list itself is optional.
+    /// * `element_is_optional`: `true` if the leaf (i.e. the elements of the list) may be `NULL`.
+    pub fn read_list<T>(
+        &mut self,
+        cr: &mut ColumnReaderImpl<T>,
+        num_rows: usize,
+        max_def_level: i16,
+        element_is_optional: bool,
+    ) -> Result<Vec<OptionalList<T::T>>, Error>
+    where
+        T: DataType,
+        T::T: BufferedDataType + Clone,
+    {
+        let element = max_def_level;
+        let null_element = if element_is_optional {
+            element - 1
+        } else {
+            // There is no such level, if elements are required.
+            -1
+        };
+        let empty_list = element - 1 - element_is_optional as i16;
+
+        let mut rep_levels = take(&mut self.rep_levels);
+        rep_levels.clear();
+        let (values, def_levels) = T::T::mut_buf(self);
+        values.clear();
+        def_levels.clear();
+        cr.read_records(num_rows, Some(def_levels), Some(&mut rep_levels), values)?;
+
+        let mut lists: Vec<OptionalList<T::T>> = Vec::with_capacity(num_rows);
+        let mut values_it = values.iter();
+        for (&def, &rep) in def_levels.iter().zip(&rep_levels) {
+            if rep == 0 {
+                if def < empty_list {
+                    lists.push(None);
+                    continue;
+                }
+                lists.push(Some(Vec::new()));
+            }
+            let list = lists
+                .last_mut()
+                .and_then(Option::as_mut)
+                .ok_or_else(|| anyhow!("Invalid repetition levels in list column."))?;
+            if def == element {
+                let value = values_it
+                    .next()
+                    .expect("Values must match definition levels");
+                list.push(Some(value.clone()));
+            } else if def == null_element {
+                list.push(None);
+            }
+        }
+        self.rep_levels = rep_levels;
+        Ok(lists)
+    }
+
     /// Iterate over the elements of a column reader over an optional column.
     ///
     /// Be careful with calling this method on required columns as the bound definition buffer will
@@ -198,6 +386,12 @@ impl BufferedDataType for i64 {
     }
 }
 
+impl BufferedDataType for Int96 {
+    fn mut_buf(buffer: &mut ParquetBuffer) -> (&mut Vec<Self>, &mut Vec<i16>) {
+        (&mut buffer.values_i96, &mut buffer.def_levels)
+    }
+}
+
 impl BufferedDataType for f32 {
     fn mut_buf(buffer: &mut ParquetBuffer) -> (&mut Vec<Self>, &mut Vec<i16>) {
         (&mut buffer.values_f32, &mut buffer.def_levels)
@@ -231,11 +425,113 @@ impl BufferedDataType for FixedLenByteArray {
 #[cfg(test)]
 mod test {
 
+    use std::sync::Arc;
+
+    use bytes::Bytes;
+    use parquet::{
+        column::reader::ColumnReader,
+        data_type::Int32Type,
+        file::{
+            reader::{FileReader, SerializedFileReader},
+            writer::SerializedFileWriter,
+        },
+        schema::parser::parse_message_type,
+    };
+
     use super::ParquetBuffer;
 
     #[test]
     #[cfg(target_pointer_width = "64")] // Memory usage is platform dependent
     fn memory_usage() {
-        assert_eq!(59, ParquetBuffer::MEMORY_USAGE_BYTES_PER_ROW);
+        assert_eq!(71, ParquetBuffer::MEMORY_USAGE_BYTES_PER_ROW);
+    }
+
+    #[test]
+    fn write_and_read_list() {
+        let schema = parse_message_type(
+            "message schema {
+                OPTIONAL group a (LIST) {
+                    REPEATED group list {
+                        OPTIONAL INT32 element;
+                    }
+                }
+            }",
+        )
+        .unwrap();
+        let lists = vec![None, Some(vec![]), Some(vec![Some(1), None, Some(3)])];
+        let mut pb = ParquetBuffer::new(lists.len());
+        pb.set_num_rows_fetched(lists.len());
+
+        let mut file = Vec::new();
+        let mut writer =
+            SerializedFileWriter::new(&mut file, Arc::new(schema), Default::default()).unwrap();
+        let mut row_group_writer = writer.next_row_group().unwrap();
+        let mut column_writer = row_group_writer.next_column().unwrap().unwrap();
+        pb.write_list(
+            column_writer.typed::<Int32Type>(),
+            lists.clone().into_iter().map(Ok),
+            true,
+        )
+        .unwrap();
+        column_writer.close().unwrap();
+        row_group_writer.close().unwrap();
+        writer.close().unwrap();
+
+        let reader = SerializedFileReader::new(Bytes::from(file)).unwrap();
+        let ColumnReader::Int32ColumnReader(mut column_reader) = reader
+            .get_row_group(0)
+            .unwrap()
+            .get_column_reader(0)
+            .unwrap()
+        else {
+            panic!("Expected INT32 column")
+        };
+        let actual = pb.read_list(&mut column_reader, 3, 3, true).unwrap();
+
+        assert_eq!(lists, actual);
+    }
+
+    #[test]
+    fn write_selected_rows() {
+        let schema = parse_message_type(
+            "message schema {
+                OPTIONAL INT32 a;
+            }",
+        )
+        .unwrap();
+        let mut pb = ParquetBuffer::new(4);
+        pb.set_num_rows_fetched(4);
+        pb.select_rows(vec![1, 3]);
+
+        let mut file = Vec::new();
+        let mut writer =
+            SerializedFileWriter::new(&mut file, Arc::new(schema), Default::default()).unwrap();
+        let mut row_group_writer = writer.next_row_group().unwrap();
+        let mut column_writer = row_group_writer.next_column().unwrap().unwrap();
+        pb.write_optional(
+            column_writer.typed::<Int32Type>(),
+            [Some(1), Some(2), Some(3), None].into_iter(),
+        )
+        .unwrap();
+        column_writer.close().unwrap();
+        row_group_writer.close().unwrap();
+        writer.close().unwrap();
+
+        let reader = SerializedFileReader::new(Bytes::from(file)).unwrap();
+        let ColumnReader::Int32ColumnReader(mut column_reader) = reader
+            .get_row_group(0)
+            .unwrap()
+            .get_column_reader(0)
+            .unwrap()
+        else {
+            panic!("Expected INT32 column")
+        };
+        let actual: Vec<_> = pb
+            .read_optional(&mut column_reader, 4)
+            .unwrap()
+            .map(|opt| opt.copied())
+            .collect();
+
+        assert_eq!(vec![Some(2), None], actual);
     }
 }
@@ -1,38 +1,93 @@
+mod arrow_column;
+mod arrow_schema;
+mod arrow_writer;
 mod batch_size_limit;
 mod binary;
 mod boolean;
+mod column_length_limit;
 mod column_strategy;
 mod conversion_strategy;
 mod current_file;
 mod date;
 mod decimal;
+mod dry_run;
+mod dump;
 mod fetch_batch;
 mod identical;
+mod identifier;
+mod lob;
+mod manifest;
+mod output;
+mod parameter;
+mod parameters_from;
 mod parquet_writer;
+mod partition;
+mod pg_array;
+mod range_split;
+mod schema_overrides;
+mod staged_rows;
 mod text;
+mod text_value;
+mod text_writer;
 mod time;
 mod timestamp;
 mod timestamp_precision;
 mod timestamp_tz;
+mod type_name;
+mod uuid;
+mod watermark;
 
 use anyhow::Error;
-use fetch_batch::{fetch_strategy, FetchBatch};
+use chrono::{DateTime, SecondsFormat, Utc};
+use fetch_batch::fetch_strategy;
 use io_arg::IoArg;
-use log::debug;
-use odbc_api::{Cursor, IntoParameter};
-use std::io::{stdin, Read};
+use log::{debug, info};
+use odbc_api::{
+    handles::Statement, parameter::InputParameter, Connection, Cursor, CursorImpl, OwnedCursor,
+    ParameterCollectionRef,
+};
+use parquet::file::metadata::KeyValue;
+use std::{
+    io::{stdin, Read},
+    thread,
+    time::Duration,
+};
+
+pub(crate) use self::uuid::UUID_TEXT_LENGTH;
+pub use self::{column_length_limit::ColumnLengthLimits, dump::dump};
 
 use self::{
     batch_size_limit::{BatchSizeLimit, FileSizeLimit},
     column_strategy::{ColumnStrategy, MappingOptions},
     conversion_strategy::ConversionStrategy,
-    parquet_writer::{parquet_output, ParquetWriterOptions},
+    dry_run::dry_run,
+    identifier::{identifier_quote, quote_identifier},
+    manifest::Manifest,
+    output::{open_output, path_with_suffix, OutputOptions},
+    parameter::Parameter,
+    parameters_from::parameters_from_file_to_parquet,
+    parquet_writer::ParquetWriterOptions,
+    range_split::range_split_queries,
+    schema_overrides::SchemaOverrides,
+    timestamp_precision::TimestampEncoding,
+    watermark::{StateFile, Watermark},
+};
+
+use crate::{
+    connection::{open_query_connection, with_retries, ConnectOpts},
+    QueryOpt,
 };
 
-use crate::{connection::open_connection, QueryOpt};
+/// Execute a query and writes the result to parquet. The export is restarted from scratch, if it
+/// fails with a transient error and retries are left.
+pub fn query(mut opt: QueryOpt) -> Result<(), Error> {
+    // Standard input can only be read once, so resolve the query text before the first attempt.
+    opt.query = query_statement_text(opt.query)?;
+    let backoff = Duration::from_secs(opt.retry_backoff);
+    with_retries(opt.retries, backoff, || query_once(opt.clone()))
+}
 
-/// Execute a query and writes the result to parquet.
-pub fn query(opt: QueryOpt) -> Result<(), Error> {
+fn query_once(opt: QueryOpt) -> Result<(), Error> {
     let QueryOpt {
         connect_opts,
         output,
@@ -45,39 +100,114 @@ pub fn query(opt: QueryOpt) -> Result<(), Error> {
         file_size_threshold,
         encoding,
         prefer_varbinary,
+        format,
         column_compression_default,
         column_compression_level_default,
         parquet_column_encoding,
+        parquet_column_compression,
+        parquet_column_dictionary,
+        parquet_column_statistics,
+        bloom_filter,
+        metadata,
+        arrow_schema,
         avoid_decimal,
+        timestamp_unit,
+        timestamp_int96,
         driver_does_not_support_64bit_integers,
         suffix_length,
         no_empty_file,
+        manifest,
         column_length_limit,
+        lob_threshold,
+        partition_by,
+        split_by,
+        parallelism,
+        incremental_column,
+        state_file,
+        schema_overrides,
+        all_result_sets,
+        parameters_from,
+        retries: _,
+        retry_backoff: _,
+        dry_run: only_print_schema,
     } = opt;
 
+    let start_time = Utc::now();
+    let manifest = manifest.map(Manifest::new);
     let batch_size = BatchSizeLimit::new(batch_size_row, batch_size_memory);
     let file_size = FileSizeLimit::new(row_groups_per_file, file_size_threshold);
-    let query = query_statement_text(query)?;
+    let mut query = query;
+    // Remember the query as passed by the user, before it is rewritten for incremental or
+    // parallel extraction.
+    let query_text = query.clone();
+
+    let state_file = incremental_column
+        .zip(state_file)
+        .map(|(column, path)| StateFile::new(path, column));
+    let watermark = state_file
+        .as_ref()
+        .map(StateFile::load)
+        .transpose()?
+        .flatten();
+    let incremental_column = state_file.as_ref().map(StateFile::column);
 
-    // Convert the input strings into parameters suitable for use with ODBC.
-    let params: Vec<_> = parameters
+    let typed_parameters = parameters
         .iter()
-        .map(|param| param.as_str().into_parameter())
-        .collect();
+        .map(|parameter| Parameter::parse(parameter))
+        .collect::<Result<Vec<_>, _>>()?;
+    let params = query_parameters(&typed_parameters, watermark.as_ref());
 
-    let odbc_conn = open_connection(&connect_opts)?;
+    let odbc_conn = open_query_connection(&connect_opts)?;
     let db_name = odbc_conn.database_management_system_name()?;
     debug!(name:display = db_name; "Database Management System");
 
-    let parquet_format_options = ParquetWriterOptions {
-        column_compression_default: column_compression_default
-            .to_compression(column_compression_level_default)?,
-        column_encodings: parquet_column_encoding,
+    let is_wrapped = if let (Some(state_file), Some(watermark)) = (&state_file, &watermark) {
+        debug!(watermark:? = watermark; "Only fetching rows beyond watermark of last run");
+        // Only fetch rows which are new since the last run
+        query = format!(
+            "SELECT * FROM ({}) incremental_query WHERE {} > ?",
+            query.trim_end().trim_end_matches(';'),
+            quote_identifier(state_file.column(), identifier_quote(&db_name))
+        );
+        true
+    } else {
+        false
+    };
+
+    let output_options = OutputOptions {
+        format,
+        parquet: ParquetWriterOptions {
+            column_compression_default: column_compression_default
+                .to_compression(column_compression_level_default)?,
+            column_encodings: parquet_column_encoding,
+            column_compressions: parquet_column_compression,
+            column_dictionaries: parquet_column_dictionary,
+            column_statistics: parquet_column_statistics,
+            bloom_filters: bloom_filter,
+            arrow_schema,
+        },
+        key_value_metadata: provenance_metadata(
+            &query_text,
+            &parameters,
+            &db_name,
+            start_time,
+            metadata,
+        )?,
         file_size,
         suffix_length,
         no_empty_file,
+        partitioned: !partition_by.is_empty(),
+        manifest: manifest.clone(),
     };
 
+    let schema_overrides = schema_overrides
+        .as_deref()
+        .map(SchemaOverrides::from_file)
+        .transpose()?
+        .unwrap_or_default();
+
+    let (column_length_limit, column_length_limits) =
+        ColumnLengthLimits::from_arguments(&column_length_limit);
     let mapping_options = MappingOptions {
         db_name: &db_name,
         use_utf16: encoding.use_utf16(),
@@ -85,30 +215,197 @@ pub fn query(opt: QueryOpt) -> Result<(), Error> {
         avoid_decimal,
         driver_does_support_i64: !driver_does_not_support_64bit_integers,
         column_length_limit,
+        column_length_limits: &column_length_limits,
+        timestamp_encoding: TimestampEncoding::from_arguments(timestamp_unit, timestamp_int96),
+        lob_threshold,
+        schema_overrides: &schema_overrides,
     };
 
-    if let Some(cursor) = odbc_conn
-        .into_cursor(&query, params.as_slice(), None)
-        // Drop the connection for odbc_api::ConnectionAndError in order to make the error
-        // convertible into an anyhow error. The connection is offered by odbc_api in the error type
-        // to allow reusing the same connection, even after conversion into cursor failed. However
-        // within the context of `odbc2parquet`, we just want to shutdown the application and
-        // present an error to the user.
-        .map_err(odbc_api::Error::from)?
-    {
-        cursor_to_parquet(
-            cursor,
+    if only_print_schema {
+        return dry_run(
+            &odbc_conn,
+            &query,
+            mapping_options,
+            &partition_by,
+            batch_size,
+            !sequential_fetching,
+        )
+        .map_err(|error| explain_incremental_error(error, is_wrapped, &db_name, &query_text));
+    }
+
+    if let Some(parameters_path) = parameters_from {
+        parameters_from_file_to_parquet(
+            &odbc_conn,
+            &query,
+            &parameters_path,
+            connect_opts.query_timeout_sec(),
+            output,
+            batch_size,
+            mapping_options,
+            output_options,
+            &partition_by,
+        )?;
+        return write_manifest(manifest);
+    }
+
+    if let Some(split_by) = split_by {
+        let queries = range_split_queries(
+            &odbc_conn,
+            &query,
+            params.as_slice(),
+            &split_by,
+            parallelism.into(),
+            connect_opts.query_timeout_sec(),
+        )?;
+        let new_watermark = split_query_to_parquet(
+            &connect_opts,
+            &queries,
+            &typed_parameters,
+            watermark.as_ref(),
             output,
             batch_size,
             !sequential_fetching,
             mapping_options,
-            parquet_format_options,
+            output_options,
+            incremental_column,
         )?;
+        write_manifest(manifest)?;
+        return commit_watermark(state_file, new_watermark);
+    }
+
+    let mut new_watermark = None;
+    let cursor = if all_result_sets {
+        first_result_set_with_columns(
+            odbc_conn,
+            &query,
+            params.as_slice(),
+            connect_opts.query_timeout_sec(),
+        )?
+    } else {
+        odbc_conn
+            .into_cursor(&query, params.as_slice(), connect_opts.query_timeout_sec())
+            // Drop the connection for odbc_api::ConnectionAndError in order to make the error
+            // convertible into an anyhow error. The connection is offered by odbc_api in the error
+            // type to allow reusing the same connection, even after conversion into cursor failed.
+            // However within the context of `odbc2parquet`, we just want to shutdown the
+            // application and present an error to the user.
+            .map_err(|error| {
+                explain_incremental_error(
+                    odbc_api::Error::from(error).into(),
+                    is_wrapped,
+                    &db_name,
+                    &query_text,
+                )
+            })?
+    };
+    if let Some(cursor) = cursor {
+        if all_result_sets {
+            result_sets_to_parquet(
+                cursor,
+                output,
+                batch_size,
+                !sequential_fetching,
+                mapping_options,
+                output_options,
+                &partition_by,
+            )?;
+        } else {
+            (new_watermark, _) = cursor_to_parquet(
+                cursor,
+                output,
+                batch_size,
+                !sequential_fetching,
+                mapping_options,
+                output_options,
+                &partition_by,
+                incremental_column,
+            )?;
+        }
     } else {
         eprintln!(
             "Query came back empty (not even a schema has been returned). No file has been created"
         );
     }
+    write_manifest(manifest)?;
+    commit_watermark(state_file, new_watermark)
+}
+
+/// `--incremental-column` wraps the query in a derived table. Microsoft SQL Server does not allow
+/// `ORDER BY` in derived tables, so explain the likely cause instead of only reporting the error of
+/// the driver.
+fn explain_incremental_error(error: Error, is_wrapped: bool, db_name: &str, query: &str) -> Error {
+    if is_wrapped && db_name == "Microsoft SQL Server" && query.to_uppercase().contains("ORDER BY")
+    {
+        error.context(
+            "`--incremental-column` wraps the query in a derived table. Microsoft SQL Server does \
+            not allow `ORDER BY` in derived tables, unless `TOP` or `OFFSET` is specified as well. \
+            Please remove the `ORDER BY` clause from the query.",
+        )
+    } else {
+        error
+    }
+}
+
+/// Key value metadata describing where the output came from. The custom metadata passed by the user
+/// is appended to it.
+fn provenance_metadata(
+    query: &str,
+    parameters: &[String],
+    db_name: &str,
+    start_time: DateTime<Utc>,
+    custom: Vec<(String, String)>,
+) -> Result<Vec<KeyValue>, Error> {
+    let provenance = [
+        ("odbc2parquet.version", env!("CARGO_PKG_VERSION").to_owned()),
+        ("odbc2parquet.query", query.to_owned()),
+        (
+            "odbc2parquet.parameters",
+            serde_json::to_string(parameters)?,
+        ),
+        ("odbc2parquet.dbms", db_name.to_owned()),
+        (
+            "odbc2parquet.start_time",
+            start_time.to_rfc3339_opts(SecondsFormat::Millis, true),
+        ),
+    ];
+    let metadata = provenance
+        .into_iter()
+        .map(|(key, value)| (key.to_owned(), value))
+        .chain(custom)
+        .map(|(key, value)| KeyValue::new(key, value))
+        .collect();
+    Ok(metadata)
+}
+
+/// Convert the parameters into values suitable for use with ODBC. The watermark of an incremental
+/// extraction is bound to the last placeholder.
+fn query_parameters(
+    parameters: &[Parameter],
+    watermark: Option<&Watermark>,
+) -> Vec<Box<dyn InputParameter>> {
+    let mut params: Vec<_> = parameters.iter().map(Parameter::to_parameter).collect();
+    params.extend(watermark.map(Watermark::to_parameter));
+    params
+}
+
+/// Written after all output files have been completed, so its presence marks a complete export.
+fn write_manifest(manifest: Option<Manifest>) -> Result<(), Error> {
+    if let Some(manifest) = manifest {
+        manifest.write()?;
+    }
+    Ok(())
+}
+
+/// Remember the watermark for the next run. Called only after all output has been written
+/// successfully. If no new rows have been fetched, the watermark of the last run stays in place.
+fn commit_watermark(
+    state_file: Option<StateFile>,
+    watermark: Option<Watermark>,
+) -> Result<(), Error> {
+    if let (Some(state_file), Some(watermark)) = (state_file, watermark) {
+        info!(watermark:? = watermark; "Commit watermark");
+        state_file.commit(watermark)?;
+    }
     Ok(())
 }
 
@@ -125,19 +422,169 @@ fn query_statement_text(query: String) -> Result<String, Error> {
     })
 }
 
-fn cursor_to_parquet(
-    mut cursor: impl Cursor + Send + 'static,
-    path: IoArg,
+/// Executes each of the range queries on its own connection and writes its result into its own
+/// numbered output file. All queries are executed concurrently.
+#[allow(clippy::too_many_arguments)]
+fn split_query_to_parquet(
+    connect_opts: &ConnectOpts,
+    queries: &[String],
+    parameters: &[Parameter],
+    watermark: Option<&Watermark>,
+    output: IoArg,
     batch_size: BatchSizeLimit,
     concurrent_fetching: bool,
     mapping_options: MappingOptions,
-    parquet_format_options: ParquetWriterOptions,
+    output_options: OutputOptions,
+    incremental_column: Option<&str>,
+) -> Result<Option<Watermark>, Error> {
+    let IoArg::File(path) = output else {
+        unreachable!("Splitting a query into ranges is only possible if writing to a file.")
+    };
+    thread::scope(|scope| {
+        let pipelines: Vec<_> = queries
+            .iter()
+            .enumerate()
+            .map(|(index, query)| {
+                let path = path_with_suffix(&path, index as u32 + 1, output_options.suffix_length);
+                let output_options = output_options.clone();
+                scope.spawn(move || -> Result<Option<Watermark>, Error> {
+                    let params = query_parameters(parameters, watermark);
+                    let odbc_conn = open_query_connection(connect_opts)?;
+                    let mut watermark = None;
+                    if let Some(cursor) = odbc_conn
+                        .into_cursor(query, params.as_slice(), connect_opts.query_timeout_sec())
+                        .map_err(odbc_api::Error::from)?
+                    {
+                        (watermark, _) = cursor_to_parquet(
+                            cursor,
+                            IoArg::File(path?),
+                            batch_size,
+                            concurrent_fetching,
+                            mapping_options,
+                            output_options,
+                            &[],
+                            incremental_column,
+                        )?;
+                    }
+                    Ok(watermark)
+                })
+            })
+            .collect();
+        pipelines.into_iter().try_fold(None, |watermark, pipeline| {
+            let range_watermark = pipeline
+                .join()
+                .expect("Range query thread must not panic")?;
+            Ok(Watermark::max(watermark, range_watermark))
+        })
+    })
+}
+
+/// Executes `query` and returns a cursor positioned on its first result set with columns. `None` if
+/// there is none. Unlike [`Connection::into_cursor`] this skips leading results without columns,
+/// like the row count of the `UPDATE` in `UPDATE ...; SELECT ...`.
+fn first_result_set_with_columns(
+    conn: Connection<'static>,
+    query: &str,
+    params: impl ParameterCollectionRef,
+    query_timeout_sec: Option<usize>,
+) -> Result<Option<OwnedCursor<Connection<'static>>>, Error> {
+    let mut preallocated = conn.into_preallocated()?;
+    if let Some(timeout) = query_timeout_sec {
+        preallocated.set_query_timeout_sec(timeout)?;
+    }
+    let has_columns = if let Some(cursor) = preallocated.execute(query, params)? {
+        // Do not close the cursor, we take it over together with the statement handle below.
+        cursor.into_stmt();
+        true
+    } else {
+        false
+    };
+    let mut statement = preallocated.into_handle();
+    if !has_columns {
+        loop {
+            // SAFETY: The parameters bound to the statement are still borrowed by this function.
+            let has_more_results =
+                unsafe { statement.more_results() }.into_result_bool(&statement)?;
+            if !has_more_results {
+                return Ok(None);
+            }
+            if statement.num_result_cols().into_result(&statement)? != 0 {
+                break;
+            }
+        }
+    }
+    // SAFETY: The statement is positioned on a result set with columns, i.e. in cursor state.
+    Ok(Some(unsafe { CursorImpl::new(statement) }))
+}
+
+/// Writes each result set of the statement into its own numbered output file, e.g. `out_01.par`,
+/// `out_02.par`, ... Result sets without columns, like the row counts of updates within a stored
+/// procedure, are skipped.
+fn result_sets_to_parquet(
+    cursor: impl Cursor + Send + 'static,
+    output: IoArg,
+    batch_size: BatchSizeLimit,
+    concurrent_fetching: bool,
+    mapping_options: MappingOptions,
+    output_options: OutputOptions,
+    partition_by: &[String],
 ) -> Result<(), Error> {
-    let table_strategy = ConversionStrategy::new(&mut cursor, mapping_options)?;
+    let IoArg::File(path) = output else {
+        unreachable!("Writing all result sets is only possible if writing to files.")
+    };
+    let mut num_result_set = 0;
+    let mut next = Some(cursor);
+    while let Some(mut cursor) = next {
+        if cursor.num_result_cols()? == 0 {
+            debug!("Skipping result set without columns");
+            next = cursor.more_results()?;
+            continue;
+        }
+        num_result_set += 1;
+        let path = path_with_suffix(&path, num_result_set, output_options.suffix_length)?;
+        info!(num_result_set, path:display = path.to_string_lossy(); "Writing result set");
+        let (_watermark, cursor) = cursor_to_parquet(
+            cursor,
+            IoArg::File(path),
+            batch_size,
+            concurrent_fetching,
+            mapping_options,
+            output_options.clone(),
+            partition_by,
+            None,
+        )?;
+        next = cursor.more_results()?;
+    }
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+fn cursor_to_parquet<C>(
+    mut cursor: C,
+    path: IoArg,
+    batch_size: BatchSizeLimit,
+    concurrent_fetching: bool,
+    mapping_options: MappingOptions,
+    output_options: OutputOptions,
+    partition_by: &[String],
+    incremental_column: Option<&str>,
+) -> Result<(Option<Watermark>, C), Error>
+where
+    C: Cursor + Send + 'static,
+{
+    let mut table_strategy = ConversionStrategy::new(&mut cursor, mapping_options)?;
+    if !partition_by.is_empty() {
+        table_strategy.partition_by(partition_by)?;
+    }
+    if let Some(incremental_column) = incremental_column {
+        table_strategy.track_watermark(incremental_column)?;
+    }
     let parquet_schema = table_strategy.parquet_schema();
-    let writer = parquet_output(path, parquet_schema.clone(), parquet_format_options)?;
-    let fetch_strategy: Box<dyn FetchBatch> =
+    let writer = open_output(path, parquet_schema.clone(), output_options)?;
+    let mut fetch_strategy =
         fetch_strategy(concurrent_fetching, cursor, &table_strategy, batch_size)?;
-    table_strategy.block_cursor_to_parquet(fetch_strategy, writer)?;
-    Ok(())
+    let watermark = table_strategy.block_cursor_to_parquet(fetch_strategy.as_mut(), writer)?;
+    // Hand the cursor back, so the caller can move on to the next result set.
+    let cursor = fetch_strategy.into_cursor()?;
+    Ok((watermark, cursor))
 }
@@ -0,0 +1,304 @@
+//! Arrow arrays built from the values the column strategies convert the ODBC fetch buffers into.
+
+use std::{borrow::Cow, sync::Arc};
+
+use anyhow::{bail, Error};
+use arrow_array::{
+    types::{
+        Date32Type, Decimal128Type, Decimal256Type, Float32Type, Float64Type, Int16Type, Int32Type,
+        Int64Type, Int8Type, Time32MillisecondType, Time32SecondType, Time64MicrosecondType,
+        Time64NanosecondType, TimestampMicrosecondType, TimestampMillisecondType,
+        TimestampNanosecondType, TimestampSecondType, UInt16Type, UInt32Type, UInt64Type,
+        UInt8Type,
+    },
+    ArrayRef, ArrowPrimitiveType, BinaryArray, BooleanArray, FixedSizeBinaryArray, ListArray,
+    PrimitiveArray, StringArray,
+};
+use arrow_buffer::{i256, NullBuffer, OffsetBuffer};
+use arrow_schema::{DataType, TimeUnit};
+
+use crate::parquet_buffer::OptionalList;
+
+/// Value produced by a column strategy, which can be stored in an Arrow array. Which array depends
+/// on the Arrow data type derived from the parquet type of the column, e.g. an `i32` is stored in an
+/// `Int16` array for a `SMALLINT` column and in a `Date32` array for a `DATE` column.
+pub trait ArrowValue: Sized {
+    /// Array of type `data_type` holding `values`. `None` elements are `NULL`.
+    fn arrow_array(
+        values: impl IntoIterator<Item = Option<Self>>,
+        data_type: &DataType,
+    ) -> Result<ArrayRef, Error>;
+
+    /// Same as [`Self::arrow_array`], for conversions which may fail.
+    fn try_arrow_array(
+        values: impl IntoIterator<Item = Result<Option<Self>, Error>>,
+        data_type: &DataType,
+    ) -> Result<ArrayRef, Error> {
+        let values = values.into_iter().collect::<Result<Vec<_>, _>>()?;
+        Self::arrow_array(values, data_type)
+    }
+}
+
+/// List array of type `data_type`. The elements are stored with [`ArrowValue::arrow_array`].
+pub fn list_array<T>(lists: Vec<OptionalList<T>>, data_type: &DataType) -> Result<ArrayRef, Error>
+where
+    T: ArrowValue,
+{
+    let DataType::List(element_field) = data_type else {
+        bail!("Lists can not be converted to Arrow type {data_type}.")
+    };
+    let mut offsets = Vec::with_capacity(lists.len() + 1);
+    let mut validity = Vec::with_capacity(lists.len());
+    let mut elements = Vec::new();
+    offsets.push(0);
+    for list in lists {
+        validity.push(list.is_some());
+        elements.extend(list.into_iter().flatten());
+        offsets.push(i32::try_from(elements.len())?);
+    }
+    let elements = T::arrow_array(elements, element_field.data_type())?;
+    Ok(Arc::new(ListArray::try_new(
+        element_field.clone(),
+        OffsetBuffer::new(offsets.into()),
+        elements,
+        Some(NullBuffer::from(validity)),
+    )?))
+}
+
+fn primitive<T>(values: impl Iterator<Item = Option<T::Native>>, data_type: &DataType) -> ArrayRef
+where
+    T: ArrowPrimitiveType,
+{
+    // `with_data_type` carries over precision and scale of decimals, as well as time zones.
+    Arc::new(
+        values
+            .collect::<PrimitiveArray<T>>()
+            .with_data_type(data_type.clone()),
+    )
+}
+
+impl ArrowValue for bool {
+    fn arrow_array(
+        values: impl IntoIterator<Item = Option<Self>>,
+        data_type: &DataType,
+    ) -> Result<ArrayRef, Error> {
+        match data_type {
+            DataType::Boolean => Ok(Arc::new(values.into_iter().collect::<BooleanArray>())),
+            other => bail!("Booleans can not be converted to Arrow type {other}."),
+        }
+    }
+}
+
+impl ArrowValue for i32 {
+    fn arrow_array(
+        values: impl IntoIterator<Item = Option<Self>>,
+        data_type: &DataType,
+    ) -> Result<ArrayRef, Error> {
+        let values = values.into_iter();
+        let array = match data_type {
+            DataType::Int8 => primitive::<Int8Type>(values.map(|v| v.map(|v| v as i8)), data_type),
+            DataType::Int16 => {
+                primitive::<Int16Type>(values.map(|v| v.map(|v| v as i16)), data_type)
+            }
+            DataType::Int32 => primitive::<Int32Type>(values, data_type),
+            DataType::UInt8 => {
+                primitive::<UInt8Type>(values.map(|v| v.map(|v| v as u8)), data_type)
+            }
+            DataType::UInt16 => {
+                primitive::<UInt16Type>(values.map(|v| v.map(|v| v as u16)), data_type)
+            }
+            DataType::UInt32 => {
+                primitive::<UInt32Type>(values.map(|v| v.map(|v| v as u32)), data_type)
+            }
+            DataType::Date32 => primitive::<Date32Type>(values, data_type),
+            DataType::Time32(TimeUnit::Second) => primitive::<Time32SecondType>(values, data_type),
+            DataType::Time32(TimeUnit::Millisecond) => {
+                primitive::<Time32MillisecondType>(values, data_type)
+            }
+            DataType::Decimal128(_, _) => {
+                primitive::<Decimal128Type>(values.map(|v| v.map(i128::from)), data_type)
+            }
+            other => bail!("32 Bit integers can not be converted to Arrow type {other}."),
+        };
+        Ok(array)
+    }
+}
+
+impl ArrowValue for i64 {
+    fn arrow_array(
+        values: impl IntoIterator<Item = Option<Self>>,
+        data_type: &DataType,
+    ) -> Result<ArrayRef, Error> {
+        let values = values.into_iter();
+        let array = match data_type {
+            DataType::Int64 => primitive::<Int64Type>(values, data_type),
+            DataType::UInt64 => {
+                primitive::<UInt64Type>(values.map(|v| v.map(|v| v as u64)), data_type)
+            }
+            DataType::Time64(TimeUnit::Microsecond) => {
+                primitive::<Time64MicrosecondType>(values, data_type)
+            }
+            DataType::Time64(TimeUnit::Nanosecond) => {
+                primitive::<Time64NanosecondType>(values, data_type)
+            }
+            DataType::Timestamp(TimeUnit::Second, _) => {
+                primitive::<TimestampSecondType>(values, data_type)
+            }
+            DataType::Timestamp(TimeUnit::Millisecond, _) => {
+                primitive::<TimestampMillisecondType>(values, data_type)
+            }
+            DataType::Timestamp(TimeUnit::Microsecond, _) => {
+                primitive::<TimestampMicrosecondType>(values, data_type)
+            }
+            DataType::Timestamp(TimeUnit::Nanosecond, _) => {
+                primitive::<TimestampNanosecondType>(values, data_type)
+            }
+            DataType::Decimal128(_, _) => {
+                primitive::<Decimal128Type>(values.map(|v| v.map(i128::from)), data_type)
+            }
+            other => bail!("64 Bit integers can not be converted to Arrow type {other}."),
+        };
+        Ok(array)
+    }
+}
+
+impl ArrowValue for i128 {
+    fn arrow_array(
+        values: impl IntoIterator<Item = Option<Self>>,
+        data_type: &DataType,
+    ) -> Result<ArrayRef, Error> {
+        match data_type {
+            DataType::Decimal128(_, _) => {
+                Ok(primitive::<Decimal128Type>(values.into_iter(), data_type))
+            }
+            other => bail!("128 Bit integers can not be converted to Arrow type {other}."),
+        }
+    }
+}
+
+impl ArrowValue for i256 {
+    fn arrow_array(
+        values: impl IntoIterator<Item = Option<Self>>,
+        data_type: &DataType,
+    ) -> Result<ArrayRef, Error> {
+        match data_type {
+            DataType::Decimal256(_, _) => {
+                Ok(primitive::<Decimal256Type>(values.into_iter(), data_type))
+            }
+            other => bail!("256 Bit integers can not be converted to Arrow type {other}."),
+        }
+    }
+}
+
+impl ArrowValue for f32 {
+    fn arrow_array(
+        values: impl IntoIterator<Item = Option<Self>>,
+        data_type: &DataType,
+    ) -> Result<ArrayRef, Error> {
+        match data_type {
+            DataType::Float32 => Ok(primitive::<Float32Type>(values.into_iter(), data_type)),
+            other => bail!("32 Bit floats can not be converted to Arrow type {other}."),
+        }
+    }
+}
+
+impl ArrowValue for f64 {
+    fn arrow_array(
+        values: impl IntoIterator<Item = Option<Self>>,
+        data_type: &DataType,
+    ) -> Result<ArrayRef, Error> {
+        match data_type {
+            DataType::Float64 => Ok(primitive::<Float64Type>(values.into_iter(), data_type)),
+            other => bail!("64 Bit floats can not be converted to Arrow type {other}."),
+        }
+    }
+}
+
+impl ArrowValue for String {
+    fn arrow_array(
+        values: impl IntoIterator<Item = Option<Self>>,
+        data_type: &DataType,
+    ) -> Result<ArrayRef, Error> {
+        match data_type {
+            DataType::Utf8 => Ok(Arc::new(values.into_iter().collect::<StringArray>())),
+            other => bail!("Text can not be converted to Arrow type {other}."),
+        }
+    }
+}
+
+impl ArrowValue for Cow<'_, str> {
+    fn arrow_array(
+        values: impl IntoIterator<Item = Option<Self>>,
+        data_type: &DataType,
+    ) -> Result<ArrayRef, Error> {
+        match data_type {
+            DataType::Utf8 => Ok(Arc::new(values.into_iter().collect::<StringArray>())),
+            other => bail!("Text can not be converted to Arrow type {other}."),
+        }
+    }
+}
+
+impl ArrowValue for &[u8] {
+    fn arrow_array(
+        values: impl IntoIterator<Item = Option<Self>>,
+        data_type: &DataType,
+    ) -> Result<ArrayRef, Error> {
+        let array: ArrayRef = match data_type {
+            DataType::Binary => Arc::new(values.into_iter().collect::<BinaryArray>()),
+            &DataType::FixedSizeBinary(length) => Arc::new(
+                FixedSizeBinaryArray::try_from_sparse_iter_with_size(values.into_iter(), length)?,
+            ),
+            other => bail!("Binary data can not be converted to Arrow type {other}."),
+        };
+        Ok(array)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use arrow_array::{
+        cast::AsArray,
+        types::{Int16Type, Int32Type, TimestampMillisecondType},
+        Array,
+    };
+    use arrow_schema::{DataType, Field, TimeUnit};
+
+    use super::{list_array, ArrowValue};
+
+    #[test]
+    fn integers_are_stored_in_the_arrow_type_of_the_column() {
+        let array = i32::arrow_array([Some(-3), None], &DataType::Int16).unwrap();
+        let array = array.as_primitive::<Int16Type>();
+        assert_eq!(-3, array.value(0));
+        assert!(array.is_null(1));
+
+        let data_type = DataType::Timestamp(TimeUnit::Millisecond, Some("UTC".into()));
+        let array = i64::arrow_array([Some(1_000)], &data_type).unwrap();
+        assert_eq!(&data_type, array.data_type());
+        assert_eq!(
+            1_000,
+            array.as_primitive::<TimestampMillisecondType>().value(0)
+        );
+
+        assert!(i32::arrow_array([Some(1)], &DataType::Utf8).is_err());
+    }
+
+    #[test]
+    fn lists_of_optional_integers() {
+        let data_type = DataType::List(Arc::new(Field::new("element", DataType::Int32, true)));
+        // [[1, NULL], NULL, []]
+        let lists = vec![Some(vec![Some(1), None]), None, Some(vec![])];
+
+        let array = list_array(lists, &data_type).unwrap();
+
+        let array = array.as_list::<i32>();
+        assert_eq!(3, array.len());
+        assert_eq!(&[0, 2, 2, 2], array.value_offsets());
+        assert!(array.is_valid(0) && array.is_null(1) && array.is_valid(2));
+        let elements = array.values().as_primitive::<Int32Type>();
+        assert_eq!(1, elements.value(0));
+        assert!(elements.is_null(1));
+    }
+}
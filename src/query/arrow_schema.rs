@@ -0,0 +1,100 @@
+use std::sync::Arc;
+
+use anyhow::{bail, Error};
+use arrow_schema::{Schema, DECIMAL256_MAX_PRECISION};
+use parquet::{
+    arrow::parquet_to_arrow_schema,
+    basic::LogicalType,
+    schema::types::{SchemaDescriptor, Type},
+};
+
+/// Arrow schema equivalent to the parquet schema, derived the same way Arrow based readers do.
+/// Shared by the `ARROW:schema` hint and the Arrow IPC output, so both agree on the types of the
+/// columns.
+pub fn arrow_schema(parquet_schema: Arc<Type>) -> Result<Schema, Error> {
+    let descriptor = SchemaDescriptor::new(parquet_schema);
+    for column in descriptor.columns() {
+        if let Some(LogicalType::Decimal(decimal)) = column.logical_type_ref() {
+            if decimal.precision > DECIMAL256_MAX_PRECISION.into() {
+                bail!(
+                    "Decimal column '{}' has a precision of {} digits. Arrow supports at most \
+                    {DECIMAL256_MAX_PRECISION}.",
+                    column.path().string(),
+                    decimal.precision
+                )
+            }
+        }
+    }
+    Ok(parquet_to_arrow_schema(&descriptor, None)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use arrow_schema::{DataType, Field, TimeUnit};
+    use parquet::schema::parser::parse_message_type;
+
+    use super::arrow_schema;
+
+    #[test]
+    fn small_integers_and_instants() {
+        let parquet_schema = parse_message_type(
+            "message schema {
+                REQUIRED INT32 a (INTEGER(8,true));
+                OPTIONAL INT32 b (INTEGER(16,true));
+                OPTIONAL INT64 c (TIMESTAMP(MICROS,true));
+                OPTIONAL INT64 d (TIMESTAMP(MILLIS,false));
+                OPTIONAL BYTE_ARRAY e (UTF8);
+                OPTIONAL GROUP f (LIST) {
+                    REPEATED GROUP list {
+                        OPTIONAL INT32 element (INTEGER(32,true));
+                    }
+                }
+            }",
+        )
+        .unwrap();
+
+        let schema = arrow_schema(Arc::new(parquet_schema)).unwrap();
+
+        let types: Vec<_> = schema
+            .fields()
+            .iter()
+            .map(|field| (field.data_type().clone(), field.is_nullable()))
+            .collect();
+        assert_eq!(
+            vec![
+                (DataType::Int8, false),
+                (DataType::Int16, true),
+                (
+                    DataType::Timestamp(TimeUnit::Microsecond, Some("UTC".into())),
+                    true
+                ),
+                (DataType::Timestamp(TimeUnit::Millisecond, None), true),
+                (DataType::Utf8, true),
+                (
+                    DataType::List(Arc::new(Field::new("element", DataType::Int32, true))),
+                    true
+                ),
+            ],
+            types
+        );
+    }
+
+    #[test]
+    fn reject_decimals_beyond_arrow_precision() {
+        let parquet_schema = parse_message_type(
+            "message schema {
+                OPTIONAL FIXED_LEN_BYTE_ARRAY (33) a (DECIMAL(77,2));
+            }",
+        )
+        .unwrap();
+
+        let error = arrow_schema(Arc::new(parquet_schema)).unwrap_err();
+
+        assert_eq!(
+            "Decimal column 'a' has a precision of 77 digits. Arrow supports at most 76.",
+            error.to_string()
+        );
+    }
+}
@@ -0,0 +1,240 @@
+use std::{
+    fs::File,
+    io::{stdout, BufWriter, Stdout},
+    path::PathBuf,
+    sync::Arc,
+};
+
+use anyhow::{Context, Error};
+use arrow_array::RecordBatch;
+use arrow_ipc::writer::{FileWriter as IpcFileWriter, StreamWriter};
+use arrow_schema::SchemaRef;
+use bytesize::ByteSize;
+use io_arg::IoArg;
+use log::info;
+use parquet::schema::types::Type;
+use tempfile::TempPath;
+
+use super::{
+    arrow_schema::arrow_schema,
+    batch_size_limit::FileSizeLimit,
+    conversion_strategy::ColumnExporter,
+    manifest::Manifest,
+    output::{path_with_suffix, Output, OutputOptions},
+};
+
+/// Writes Arrow IPC. Files are written in the IPC file format (also known as Feather V2), standard
+/// out in the IPC stream format, since it can be consumed without seeking.
+pub fn arrow_output(
+    output: IoArg,
+    parquet_schema: Arc<Type>,
+    options: OutputOptions,
+) -> Result<Box<dyn Output>, Error> {
+    let converter = RecordBatchConverter::new(parquet_schema, &options)?;
+    let writer: Box<dyn Output> = match output {
+        IoArg::StdStream => Box::new(ArrowStandardOut::new(converter)?),
+        IoArg::File(path) => Box::new(ArrowFileWriter::new(path, converter, options)?),
+    };
+    Ok(writer)
+}
+
+/// Converts fetched batches into Arrow record batches. The column strategies convert the values
+/// of the fetch buffers directly into the Arrow type derived from the parquet type of the column,
+/// so the values are identical to the ones written into parquet files.
+struct RecordBatchConverter {
+    arrow_schema: SchemaRef,
+}
+
+impl RecordBatchConverter {
+    fn new(parquet_schema: Arc<Type>, options: &OutputOptions) -> Result<Self, Error> {
+        let metadata = options
+            .key_value_metadata
+            .iter()
+            .filter_map(|kv| Some((kv.key.clone(), kv.value.clone()?)))
+            .collect();
+        let arrow_schema = arrow_schema(parquet_schema)?.with_metadata(metadata);
+        Ok(Self {
+            arrow_schema: Arc::new(arrow_schema),
+        })
+    }
+
+    fn convert(&self, column_exporter: &ColumnExporter) -> Result<RecordBatch, Error> {
+        let columns = self
+            .arrow_schema
+            .fields()
+            .iter()
+            .enumerate()
+            .map(|(index, field)| column_exporter.arrow_column(index, field.data_type()))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(RecordBatch::try_new(self.arrow_schema.clone(), columns)?)
+    }
+}
+
+/// Writes Arrow IPC files. Handles splitting into new files after the file size limit is reached.
+struct ArrowFileWriter {
+    base_path: PathBuf,
+    converter: RecordBatchConverter,
+    file_size: FileSizeLimit,
+    num_file: u32,
+    /// Length of the suffix, appended to the end of a file in case they are numbered.
+    suffix_length: usize,
+    /// Current file open for writing. `None`, if we are in between files.
+    current_file: Option<CurrentArrowFile>,
+    /// Records each completed file, if a manifest has been requested.
+    manifest: Option<Manifest>,
+}
+
+impl ArrowFileWriter {
+    fn new(
+        path: PathBuf,
+        converter: RecordBatchConverter,
+        options: OutputOptions,
+    ) -> Result<Self, Error> {
+        let mut writer = Self {
+            base_path: path,
+            converter,
+            file_size: options.file_size,
+            num_file: 0,
+            suffix_length: options.suffix_length,
+            current_file: None,
+            manifest: options.manifest,
+        };
+        if !options.no_empty_file {
+            writer.next_file()?;
+        }
+        Ok(writer)
+    }
+
+    fn next_file(&mut self) -> Result<(), Error> {
+        let path = if self.file_size.output_is_splitted() {
+            path_with_suffix(&self.base_path, self.num_file + 1, self.suffix_length)?
+        } else {
+            self.base_path.clone()
+        };
+        self.current_file = Some(CurrentArrowFile::new(path, &self.converter.arrow_schema)?);
+        self.num_file += 1;
+        Ok(())
+    }
+}
+
+impl Output for ArrowFileWriter {
+    fn write_batch(
+        &mut self,
+        num_batch: u32,
+        column_exporter: ColumnExporter,
+    ) -> Result<(), Error> {
+        if self.current_file.is_none() {
+            self.next_file()?
+        }
+
+        let batch = self.converter.convert(&column_exporter)?;
+        let file_size = self.current_file.as_mut().unwrap().write(&batch)?;
+
+        if self.file_size.file_limit_reached(num_batch, file_size) {
+            self.current_file
+                .take()
+                .unwrap()
+                .finalize(self.manifest.as_ref())?;
+        }
+        Ok(())
+    }
+
+    fn close(self) -> Result<(), Error> {
+        if let Some(open_file) = self.current_file {
+            open_file.finalize(self.manifest.as_ref())?;
+        }
+        Ok(())
+    }
+
+    fn close_box(self: Box<Self>) -> Result<(), Error> {
+        self.close()
+    }
+}
+
+struct CurrentArrowFile {
+    writer: IpcFileWriter<BufWriter<File>>,
+    /// Path to the file currently being written to. Removed again, should we fail before it is
+    /// finalized.
+    path: TempPath,
+    /// Number of rows written so far.
+    total_num_rows: u64,
+    /// Number of record batches written so far.
+    num_batches: u32,
+}
+
+impl CurrentArrowFile {
+    fn new(path: PathBuf, schema: &SchemaRef) -> Result<Self, Error> {
+        let file = File::create(&path).with_context(|| {
+            format!("Could not create output file '{}'", path.to_string_lossy())
+        })?;
+        let path = TempPath::try_from_path(path).context("Unable to create temporary file")?;
+        let writer = IpcFileWriter::try_new_buffered(file, schema)?;
+        Ok(Self {
+            writer,
+            path,
+            total_num_rows: 0,
+            num_batches: 0,
+        })
+    }
+
+    /// Returns the size of the file after the write.
+    fn write(&mut self, batch: &RecordBatch) -> Result<ByteSize, Error> {
+        self.writer.write(batch)?;
+        self.writer.flush()?;
+        self.total_num_rows += batch.num_rows() as u64;
+        self.num_batches += 1;
+        let file_size = self.writer.get_ref().get_ref().metadata()?.len();
+        Ok(ByteSize::b(file_size))
+    }
+
+    fn finalize(mut self, manifest: Option<&Manifest>) -> Result<(), Error> {
+        self.writer.finish()?;
+        let file_size = ByteSize::b(self.writer.get_ref().get_ref().metadata()?.len());
+        let path = self.path.keep()?;
+        info!(
+            rows = self.total_num_rows,
+            size:display = file_size,
+            path:display = path.to_string_lossy();
+            "File complete"
+        );
+        if let Some(manifest) = manifest {
+            manifest.record(&path, self.total_num_rows, self.num_batches)?;
+        }
+        Ok(())
+    }
+}
+
+/// Stream Arrow IPC directly to standard out.
+struct ArrowStandardOut {
+    writer: StreamWriter<BufWriter<Stdout>>,
+    converter: RecordBatchConverter,
+}
+
+impl ArrowStandardOut {
+    fn new(converter: RecordBatchConverter) -> Result<Self, Error> {
+        let writer = StreamWriter::try_new_buffered(stdout(), &converter.arrow_schema)?;
+        Ok(Self { writer, converter })
+    }
+}
+
+impl Output for ArrowStandardOut {
+    fn write_batch(
+        &mut self,
+        _num_batch: u32,
+        column_exporter: ColumnExporter,
+    ) -> Result<(), Error> {
+        let batch = self.converter.convert(&column_exporter)?;
+        self.writer.write(&batch)?;
+        Ok(())
+    }
+
+    fn close(mut self) -> Result<(), Error> {
+        self.writer.finish()?;
+        self.writer.flush()?;
+        Ok(())
+    }
+
+    fn close_box(self: Box<Self>) -> Result<(), Error> {
+        self.close()
+    }
+}
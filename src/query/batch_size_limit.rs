@@ -15,6 +15,7 @@ const DEFAULT_BATCH_SIZE_BYTES: ByteSize = ByteSize::gib(1); // 1GB
 const DEFAULT_BATCH_SIZE_ROWS: usize = u16::MAX as usize; // 65535 rows
 
 /// Describes how we limit the size of individual parquet files.
+#[derive(Clone, Copy)]
 pub enum FileSizeLimit {
     /// No file size limit is applied. The entire output is written to one parquet file.
     None,
@@ -56,6 +57,7 @@ impl FileSizeLimit {
 
 /// Batches can be limited by either number of rows or the total size of the rows in the batch in
 /// bytes.
+#[derive(Clone, Copy)]
 pub enum BatchSizeLimit {
     Rows(usize),
     Bytes(ByteSize),
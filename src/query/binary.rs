@@ -1,6 +1,8 @@
 use std::marker::PhantomData;
 
 use anyhow::Error;
+use arrow_array::ArrayRef;
+use arrow_schema::DataType as ArrowDataType;
 use odbc_api::buffers::{AnyColumnBufferSlice, BufferDesc};
 use parquet::{
     basic::{Repetition, Type as PhysicalType},
@@ -11,7 +13,7 @@ use parquet::{
 
 use crate::parquet_buffer::{BufferedDataType, ParquetBuffer};
 
-use super::column_strategy::ColumnStrategy;
+use super::{arrow_column::ArrowValue, column_strategy::ColumnStrategy};
 
 pub struct Binary<Pdt> {
     repetition: Repetition,
@@ -86,4 +88,16 @@ where
         }
         Ok(())
     }
+
+    fn copy_odbc_to_arrow(
+        &self,
+        data_type: &ArrowDataType,
+        column_view: AnyColumnBufferSlice,
+    ) -> Result<ArrayRef, Error> {
+        let view = column_view.as_binary().expect(
+            "Invalid Column view type. This is not supposed to happen. Please open a Bug at \
+            https://github.com/pacman82/odbc2parquet/issues.",
+        );
+        <&[u8]>::arrow_array(view.iter(), data_type)
+    }
 }
@@ -1,4 +1,6 @@
 use anyhow::Error;
+use arrow_array::ArrayRef;
+use arrow_schema::DataType as ArrowDataType;
 use odbc_api::{
     buffers::{AnyColumnBufferSlice, BufferDesc},
     Bit,
@@ -12,7 +14,7 @@ use parquet::{
 
 use crate::parquet_buffer::ParquetBuffer;
 
-use super::column_strategy::ColumnStrategy;
+use super::{arrow_column::ArrowValue, column_strategy::ColumnStrategy};
 
 /// Could be the identical strategy on most platform. Yet Rust does not give any guarantees with
 /// regard to the memory layout of a bool, so we do an explicit conversion from `Bit`.
@@ -49,4 +51,13 @@ impl ColumnStrategy for Boolean {
         parquet_buffer.write_optional(column_writer, it.map(|bit| bit.map(|bit| bit.as_bool())))?;
         Ok(())
     }
+
+    fn copy_odbc_to_arrow(
+        &self,
+        data_type: &ArrowDataType,
+        column_view: AnyColumnBufferSlice,
+    ) -> Result<ArrayRef, Error> {
+        let it = column_view.as_nullable_slice::<Bit>().unwrap();
+        bool::arrow_array(it.map(|bit| bit.map(|bit| bit.as_bool())), data_type)
+    }
 }
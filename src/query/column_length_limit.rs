@@ -0,0 +1,90 @@
+use std::collections::HashMap;
+
+use log::warn;
+
+use crate::enum_args::ColumnLengthLimitArgument;
+
+use super::column_strategy::MappingOptions;
+
+/// Maximum element length of text and binary columns, set for individual columns with
+/// `--column-length-limit COLUMN:LENGTH`. Allows raising the limit for a few columns with large
+/// values, without multiplying the memory required for all other columns in the batch.
+#[derive(Default)]
+pub struct ColumnLengthLimits {
+    columns: HashMap<String, usize>,
+}
+
+impl ColumnLengthLimits {
+    /// Global limit used for all columns without a limit of their own, unless specified otherwise.
+    pub const DEFAULT: usize = 4096;
+
+    /// Splits the command line arguments into the global limit and the limits of individual
+    /// columns. If the global limit is passed more than once, the last one wins.
+    pub fn from_arguments(arguments: &[ColumnLengthLimitArgument]) -> (usize, Self) {
+        let mut global = Self::DEFAULT;
+        let mut columns = HashMap::new();
+        for argument in arguments {
+            match &argument.column {
+                Some(column) => {
+                    columns.insert(column.clone(), argument.length);
+                }
+                None => global = argument.length,
+            }
+        }
+        (global, Self { columns })
+    }
+
+    /// Replaces the global length limit with the one for the column, if any.
+    pub fn mapping_options<'a>(
+        &self,
+        name: &str,
+        mut mapping_options: MappingOptions<'a>,
+    ) -> MappingOptions<'a> {
+        if let Some(&length) = self.columns.get(name) {
+            mapping_options.column_length_limit = length;
+        }
+        mapping_options
+    }
+
+    /// Warns about limits for columns, which are not part of the result set. These are likely
+    /// typos.
+    pub fn warn_about_unknown_columns<'a>(&self, column_names: impl Iterator<Item = &'a str>) {
+        let column_names: Vec<_> = column_names.collect();
+        for name in self.columns.keys() {
+            if !column_names.contains(&name.as_str()) {
+                warn!(
+                    "Column length limit for column '{name}' is ignored, since it is not part of \
+                    the result set."
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::enum_args::ColumnLengthLimitArgument;
+
+    use super::ColumnLengthLimits;
+
+    #[test]
+    fn global_and_per_column_length_limits() {
+        let argument = |column: Option<&str>, length| ColumnLengthLimitArgument {
+            column: column.map(str::to_owned),
+            length,
+        };
+
+        let (global, limits) =
+            ColumnLengthLimits::from_arguments(&[argument(Some("description"), 1_000_000)]);
+        assert_eq!(ColumnLengthLimits::DEFAULT, global);
+        assert_eq!(Some(&1_000_000), limits.columns.get("description"));
+
+        let (global, limits) = ColumnLengthLimits::from_arguments(&[
+            argument(None, 100),
+            argument(Some("description"), 10),
+            argument(Some("description"), 20),
+        ]);
+        assert_eq!(100, global);
+        assert_eq!(Some(&20), limits.columns.get("description"));
+    }
+}
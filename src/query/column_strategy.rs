@@ -1,6 +1,8 @@
 use std::{cmp::min, convert::TryInto, num::NonZeroUsize};
 
 use anyhow::Error;
+use arrow_array::ArrayRef;
+use arrow_schema::DataType as ArrowDataType;
 use log::debug;
 use odbc_api::{
     buffers::{AnyColumnBufferSlice, BufferDesc},
@@ -21,19 +23,25 @@ use crate::{
     query::{
         binary::Binary,
         boolean::Boolean,
+        column_length_limit::ColumnLengthLimits,
         date::Date,
         decimal::decimal_fetch_strategy,
         identical::{fetch_identical, fetch_identical_with_logical_type},
+        pg_array::postgres_array_strategy,
+        schema_overrides::SchemaOverrides,
         text::text_strategy,
-        time::time_from_text,
+        time::{is_time_of_day, time_from_text, time_strategy},
         timestamp::timestamp_without_tz,
+        timestamp_precision::TimestampEncoding,
         timestamp_tz::timestamp_tz,
+        type_name::column_type_name,
+        uuid::Uuid,
     },
 };
 
 /// Decisions on how to handle a particular column of the ODBC result set. What buffer to bind to it
 /// for fetching, into what parquet type it is going to be translated and how to translate it from
-/// the odbc buffer elements to aforementioned parquet type.
+/// the odbc buffer elements to aforementioned parquet type, or the equivalent Arrow type.
 pub trait ColumnStrategy {
     /// Parquet column type used in parquet schema
     fn parquet_type(&self, name: &str) -> Type;
@@ -46,6 +54,13 @@ pub trait ColumnStrategy {
         column_writer: &mut ColumnWriter,
         column_view: AnyColumnBufferSlice,
     ) -> Result<(), Error>;
+    /// Convert the contents of an ODBC `AnySlice` into an Arrow array. `data_type` is the Arrow
+    /// type derived from `parquet_type`, so both representations hold the same values.
+    fn copy_odbc_to_arrow(
+        &self,
+        data_type: &ArrowDataType,
+        column_view: AnyColumnBufferSlice,
+    ) -> Result<ArrayRef, Error>;
 }
 
 /// Controls how columns a queried and mapped onto parquet columns
@@ -57,6 +72,15 @@ pub struct MappingOptions<'a> {
     pub avoid_decimal: bool,
     pub driver_does_support_i64: bool,
     pub column_length_limit: usize,
+    /// Encoding of all timestamp columns chosen by the user. `None` picks the unit based on the
+    /// precision reported by the driver.
+    pub timestamp_encoding: Option<TimestampEncoding>,
+    /// Replace `column_length_limit` for individual columns.
+    pub column_length_limits: &'a ColumnLengthLimits,
+    /// Text and binary columns whose values may be larger than this are fetched row by row, rather
+    /// than with bound buffers. `None` means all columns are fetched with bound buffers.
+    pub lob_threshold: Option<usize>,
+    pub schema_overrides: &'a SchemaOverrides,
 }
 
 /// Fetch strategies based on column description and environment arguments `MappingOptions`.
@@ -85,6 +109,10 @@ pub fn strategy_from_column_description(
         avoid_decimal,
         driver_does_support_i64,
         column_length_limit,
+        column_length_limits: _,
+        timestamp_encoding,
+        lob_threshold: _,
+        schema_overrides: _,
     } = mapping_options;
 
     let is_optional = nullability.could_be_nullable();
@@ -106,6 +134,22 @@ pub fn strategy_from_column_description(
         )
     };
 
+    let timestamp_encoding =
+        |precision: i16| TimestampEncoding::new(precision.try_into().unwrap(), timestamp_encoding);
+
+    if db_name == "PostgreSQL" {
+        if let Some(strategy) = postgres_array_strategy(
+            &data_type,
+            cursor,
+            index,
+            repetition,
+            use_utf16,
+            apply_length_limit,
+        )? {
+            return Ok(strategy);
+        }
+    }
+
     let strategy: Box<dyn ColumnStrategy> = match data_type {
         DataType::Float { precision: 0..=24 } | DataType::Real => {
             fetch_identical::<FloatType>(is_optional)
@@ -132,13 +176,28 @@ pub fn strategy_from_column_description(
             decimal_fetch_strategy(
                 is_optional,
                 scale as i32,
-                precision.try_into().unwrap(),
+                precision,
                 avoid_decimal,
                 driver_does_support_i64,
             )
         }
         DataType::Timestamp { precision } => {
-            timestamp_without_tz(repetition, precision.try_into().unwrap())
+            if has_time_zone(db_name, cursor, index) {
+                debug!(
+                    column = name;
+                    "Detected Timestamp type with time zone => Applying instant semantics",
+                );
+                timestamp_tz(
+                    precision.try_into().unwrap(),
+                    timestamp_encoding(precision),
+                    repetition,
+                )
+            } else {
+                timestamp_without_tz(repetition, timestamp_encoding(precision))
+            }
+        }
+        DataType::Time { precision } if is_time_of_day(db_name) => {
+            time_strategy(repetition, precision.try_into().unwrap())
         }
         DataType::BigInt => fetch_identical::<Int64Type>(is_optional),
         DataType::Bit => Box::new(Boolean::new(repetition)),
@@ -205,11 +264,35 @@ pub fn strategy_from_column_description(
                     column = name;
                     "Detected Timestamp type with time zone => Applying instant semantics",
                 );
-                timestamp_tz(precision.try_into().unwrap(), repetition)?
+                timestamp_tz(
+                    precision.try_into().unwrap(),
+                    timestamp_encoding(precision),
+                    repetition,
+                )
             } else {
                 unknown_non_char_type(&data_type, cursor, index, repetition, apply_length_limit)?
             }
         }
+        DataType::Other {
+            data_type: SqlDataType::EXT_GUID,
+            column_size: _,
+            decimal_digits: _,
+        } => Box::new(Uuid::new(repetition)),
+        DataType::Other {
+            data_type: _,
+            column_size: _,
+            decimal_digits: precision,
+        } if has_time_zone(db_name, cursor, index) => {
+            debug!(
+                column = name;
+                "Detected Timestamp type with time zone => Applying instant semantics",
+            );
+            timestamp_tz(
+                precision.try_into().unwrap(),
+                timestamp_encoding(precision),
+                repetition,
+            )
+        }
         DataType::Unknown | DataType::Time { .. } | DataType::Other { .. } => {
             unknown_non_char_type(&data_type, cursor, index, repetition, apply_length_limit)?
         }
@@ -221,6 +304,23 @@ pub fn strategy_from_column_description(
     Ok(strategy)
 }
 
+/// PostgreSQL and Oracle do not report timestamps with time zone as a distinct SQL data type, but
+/// as a naive timestamp or a driver specific type. We tell them apart by the name of the column
+/// type, i.e. `timestamptz` or `TIMESTAMP(6) WITH TIME ZONE`. Oracle's `WITH LOCAL TIME ZONE` is
+/// converted into the session time zone and carries no offset.
+fn has_time_zone(db_name: &str, cursor: &mut impl ResultSetMetadata, index: i16) -> bool {
+    if !matches!(db_name, "PostgreSQL" | "Oracle") {
+        return false;
+    }
+    let Some(type_name) = column_type_name(cursor, index.try_into().unwrap()) else {
+        return false;
+    };
+    match db_name {
+        "PostgreSQL" => type_name == "timestamptz",
+        _ => type_name.to_uppercase().contains("WITH TIME ZONE"),
+    }
+}
+
 fn unknown_non_char_type(
     data_type: &DataType,
     cursor: &mut impl ResultSetMetadata,
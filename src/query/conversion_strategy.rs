@@ -1,18 +1,28 @@
 use anyhow::{anyhow, bail, Context, Error};
+use arrow_array::ArrayRef;
+use arrow_schema::{DataType as ArrowDataType, Field};
 use log::{debug, info};
-use odbc_api::{buffers::ColumnarDynBuffer, ResultSetMetadata};
+use odbc_api::{
+    buffers::{BufferDesc, ColumnarDynBuffer},
+    DataType, ResultSetMetadata,
+};
 use parquet::{
     file::writer::SerializedColumnWriter,
     schema::types::{Type, TypePtr},
 };
-use std::sync::Arc;
+use std::{path::PathBuf, sync::Arc};
 
 use crate::parquet_buffer::ParquetBuffer;
 
 use super::{
     column_strategy::{strategy_from_column_description, ColumnStrategy, MappingOptions},
     fetch_batch::FetchBatch,
-    parquet_writer::ParquetOutput,
+    lob::is_lob,
+    output::Output,
+    partition::{partition_dir_name, partition_value},
+    staged_rows::StagedRows,
+    text_value::{text_values, TextValue},
+    watermark::Watermark,
 };
 
 /// Contains the decisions of how to fetch each columns of a table from an ODBC data source and copy
@@ -20,12 +30,51 @@ use super::{
 /// and in what these columns are transformed.
 pub struct ConversionStrategy {
     columns: Vec<ColumnInfo>,
+    /// Indices of the columns the output is partitioned by. Their values are encoded in the
+    /// directory names, rather than written into the parquet files.
+    partition_columns: Vec<usize>,
+    /// Indices of the columns written into the parquet files. All columns, which are not partition
+    /// columns.
+    file_columns: Vec<usize>,
+    /// Index of the column whose maximum value is tracked for incremental extraction.
+    watermark_column: Option<usize>,
+    /// Relational type of each column, after applying schema overrides.
+    data_types: Vec<DataType>,
+    /// Indices of text and binary columns, whose values may exceed `--lob-threshold`. If there are
+    /// any, the result set is fetched row by row.
+    lob_columns: Vec<usize>,
     parquet_schema: TypePtr,
 }
 
 /// Name, ColumnStrategy
 type ColumnInfo = (String, Box<dyn ColumnStrategy>);
 
+/// State carried over between batches written into the same output.
+pub struct WriteProgress {
+    /// Number of batches written so far.
+    num_batch: u32,
+    /// Count the number of total rows fetched so far for logging. This should be identical to
+    /// `num_batch * batch_size_row + num_rows`, as long as all batches are fetched from the same
+    /// cursor.
+    total_rows_fetched: usize,
+    /// Reused between batches to avoid allocations.
+    pb: ParquetBuffer,
+}
+
+impl WriteProgress {
+    pub fn new(max_batch_size_in_rows: usize) -> Self {
+        Self {
+            num_batch: 0,
+            total_rows_fetched: 0,
+            pb: ParquetBuffer::new(max_batch_size_in_rows),
+        }
+    }
+
+    pub fn total_rows_fetched(&self) -> usize {
+        self.total_rows_fetched
+    }
+}
+
 impl ConversionStrategy {
     pub fn new(
         cursor: &mut impl ResultSetMetadata,
@@ -34,17 +83,19 @@ impl ConversionStrategy {
         let num_cols = cursor.num_result_cols()?;
 
         let mut columns = Vec::new();
+        let mut data_types = Vec::new();
+        let mut lob_columns = Vec::new();
 
         for index in 1..(num_cols + 1) {
             let name = cursor.col_name(index as u16)?;
-            let nullability = cursor.col_nullability(index as u16)?;
-            let data_type = cursor.col_data_type(index as u16)?;
+            let reported_nullability = cursor.col_nullability(index as u16)?;
+            let reported_data_type = cursor.col_data_type(index as u16)?;
 
             debug!(
                 index = index,
                 name:display = name,
-                relational_type:? = data_type,
-                nullability:? = nullability;
+                relational_type:? = reported_data_type,
+                nullability:? = reported_nullability;
                 "ODBC column description",
             );
 
@@ -55,38 +106,111 @@ impl ConversionStrategy {
                 name
             };
 
+            let schema_overrides = mapping_options.schema_overrides;
+            let (data_type, nullability) =
+                schema_overrides.apply(&name, reported_data_type, reported_nullability)?;
+            if (data_type, nullability) != (reported_data_type, reported_nullability) {
+                debug!(
+                    name:display = name,
+                    relational_type:? = data_type,
+                    nullability:? = nullability;
+                    "Overridden column description",
+                );
+            }
+
+            if let Some(threshold) = mapping_options.lob_threshold {
+                if is_lob(&data_type, threshold) {
+                    debug!(name:display = name; "Column is fetched row by row");
+                    lob_columns.push(columns.len());
+                }
+            }
+
             let column_fetch_strategy = strategy_from_column_description(
                 &name,
                 data_type,
                 nullability,
-                mapping_options,
+                schema_overrides.mapping_options(
+                    &name,
+                    mapping_options
+                        .column_length_limits
+                        .mapping_options(&name, mapping_options),
+                ),
                 cursor,
                 index,
             )?;
             columns.push((name, column_fetch_strategy));
+            data_types.push(data_type);
         }
 
         if columns.is_empty() {
             bail!("Resulting parquet file would not have any columns!")
         }
+        mapping_options
+            .schema_overrides
+            .warn_about_unknown_columns(columns.iter().map(|(name, _strategy)| name.as_str()));
+        mapping_options
+            .column_length_limits
+            .warn_about_unknown_columns(columns.iter().map(|(name, _strategy)| name.as_str()));
 
-        let fields = columns
-            .iter()
-            .map(|(name, s)| Arc::new(s.parquet_type(name)))
-            .collect();
-        let parquet_schema = Arc::new(
-            Type::group_type_builder("schema")
-                .with_fields(fields)
-                .build()
-                .unwrap(),
-        );
+        let file_columns: Vec<usize> = (0..columns.len()).collect();
+        let parquet_schema = parquet_schema(&columns, &file_columns);
 
         Ok(ConversionStrategy {
             columns,
+            partition_columns: Vec::new(),
+            file_columns,
+            watermark_column: None,
+            data_types,
+            lob_columns,
             parquet_schema,
         })
     }
 
+    /// Partition the output by the values of the columns with the given names. These columns are
+    /// removed from the parquet schema, since their values are encoded in the directory names.
+    pub fn partition_by(&mut self, column_names: &[String]) -> Result<(), Error> {
+        self.partition_columns = column_names
+            .iter()
+            .map(|partition_name| {
+                self.columns
+                    .iter()
+                    .position(|(name, _strategy)| name == partition_name)
+                    .ok_or_else(|| {
+                        anyhow!(
+                            "Partition column '{partition_name}' is not part of the result set."
+                        )
+                    })
+            })
+            .collect::<Result<_, _>>()?;
+        self.file_columns = (0..self.columns.len())
+            .filter(|index| !self.partition_columns.contains(index))
+            .collect();
+        if self.file_columns.is_empty() {
+            bail!("Resulting parquet file would not have any columns!")
+        }
+        self.parquet_schema = parquet_schema(&self.columns, &self.file_columns);
+        Ok(())
+    }
+
+    /// Track the largest value of the column with the given name, so it can be used as watermark
+    /// for the next incremental extraction.
+    pub fn track_watermark(&mut self, column_name: &str) -> Result<(), Error> {
+        let index = self
+            .columns
+            .iter()
+            .position(|(name, _strategy)| name == column_name)
+            .ok_or_else(|| {
+                anyhow!("Incremental column '{column_name}' is not part of the result set.")
+            })?;
+        Watermark::check_column(
+            column_name,
+            &self.data_types[index],
+            self.columns[index].1.buffer_desc(),
+        )?;
+        self.watermark_column = Some(index);
+        Ok(())
+    }
+
     /// Size of a single fetch buffer per row
     pub fn fetch_buffer_size_per_row(&self) -> usize {
         self.columns
@@ -95,6 +219,26 @@ impl ConversionStrategy {
             .sum()
     }
 
+    /// Names of all result set columns and the buffers used to fetch them.
+    pub fn buffer_descs(&self) -> impl Iterator<Item = (&str, BufferDesc)> {
+        self.columns
+            .iter()
+            .map(|(name, strategy)| (name.as_str(), strategy.buffer_desc()))
+    }
+
+    /// `true` if the result set contains columns with values larger than `--lob-threshold`. These
+    /// can not be bound to a fetch buffer, so the entire result set must be fetched row by row.
+    pub fn fetch_row_by_row(&self) -> bool {
+        !self.lob_columns.is_empty()
+    }
+
+    /// Names of the columns whose values may exceed `--lob-threshold`.
+    pub fn lob_column_names(&self) -> impl Iterator<Item = &str> {
+        self.lob_columns
+            .iter()
+            .map(|&index| self.columns[index].0.as_str())
+    }
+
     pub fn allocate_fetch_buffer(&self, batch_size_row: usize) -> ColumnarDynBuffer {
         ColumnarDynBuffer::from_descs(
             batch_size_row,
@@ -108,41 +252,108 @@ impl ConversionStrategy {
         self.parquet_schema.clone()
     }
 
-    pub fn block_cursor_to_parquet(
+    /// Writes all batches of the cursor to parquet. Returns the largest value of the watermark
+    /// column, if one is tracked and any rows have been fetched. It is only returned once the
+    /// output has been closed successfully.
+    pub fn block_cursor_to_parquet<C>(
         &self,
-        mut fetch_strategy: Box<dyn FetchBatch>,
-        mut writer: Box<dyn ParquetOutput>,
-    ) -> Result<(), Error> {
-        let mut num_batch = 0;
-        // Count the number of total rows fetched so far for logging. This should be identical to
-        // `num_batch * batch_size_row + num_rows`.
-        let mut total_rows_fetched = 0;
+        fetch_strategy: &mut dyn FetchBatch<C>,
+        mut writer: Box<dyn Output>,
+    ) -> Result<Option<Watermark>, Error> {
+        let mut progress = WriteProgress::new(fetch_strategy.max_batch_size_in_rows());
+        let watermark = self.append_batches(fetch_strategy, &mut writer, &mut progress)?;
+        writer.close_box()?;
+        info!(total_rows_fetched = progress.total_rows_fetched; "Done");
+        Ok(watermark)
+    }
 
-        let mut pb = ParquetBuffer::new(fetch_strategy.max_batch_size_in_rows());
+    /// Writes all batches of the cursor, without closing the writer.
+    fn append_batches<C>(
+        &self,
+        fetch_strategy: &mut dyn FetchBatch<C>,
+        writer: &mut Box<dyn Output>,
+        progress: &mut WriteProgress,
+    ) -> Result<Option<Watermark>, Error> {
+        let WriteProgress {
+            num_batch,
+            total_rows_fetched,
+            pb,
+        } = progress;
+        let mut watermark = None;
 
         while let Some(buffer) = fetch_strategy
             .next_batch()
             .map_err(|e| self.translate_fetch_error(e))?
         {
-            num_batch += 1;
+            *num_batch += 1;
             let num_rows = buffer.num_rows();
-            total_rows_fetched += num_rows;
+            *total_rows_fetched += num_rows;
             debug!(
-                num_batch = num_batch,
+                num_batch = *num_batch,
                 num_rows = num_rows,
-                total_row_count=total_rows_fetched;
+                total_row_count = *total_rows_fetched;
                 "Fetched batch",
             );
-            self.write_batch(&mut writer, num_batch, buffer, &mut pb)?;
+            if let Some(index) = self.watermark_column {
+                let desc = self.columns[index].1.buffer_desc();
+                let batch_max = Watermark::batch_max(buffer.column(index), desc)?;
+                watermark = Watermark::max(watermark, batch_max);
+            }
+            self.write_batch(writer, *num_batch, buffer, pb)?;
+        }
+        Ok(watermark)
+    }
+
+    /// Copies all batches of the cursor into `staged`, rather than writing them directly. Staged
+    /// rows are written each time they fill a complete batch. This allows for writing the results
+    /// of many small result sets in batches of full size.
+    pub fn stage_batches<C>(
+        &self,
+        fetch_strategy: &mut dyn FetchBatch<C>,
+        staged: &mut StagedRows,
+        writer: &mut Box<dyn Output>,
+        progress: &mut WriteProgress,
+    ) -> Result<(), Error> {
+        while let Some(buffer) = fetch_strategy
+            .next_batch()
+            .map_err(|e| self.translate_fetch_error(e))?
+        {
+            let num_rows = buffer.num_rows();
+            progress.total_rows_fetched += num_rows;
+            debug!(
+                num_rows = num_rows,
+                total_row_count = progress.total_rows_fetched;
+                "Fetched batch",
+            );
+            let mut offset = 0;
+            while offset != num_rows {
+                offset += staged.append(buffer, offset);
+                if staged.is_full() {
+                    self.write_staged(staged, writer, progress)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes the rows in `staged` as one batch, if there are any.
+    pub fn write_staged(
+        &self,
+        staged: &mut StagedRows,
+        writer: &mut Box<dyn Output>,
+        progress: &mut WriteProgress,
+    ) -> Result<(), Error> {
+        if let Some(batch) = staged.take_batch() {
+            progress.num_batch += 1;
+            debug!(num_batch = progress.num_batch, num_rows = batch.num_rows(); "Writing batch");
+            self.write_batch(writer, progress.num_batch, batch, &mut progress.pb)?;
         }
-        writer.close_box()?;
-        info!(total_rows_fetched=total_rows_fetched; "Done");
         Ok(())
     }
 
     fn write_batch(
         &self,
-        writer: &mut Box<dyn ParquetOutput>,
+        writer: &mut Box<dyn Output>,
         num_batch: u32,
         buffer: &ColumnarDynBuffer,
         pb: &mut ParquetBuffer,
@@ -154,9 +365,11 @@ impl ConversionStrategy {
             buffer,
             conversion_buffer: pb,
             columns: &self.columns,
+            file_columns: &self.file_columns,
+            partition_columns: &self.partition_columns,
         };
 
-        writer.write_row_group(num_batch, column_exporter)?;
+        writer.write_batch(num_batch, column_exporter)?;
         Ok(())
     }
 
@@ -192,7 +405,9 @@ impl ConversionStrategy {
                 anyhow!(format!(
                     "A field exceeds the maximum element length of a column buffer. You can use \
                     the `--column-length-limit` option to increase the maximum element size of \
-                    columns. {indicator_msg} The error occurred for column {column_name}."
+                    columns. {indicator_msg} The error occurred for column {column_name}. \
+                    Alternatively `--lob-threshold` fetches large values row by row, without any \
+                    length limit."
                 ))
             }
             other => other.into(),
@@ -200,19 +415,95 @@ impl ConversionStrategy {
     }
 }
 
-/// Exposes the contents from a fetch buffer column by column to a parquet serializer
+/// Parquet schema consisting of the columns with the given indices.
+fn parquet_schema(columns: &[ColumnInfo], indices: &[usize]) -> TypePtr {
+    let fields = indices
+        .iter()
+        .map(|&index| {
+            let (name, s) = &columns[index];
+            Arc::new(s.parquet_type(name))
+        })
+        .collect();
+    Arc::new(
+        Type::group_type_builder("schema")
+            .with_fields(fields)
+            .build()
+            .unwrap(),
+    )
+}
+
+/// Exposes the contents from a fetch buffer column by column to a parquet serializer, or any
+/// other output format
 pub struct ColumnExporter<'a> {
     buffer: &'a ColumnarDynBuffer,
     conversion_buffer: &'a mut ParquetBuffer,
     columns: &'a [(String, Box<dyn ColumnStrategy>)],
+    file_columns: &'a [usize],
+    partition_columns: &'a [usize],
 }
 
 impl ColumnExporter<'_> {
+    /// Relative path of the hive style partition directory for each row in the batch. E.g.
+    /// `region=EU/year=2024`.
+    pub fn partition_dirs(&self) -> Result<Vec<PathBuf>, Error> {
+        let mut dirs = vec![PathBuf::new(); self.buffer.num_rows()];
+        for &index in self.partition_columns {
+            let (name, strategy) = &self.columns[index];
+            let desc = strategy.buffer_desc();
+            let odbc_column = self.buffer.column(index);
+            for (row, dir) in dirs.iter_mut().enumerate() {
+                let value = partition_value(odbc_column, desc, row)?;
+                dir.push(partition_dir_name(name, value.as_deref()));
+            }
+        }
+        Ok(dirs)
+    }
+
+    /// Only export the rows with the given indices. Indices must be sorted.
+    pub fn select_rows(&mut self, rows: Vec<usize>) {
+        self.conversion_buffer.select_rows(rows);
+    }
+
+    /// Number of rows in the batch.
+    pub fn num_rows(&self) -> usize {
+        self.buffer.num_rows()
+    }
+
+    /// Values of the nth column written to the output, rendered as text. `field` is the Arrow
+    /// representation of the column. Text is rendered from the converted values, so text output
+    /// formats show the same values as parquet files.
+    pub fn text_column(
+        &self,
+        col_index: usize,
+        field: &Field,
+    ) -> Result<Vec<Option<TextValue>>, Error> {
+        let array = self.arrow_column(col_index, field.data_type())?;
+        text_values(&array, field).with_context(|| {
+            let col_name = &self.columns[self.file_columns[col_index]].0;
+            format!("Failed to render column '{col_name}' as text.")
+        })
+    }
+
+    /// Values of the nth column written to the output as an Arrow array of type `data_type`.
+    pub fn arrow_column(
+        &self,
+        col_index: usize,
+        data_type: &ArrowDataType,
+    ) -> Result<ArrayRef, Error> {
+        let col_index = self.file_columns[col_index];
+        let (col_name, strategy) = &self.columns[col_index];
+        let odbc_column = self.buffer.column(col_index);
+        strategy
+            .copy_odbc_to_arrow(data_type, odbc_column)
+            .with_context(|| format!("Failed to convert column '{col_name}' to Arrow."))
+    }
+
     pub fn export_nth_column(
         &mut self,
         col_index: usize,
         column_writer: &mut SerializedColumnWriter,
     ) -> Result<(), Error> {
+        let col_index = self.file_columns[col_index];
         let col_name = &self.columns[col_index].0;
         debug!(index = col_index, name:display =col_name; "Writing column");
         let odbc_column = self.buffer.column(col_index);
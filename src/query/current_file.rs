@@ -2,14 +2,15 @@ use std::{fs::File, io::Write, path::PathBuf, sync::Arc};
 
 use anyhow::{Context, Error};
 use bytesize::ByteSize;
+use chrono::{SecondsFormat, Utc};
 use log::info;
 use parquet::{
-    file::{properties::WriterProperties, writer::SerializedFileWriter},
+    file::{metadata::KeyValue, properties::WriterProperties, writer::SerializedFileWriter},
     schema::types::Type,
 };
 use tempfile::TempPath;
 
-use super::conversion_strategy::ColumnExporter;
+use super::{conversion_strategy::ColumnExporter, manifest::Manifest};
 
 pub struct CurrentFile {
     writer: SerializedFileWriter<Box<dyn Write + Send>>,
@@ -19,6 +20,8 @@ pub struct CurrentFile {
     file_size: ByteSize,
     /// Keep track of the total number of rows written into the file so far.
     total_num_rows: u64,
+    /// Number of row groups written into the file so far.
+    num_row_groups: u32,
 }
 
 impl CurrentFile {
@@ -41,12 +44,13 @@ impl CurrentFile {
             path,
             file_size: ByteSize::b(0),
             total_num_rows: 0,
+            num_row_groups: 0,
         })
     }
 
     pub fn write_row_group(
         &mut self,
-        mut column_exporter: ColumnExporter,
+        column_exporter: &mut ColumnExporter,
     ) -> Result<ByteSize, Error> {
         let mut col_index = 0;
         let mut row_group_writer = self.writer.next_row_group()?;
@@ -61,12 +65,14 @@ impl CurrentFile {
         self.file_size += ByteSize::b(metadata.compressed_size().try_into().unwrap());
         let rows_in_row_group: u64 = metadata.num_rows().try_into().unwrap();
         self.total_num_rows += rows_in_row_group;
+        self.num_row_groups += 1;
         Ok(self.file_size)
     }
 
     /// Writes metadata at the end and persists the file. Called if we do not want to continue
     /// writing batches into this file.
-    pub fn finalize(self) -> Result<(), Error> {
+    pub fn finalize(mut self, manifest: Option<&Manifest>) -> Result<(), Error> {
+        append_completion_metadata(&mut self.writer, self.total_num_rows);
         self.writer.close()?;
         // Do not persist empty files
         let path = self.path.keep()?;
@@ -76,6 +82,25 @@ impl CurrentFile {
             path:display = path.to_string_lossy();
             "File complete"
         );
+        if let Some(manifest) = manifest {
+            manifest.record(&path, self.total_num_rows, self.num_row_groups)?;
+        }
         Ok(())
     }
 }
+
+/// Records when the file has been completed and how many rows it holds in its key value metadata.
+/// Other provenance information is known upfront and already part of the writer properties.
+pub fn append_completion_metadata(
+    writer: &mut SerializedFileWriter<Box<dyn Write + Send>>,
+    total_num_rows: u64,
+) {
+    writer.append_key_value_metadata(KeyValue::new(
+        "odbc2parquet.end_time".to_owned(),
+        Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+    ));
+    writer.append_key_value_metadata(KeyValue::new(
+        "odbc2parquet.row_count".to_owned(),
+        total_num_rows.to_string(),
+    ));
+}
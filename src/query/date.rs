@@ -1,4 +1,6 @@
 use anyhow::Error;
+use arrow_array::ArrayRef;
+use arrow_schema::DataType as ArrowDataType;
 use chrono::NaiveDate;
 use odbc_api::{
     buffers::{AnyColumnBufferSlice, BufferDesc},
@@ -13,7 +15,7 @@ use parquet::{
 
 use crate::parquet_buffer::ParquetBuffer;
 
-use super::column_strategy::ColumnStrategy;
+use super::{arrow_column::ArrowValue, column_strategy::ColumnStrategy};
 
 pub struct Date {
     repetition: Repetition,
@@ -49,6 +51,15 @@ impl ColumnStrategy for Date {
         parquet_buffer.write_optional(column_writer, it.map(|date| date.map(days_since_epoch)))?;
         Ok(())
     }
+
+    fn copy_odbc_to_arrow(
+        &self,
+        data_type: &ArrowDataType,
+        column_view: AnyColumnBufferSlice,
+    ) -> Result<ArrayRef, Error> {
+        let it = column_view.as_nullable_slice().unwrap();
+        i32::arrow_array(it.map(|date| date.map(days_since_epoch)), data_type)
+    }
 }
 
 /// Transform date to days since unix epoch as i32
@@ -1,7 +1,11 @@
-use std::{convert::TryInto, marker::PhantomData};
+use std::{convert::TryInto, iter::repeat, marker::PhantomData};
 
-use anyhow::Error;
+use anyhow::{bail, Error};
+use arrow_array::ArrayRef;
+use arrow_buffer::i256;
+use arrow_schema::DataType as ArrowDataType;
 use atoi::FromRadix10Signed;
+use num_bigint::{BigInt, Sign};
 use odbc_api::{
     buffers::{AnyColumnBufferSlice, BufferDesc},
     decimal_text_to_i128, decimal_text_to_i32, decimal_text_to_i64, DataType,
@@ -9,21 +13,25 @@ use odbc_api::{
 use parquet::{
     basic::{DecimalType, IntType, LogicalType, Repetition, Type as PhysicalType},
     column::writer::ColumnWriter,
-    data_type::{DataType as ParquetDataType, FixedLenByteArrayType, Int32Type, Int64Type},
+    data_type::{
+        ByteArray, DataType as ParquetDataType, FixedLenByteArray, FixedLenByteArrayType,
+        Int32Type, Int64Type,
+    },
     schema::types::Type,
 };
 
 use crate::parquet_buffer::{BufferedDataType, ParquetBuffer};
 
 use super::{
-    column_strategy::ColumnStrategy, identical::fetch_identical_with_logical_type, text::Utf8,
+    arrow_column::ArrowValue, column_strategy::ColumnStrategy,
+    identical::fetch_identical_with_logical_type, text::Utf8,
 };
 
 /// Choose how to fetch decimals from ODBC and store them in parquet
 pub fn decimal_fetch_strategy(
     is_optional: bool,
     scale: i32,
-    precision: u8,
+    precision: usize,
     avoid_decimal: bool,
     driver_does_support_i64: bool,
 ) -> Box<dyn ColumnStrategy> {
@@ -35,7 +43,7 @@ pub fn decimal_fetch_strategy(
 
     if avoid_decimal && scale != 0 {
         // Precision + sign and radix character
-        let length = precision as usize + 2;
+        let length = precision + 2;
         return Box::new(Utf8::with_bytes_length(repetition, length));
     }
 
@@ -121,21 +129,12 @@ pub fn decimal_fetch_strategy(
                 }),
             ))
         }
-        (0..=38, _) => Box::new(DecimalAsBinary::new(repetition, scale, precision)),
-        (_, _) => {
-            let length = odbc_api::DataType::Decimal {
-                precision: precision as usize,
-                scale: scale.try_into().unwrap(),
-            }
-            .display_size()
-            .unwrap();
-            Box::new(Utf8::with_bytes_length(repetition, length.get()))
-        }
+        (_, _) => Box::new(DecimalAsBinary::new(repetition, scale, precision)),
     }
 }
 
 struct DecimalTextToInteger<Pdt> {
-    precision: u8,
+    precision: usize,
     scale: i32,
     repetition: Repetition,
     logical_type: LogicalType,
@@ -143,7 +142,12 @@ struct DecimalTextToInteger<Pdt> {
 }
 
 impl<Pdt> DecimalTextToInteger<Pdt> {
-    fn new(precision: u8, scale: i32, repetition: Repetition, logical_type: LogicalType) -> Self {
+    fn new(
+        precision: usize,
+        scale: i32,
+        repetition: Repetition,
+        logical_type: LogicalType,
+    ) -> Self {
         Self {
             precision,
             scale,
@@ -157,7 +161,7 @@ impl<Pdt> DecimalTextToInteger<Pdt> {
 impl<Pdt> ColumnStrategy for DecimalTextToInteger<Pdt>
 where
     Pdt: ParquetDataType,
-    Pdt::T: FromRadix10Signed + BufferedDataType + FromDecimalTextRepresentation,
+    Pdt::T: FromRadix10Signed + BufferedDataType + FromDecimalTextRepresentation + ArrowValue,
 {
     fn parquet_type(&self, name: &str) -> Type {
         Type::primitive_type_builder(name, Pdt::get_physical_type())
@@ -174,7 +178,7 @@ where
 
         // Precision + 2. (One byte for the radix character and another for the sign)
         let max_str_len = DataType::Decimal {
-            precision: self.precision as usize,
+            precision: self.precision,
             scale: self.scale.try_into().unwrap(),
         }
         .display_size()
@@ -202,6 +206,24 @@ where
             }),
         )
     }
+
+    fn copy_odbc_to_arrow(
+        &self,
+        data_type: &ArrowDataType,
+        column_view: AnyColumnBufferSlice,
+    ) -> Result<ArrayRef, Error> {
+        let view = column_view.as_text().expect(
+            "Invalid Column view type. This is not supposed to happen. Please open a Bug at \
+            https://github.com/pacman82/odbc2parquet/issues.",
+        );
+        let scale = self.scale as usize;
+        Pdt::T::arrow_array(
+            view.iter().map(|value| {
+                value.map(|text| Pdt::T::from_decimal_text_representation(text, scale))
+            }),
+            data_type,
+        )
+    }
 }
 
 trait FromDecimalTextRepresentation {
@@ -220,16 +242,18 @@ impl FromDecimalTextRepresentation for i64 {
     }
 }
 
-/// Strategy for fetching decimal values which can not be represented as either 32Bit or 64Bit
+/// Strategy for fetching decimal values which can not be represented as either 32Bit or 64Bit.
+/// Decimals with more than 38 digits do not fit into 128Bit either and are converted using
+/// arbitrary precision integers.
 struct DecimalAsBinary {
     repetition: Repetition,
     scale: i32,
-    precision: u8,
+    precision: usize,
     length_in_bytes: usize,
 }
 
 impl DecimalAsBinary {
-    pub fn new(repetition: Repetition, scale: i32, precision: u8) -> Self {
+    pub fn new(repetition: Repetition, scale: i32, precision: usize) -> Self {
         // Length of the two's complement.
         let num_binary_digits = precision as f64 * 10f64.log2();
         // Plus one bit for the sign (+/-)
@@ -253,7 +277,7 @@ impl ColumnStrategy for DecimalAsBinary {
                 scale: self.scale,
                 precision: self.precision as i32,
             })))
-            .with_precision(self.precision.into())
+            .with_precision(self.precision as i32)
             .with_scale(self.scale)
             .with_repetition(self.repetition)
             .build()
@@ -263,7 +287,7 @@ impl ColumnStrategy for DecimalAsBinary {
     fn buffer_desc(&self) -> BufferDesc {
         // Precision + 2. (One byte for the radix character and another for the sign)
         let max_str_len = DataType::Decimal {
-            precision: self.precision as usize,
+            precision: self.precision,
             scale: self.scale.try_into().unwrap(),
         }
         .display_size()
@@ -286,6 +310,37 @@ impl ColumnStrategy for DecimalAsBinary {
             self.scale,
         )
     }
+
+    fn copy_odbc_to_arrow(
+        &self,
+        data_type: &ArrowDataType,
+        column_view: AnyColumnBufferSlice,
+    ) -> Result<ArrayRef, Error> {
+        let view = column_view.as_text().expect(
+            "Invalid Column view type. This is not supposed to happen. Please open a Bug at \
+            https://github.com/pacman82/odbc2parquet/issues.",
+        );
+        let scale = self.scale as usize;
+        if self.length_in_bytes <= 16 {
+            i128::arrow_array(
+                view.iter()
+                    .map(|field| field.map(|text| decimal_text_to_i128(text, scale))),
+                data_type,
+            )
+        } else {
+            i256::try_arrow_array(
+                view.iter().map(|field| {
+                    field
+                        .map(|text| {
+                            let n = decimal_text_to_big_int(text, scale);
+                            big_int_to_i256(&n, self.length_in_bytes)
+                        })
+                        .transpose()
+                }),
+                data_type,
+            )
+        }
+    }
 }
 
 fn write_decimal_col(
@@ -303,12 +358,127 @@ fn write_decimal_col(
 
     let scale = scale as usize;
 
-    parquet_buffer.write_twos_complement_i128(
-        column_writer,
-        view.iter()
-            .map(|field| field.map(|text| decimal_text_to_i128(text, scale))),
-        length_in_bytes,
-    )?;
+    if length_in_bytes <= 16 {
+        parquet_buffer.write_twos_complement_i128(
+            column_writer,
+            view.iter()
+                .map(|field| field.map(|text| decimal_text_to_i128(text, scale))),
+            length_in_bytes,
+        )?;
+    } else {
+        parquet_buffer.write_optional_fallible(
+            column_writer,
+            view.iter().map(|field| {
+                field
+                    .map(|text| {
+                        let n = decimal_text_to_big_int(text, scale);
+                        twos_complement_big_int(&n, length_in_bytes)
+                    })
+                    .transpose()
+            }),
+        )?;
+    }
 
     Ok(())
 }
+
+/// Same as [`decimal_text_to_i128`], but for decimals with more than 38 digits. Any non digit
+/// character is regarded as the radix character, with the exception of a `+` or `-` at the
+/// beginning of the text. Digits of the fraction beyond `scale` are truncated.
+fn decimal_text_to_big_int(text: &[u8], scale: usize) -> BigInt {
+    let (sign, text) = match text.split_first() {
+        Some((b'-', rest)) => (Sign::Minus, rest),
+        Some((b'+', rest)) => (Sign::Plus, rest),
+        _ => (Sign::Plus, text),
+    };
+    let num_digits_high = text.iter().take_while(|c| c.is_ascii_digit()).count();
+    let (high, low) = text.split_at(num_digits_high);
+    let low = low
+        .get(1..)
+        .unwrap_or_default()
+        .iter()
+        .take_while(|c| c.is_ascii_digit());
+    // Integer part, followed by the fraction padded with zeroes to scale
+    let digits: Vec<u8> = high
+        .iter()
+        .chain(low.chain(repeat(&b'0')).take(scale))
+        .map(|c| c - b'0')
+        .collect();
+    BigInt::from_radix_be(sign, &digits, 10).unwrap()
+}
+
+/// Big endian two's complement of `n`, sign extended to `length_in_bytes`.
+fn twos_complement_big_int(n: &BigInt, length_in_bytes: usize) -> Result<FixedLenByteArray, Error> {
+    let bytes = n.to_signed_bytes_be();
+    if bytes.len() > length_in_bytes {
+        bail!("Decimal value {n} does not fit into the precision of its column.")
+    }
+    let fill = if n.sign() == Sign::Minus { 0xff } else { 0 };
+    let mut out = vec![fill; length_in_bytes - bytes.len()];
+    out.extend_from_slice(&bytes);
+    // Vec<u8> -> ByteArray -> FixedLenByteArray
+    let out: ByteArray = out.into();
+    Ok(out.into())
+}
+
+/// Same as [`twos_complement_big_int`], but sign extended to the 256 Bit integer Arrow uses for
+/// decimals with more than 38 digits.
+fn big_int_to_i256(n: &BigInt, length_in_bytes: usize) -> Result<i256, Error> {
+    let bytes = twos_complement_big_int(n, length_in_bytes)?;
+    let fill = if n.sign() == Sign::Minus { 0xff } else { 0 };
+    let mut out = [fill; 32];
+    out[32 - length_in_bytes..].copy_from_slice(bytes.data());
+    Ok(i256::from_be_bytes(out))
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow_buffer::i256;
+    use num_bigint::BigInt;
+
+    use super::{big_int_to_i256, decimal_text_to_big_int, twos_complement_big_int};
+
+    fn big_int(text: &str) -> BigInt {
+        text.parse().unwrap()
+    }
+
+    #[test]
+    fn decimal_text_with_more_than_38_digits_to_big_int() {
+        assert_eq!(
+            big_int("123456789012345678901234567890123456789012345"),
+            decimal_text_to_big_int(b"12345678901234567890123456789012345678901.2345", 4)
+        );
+        assert_eq!(big_int("-1234500"), decimal_text_to_big_int(b"-123.45", 4));
+        // Fraction beyond scale is truncated, radix character may differ
+        assert_eq!(big_int("12345"), decimal_text_to_big_int(b"+123,456", 2));
+        assert_eq!(big_int("-5"), decimal_text_to_big_int(b"-.5", 1));
+        assert_eq!(big_int("0"), decimal_text_to_big_int(b"0", 0));
+    }
+
+    #[test]
+    fn sign_extend_twos_complement_of_big_int() {
+        assert_eq!(
+            &[0, 0, 1, 0][..],
+            twos_complement_big_int(&big_int("256"), 4).unwrap().data()
+        );
+        assert_eq!(
+            &[0xff, 0xff, 0xff, 0x00][..],
+            twos_complement_big_int(&big_int("-256"), 4).unwrap().data()
+        );
+        assert!(twos_complement_big_int(&big_int("2147483648"), 4).is_err());
+    }
+
+    #[test]
+    fn big_int_to_arrow_decimal() {
+        assert_eq!(
+            i256::from_i128(-256),
+            big_int_to_i256(&big_int("-256"), 17).unwrap()
+        );
+        let n = big_int("12345678901234567890123456789012345678901234567890");
+        assert_eq!(
+            i256::from_string("12345678901234567890123456789012345678901234567890").unwrap(),
+            big_int_to_i256(&n, 21).unwrap()
+        );
+        assert!(big_int_to_i256(&n, 17).is_err());
+    }
+}
@@ -0,0 +1,77 @@
+use std::io::{stdout, Write};
+
+use anyhow::{bail, Error};
+use odbc_api::{Connection, ResultSetMetadata};
+use parquet::schema::printer::print_schema;
+
+use crate::parquet_buffer::ParquetBuffer;
+
+use super::{
+    batch_size_limit::BatchSizeLimit, column_strategy::MappingOptions,
+    conversion_strategy::ConversionStrategy,
+};
+
+/// Prepares the query without executing it and prints the decisions an extraction would be based
+/// on: The parquet schema, the buffer used to fetch each column, the memory required per row and
+/// the resulting batch size.
+pub fn dry_run(
+    conn: &Connection,
+    query: &str,
+    mapping_options: MappingOptions,
+    partition_by: &[String],
+    batch_size: BatchSizeLimit,
+    concurrent_fetching: bool,
+) -> Result<(), Error> {
+    let mut prepared = conn.prepare(query)?;
+    if prepared.num_result_cols()? == 0 {
+        bail!("Query does not return a result set.")
+    }
+    let mut table_strategy = ConversionStrategy::new(&mut prepared, mapping_options)?;
+    if !partition_by.is_empty() {
+        table_strategy.partition_by(partition_by)?;
+    }
+
+    let mem_usage_odbc_buffer_per_row = table_strategy.fetch_buffer_size_per_row();
+    let total_mem_usage_per_row =
+        mem_usage_odbc_buffer_per_row + ParquetBuffer::MEMORY_USAGE_BYTES_PER_ROW;
+    let batch_size_row = batch_size.batch_size_in_rows(total_mem_usage_per_row)?;
+    let num_buffers = if concurrent_fetching && !table_strategy.fetch_row_by_row() {
+        2
+    } else {
+        1
+    };
+
+    let mut out = stdout().lock();
+    print_schema(&mut out, &table_strategy.parquet_schema());
+    writeln!(out)?;
+    writeln!(out, "ODBC buffers:")?;
+    for (name, desc) in table_strategy.buffer_descs() {
+        writeln!(
+            out,
+            "  {name}: {desc:?} ({} bytes per row)",
+            desc.bytes_per_row()
+        )?;
+    }
+    writeln!(out)?;
+    writeln!(
+        out,
+        "Bytes per row: {total_mem_usage_per_row} ({mem_usage_odbc_buffer_per_row} ODBC buffer, \
+        {} parquet buffer)",
+        ParquetBuffer::MEMORY_USAGE_BYTES_PER_ROW
+    )?;
+    writeln!(out, "Batch size: {batch_size_row} rows")?;
+    writeln!(
+        out,
+        "Fetch buffers: {num_buffers} x {} bytes",
+        batch_size_row * mem_usage_odbc_buffer_per_row
+    )?;
+    if table_strategy.fetch_row_by_row() {
+        let lob_columns: Vec<_> = table_strategy.lob_column_names().collect();
+        writeln!(
+            out,
+            "Fetched row by row, due to large columns: {}",
+            lob_columns.join(", ")
+        )?;
+    }
+    Ok(())
+}
@@ -0,0 +1,306 @@
+use std::{
+    fs,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+
+use anyhow::{bail, Context, Error};
+use chrono::Utc;
+use io_arg::IoArg;
+use log::{debug, info};
+use odbc_api::{ConnectionTransitions, SharedConnection};
+
+use super::{
+    batch_size_limit::{BatchSizeLimit, FileSizeLimit},
+    column_length_limit::ColumnLengthLimits,
+    column_strategy::MappingOptions,
+    cursor_to_parquet,
+    identifier::{identifier_quote, quote_identifier},
+    output::OutputOptions,
+    parquet_writer::ParquetWriterOptions,
+    partition::escape_path_name,
+    provenance_metadata,
+    schema_overrides::SchemaOverrides,
+    timestamp_precision::TimestampEncoding,
+};
+use crate::{connection::open_query_connection, enum_args::OutputFormat, DumpOpt};
+
+/// Table reported by the catalog of the data source.
+struct TableName {
+    catalog: Option<String>,
+    schema: Option<String>,
+    table: String,
+}
+
+impl TableName {
+    /// `catalog.schema.table`, leaving out the parts the data source does not report (e.g. the
+    /// schema in case of MySQL). Tables of different catalogs may share schema and table name, so
+    /// the catalog is always part of the name if present.
+    fn display_name(&self) -> String {
+        [
+            self.catalog.as_deref(),
+            self.schema.as_deref(),
+            Some(self.table.as_str()),
+        ]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>()
+        .join(".")
+    }
+
+    /// `SELECT` statement fetching the entire table. Each part of the name is quoted, so names
+    /// with spaces, reserved words or mixed case work.
+    fn select_all(&self, quote: char) -> String {
+        let qualified = [
+            self.catalog.as_deref(),
+            self.schema.as_deref(),
+            Some(self.table.as_str()),
+        ]
+        .into_iter()
+        .flatten()
+        .map(|part| quote_identifier(part, quote))
+        .collect::<Vec<_>>()
+        .join(".");
+        format!("SELECT * FROM {qualified}")
+    }
+}
+
+/// Writes every table matching the catalog, schema and table name patterns into its own file in
+/// the output directory. All tables are fetched over the same connection. A table which can not be
+/// dumped does not stop the others. Instead, all failures are summarized at the end.
+pub fn dump(opt: DumpOpt) -> Result<(), Error> {
+    let DumpOpt {
+        connect_opts,
+        catalog,
+        schema,
+        table,
+        table_type,
+        batch_size_row,
+        batch_size_memory,
+        sequential_fetching,
+        column_length_limit,
+        lob_threshold,
+        format,
+        column_compression_default,
+        column_compression_level_default,
+        encoding,
+        prefer_varbinary,
+        driver_does_not_support_64bit_integers,
+        avoid_decimal,
+        timestamp_unit,
+        timestamp_int96,
+        arrow_schema,
+        no_empty_file,
+        output_dir,
+    } = opt;
+
+    let batch_size = BatchSizeLimit::new(batch_size_row, batch_size_memory);
+    let odbc_conn = open_query_connection(&connect_opts)?;
+    let query_timeout_sec = connect_opts.query_timeout_sec();
+    let db_name = odbc_conn.database_management_system_name()?;
+    debug!(name:display = db_name; "Database Management System");
+
+    // Unlike an empty string, which selects tables without a catalog or schema, `%` does not
+    // restrict the tables returned.
+    let catalog = catalog.as_deref().unwrap_or("%");
+    let schema = schema.as_deref().unwrap_or("%");
+    let mut tables = Vec::new();
+    for row in odbc_conn.tables(catalog, schema, &table, &table_type)? {
+        let row = row?;
+        let text = |column: &[u8]| {
+            let text = String::from_utf8_lossy(column);
+            (!text.is_empty()).then(|| text.into_owned())
+        };
+        let Some(table) = row.table.as_bytes().and_then(text) else {
+            continue;
+        };
+        tables.push(TableName {
+            catalog: row.catalog.as_bytes().and_then(text),
+            schema: row.schema.as_bytes().and_then(text),
+            table,
+        });
+    }
+    if tables.is_empty() {
+        bail!("No tables match catalog '{catalog}', schema '{schema}' and table '{table}'.")
+    }
+    info!(num_tables = tables.len(); "Dumping tables");
+
+    fs::create_dir_all(&output_dir)
+        .with_context(|| format!("Could not create output directory {output_dir:?}"))?;
+
+    let quote = identifier_quote(&db_name);
+    let schema_overrides = SchemaOverrides::default();
+    let column_length_limits = ColumnLengthLimits::default();
+    let mapping_options = MappingOptions {
+        db_name: &db_name,
+        use_utf16: encoding.use_utf16(),
+        prefer_varbinary,
+        avoid_decimal,
+        driver_does_support_i64: !driver_does_not_support_64bit_integers,
+        column_length_limit,
+        column_length_limits: &column_length_limits,
+        timestamp_encoding: TimestampEncoding::from_arguments(timestamp_unit, timestamp_int96),
+        lob_threshold,
+        schema_overrides: &schema_overrides,
+    };
+    let column_compression_default =
+        column_compression_default.to_compression(column_compression_level_default)?;
+
+    // Each cursor shares ownership of the connection, so it can be handed over to the fetch thread.
+    let odbc_conn: SharedConnection = Arc::new(Mutex::new(odbc_conn));
+    let mut failures = Vec::new();
+    for table in &tables {
+        let query = table.select_all(quote);
+        let path = output_dir.join(file_name(table, format));
+        info!(table:display = table.display_name(), query, path:display = path.to_string_lossy();
+            "Dumping table");
+        let output_options = OutputOptions {
+            format,
+            parquet: ParquetWriterOptions {
+                column_compression_default,
+                column_encodings: Vec::new(),
+                column_compressions: Vec::new(),
+                column_dictionaries: Vec::new(),
+                column_statistics: Vec::new(),
+                bloom_filters: Vec::new(),
+                arrow_schema,
+            },
+            key_value_metadata: provenance_metadata(&query, &[], &db_name, Utc::now(), Vec::new())?,
+            suffix_length: 2,
+            file_size: FileSizeLimit::new(0, None),
+            no_empty_file,
+            partitioned: false,
+            manifest: None,
+        };
+        let result = dump_table(
+            odbc_conn.clone(),
+            &query,
+            query_timeout_sec,
+            path,
+            batch_size,
+            !sequential_fetching,
+            mapping_options,
+            output_options,
+        );
+        if let Err(error) = result {
+            failures.push((table.display_name(), error));
+        }
+    }
+
+    if !failures.is_empty() {
+        eprintln!("Failed to dump {} tables:", failures.len());
+        for (table, error) in &failures {
+            eprintln!("  {table}: {error:#}");
+        }
+        bail!(
+            "Failed to dump {} of {} tables.",
+            failures.len(),
+            tables.len()
+        )
+    }
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+fn dump_table(
+    odbc_conn: SharedConnection<'static>,
+    query: &str,
+    query_timeout_sec: Option<usize>,
+    path: PathBuf,
+    batch_size: BatchSizeLimit,
+    concurrent_fetching: bool,
+    mapping_options: MappingOptions,
+    output_options: OutputOptions,
+) -> Result<(), Error> {
+    let Some(cursor) = odbc_conn
+        .into_cursor(query, (), query_timeout_sec)
+        .map_err(odbc_api::Error::from)?
+    else {
+        bail!("Query did not return a result set.")
+    };
+    cursor_to_parquet(
+        cursor,
+        IoArg::File(path),
+        batch_size,
+        concurrent_fetching,
+        mapping_options,
+        output_options,
+        &[],
+        None,
+    )?;
+    Ok(())
+}
+
+/// Name of the output file of a table, e.g. `shop.dbo.Sales.parquet`.
+fn file_name(table: &TableName, format: OutputFormat) -> PathBuf {
+    let extension = match format {
+        OutputFormat::Parquet => "parquet",
+        OutputFormat::Csv => "csv",
+        OutputFormat::Jsonl => "jsonl",
+        OutputFormat::Arrow => "arrow",
+    };
+    PathBuf::from(format!(
+        "{}.{extension}",
+        escape_path_name(&table.display_name())
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use crate::enum_args::OutputFormat;
+
+    use super::{file_name, TableName};
+
+    #[test]
+    fn select_all_from_quoted_table_name() {
+        let table = TableName {
+            catalog: None,
+            schema: Some("dbo".to_owned()),
+            table: "Order \"Details\"".to_owned(),
+        };
+
+        assert_eq!(
+            "SELECT * FROM \"dbo\".\"Order \"\"Details\"\"\"",
+            table.select_all('"')
+        );
+        assert_eq!(
+            PathBuf::from("dbo.Order %22Details%22.parquet"),
+            file_name(&table, OutputFormat::Parquet)
+        );
+    }
+
+    #[test]
+    fn tables_of_different_catalogs_are_written_to_different_files() {
+        let table = |catalog: &str| TableName {
+            catalog: Some(catalog.to_owned()),
+            schema: Some("dbo".to_owned()),
+            table: "sales".to_owned(),
+        };
+
+        assert_eq!(
+            PathBuf::from("shop.dbo.sales.parquet"),
+            file_name(&table("shop"), OutputFormat::Parquet)
+        );
+        assert_eq!(
+            PathBuf::from("archive.dbo.sales.parquet"),
+            file_name(&table("archive"), OutputFormat::Parquet)
+        );
+    }
+
+    #[test]
+    fn schema_less_tables_are_prefixed_with_catalog() {
+        let table = TableName {
+            catalog: Some("shop".to_owned()),
+            schema: None,
+            table: "sales".to_owned(),
+        };
+
+        assert_eq!("SELECT * FROM `shop`.`sales`", table.select_all('`'));
+        assert_eq!(
+            PathBuf::from("shop.sales.csv"),
+            file_name(&table, OutputFormat::Csv)
+        );
+    }
+}
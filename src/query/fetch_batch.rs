@@ -8,23 +8,40 @@ use odbc_api::{
 
 use crate::parquet_buffer::ParquetBuffer;
 
-use super::{batch_size_limit::BatchSizeLimit, conversion_strategy::ConversionStrategy};
+use super::{
+    batch_size_limit::BatchSizeLimit, conversion_strategy::ConversionStrategy, lob::RowByRowFetch,
+};
 
-pub trait FetchBatch {
+pub trait FetchBatch<C> {
     /// Maximum batch size in rows. This is used to allocate the parquet buffer of correct size.
     fn max_batch_size_in_rows(&self) -> usize;
 
     /// Borrows a buffer containing the next batch to be written to the output parquet file
     fn next_batch(&mut self) -> Result<Option<&ColumnarDynBuffer>, odbc_api::Error>;
+
+    /// Unbinds the fetch buffers and yields the cursor back, e.g. to move on to the next result
+    /// set.
+    fn into_cursor(self: Box<Self>) -> Result<C, odbc_api::Error>;
 }
 
-pub fn fetch_strategy(
+pub fn fetch_strategy<C>(
     concurrent_fetching: bool,
-    cursor: impl Cursor + 'static + Send,
+    cursor: C,
     conversion_strategy: &ConversionStrategy,
     batch_size_limit: BatchSizeLimit,
-) -> Result<Box<dyn FetchBatch>, Error> {
-    if concurrent_fetching {
+) -> Result<Box<dyn FetchBatch<C>>, Error>
+where
+    C: Cursor + Send + 'static,
+{
+    if conversion_strategy.fetch_row_by_row() {
+        // Columns fetched with `SQLGetData` can not be combined with bound buffers, so concurrent
+        // fetching is not an option.
+        Ok(Box::new(row_by_row_fetch(
+            cursor,
+            conversion_strategy,
+            batch_size_limit,
+        )?))
+    } else if concurrent_fetching {
         Ok(Box::new(ConcurrentFetch::new(
             cursor,
             conversion_strategy,
@@ -39,6 +56,33 @@ pub fn fetch_strategy(
     }
 }
 
+/// Fetch row by row, with batches limited to the number of rows and memory a bound fetch buffer
+/// would have used.
+pub fn row_by_row_fetch<C>(
+    cursor: C,
+    table_strategy: &ConversionStrategy,
+    batch_size_limit: BatchSizeLimit,
+) -> Result<RowByRowFetch<C>, Error>
+where
+    C: Cursor,
+{
+    let mem_usage_odbc_buffer_per_row = table_strategy.fetch_buffer_size_per_row();
+    let total_mem_usage_per_row =
+        mem_usage_odbc_buffer_per_row + ParquetBuffer::MEMORY_USAGE_BYTES_PER_ROW;
+    let batch_size_row = batch_size_limit.batch_size_in_rows(total_mem_usage_per_row)?;
+    debug!(rows = batch_size_row; "Batch size for fetching row by row");
+    let descs = table_strategy
+        .buffer_descs()
+        .map(|(_name, desc)| desc)
+        .collect();
+    Ok(RowByRowFetch::new(
+        cursor,
+        descs,
+        batch_size_row,
+        batch_size_row * mem_usage_odbc_buffer_per_row,
+    ))
+}
+
 /// Fetch one fetch buffer and write its contents to parquet. Then fill it again. This is not as
 /// fast as double buffering with concurrent fetching, but it uses less memory due to only requiring
 /// one fetch buffer.
@@ -74,7 +118,7 @@ where
     }
 }
 
-impl<C> FetchBatch for SequentialFetch<C>
+impl<C> FetchBatch<C> for SequentialFetch<C>
 where
     C: Cursor,
 {
@@ -86,6 +130,46 @@ where
     fn max_batch_size_in_rows(&self) -> usize {
         self.block_cursor.row_array_size()
     }
+
+    fn into_cursor(self: Box<Self>) -> Result<C, odbc_api::Error> {
+        let (cursor, _buffer) = self.block_cursor.unbind()?;
+        Ok(cursor)
+    }
+}
+
+/// Fetches into a buffer borrowed from the caller, so the same buffer can be reused for the
+/// results of many executions of a prepared statement.
+pub struct BorrowedBufferFetch<'b, C: Cursor> {
+    block_cursor: BlockCursor<C, &'b mut ColumnarDynBuffer>,
+}
+
+impl<'b, C> BorrowedBufferFetch<'b, C>
+where
+    C: Cursor,
+{
+    pub fn new(cursor: C, buffer: &'b mut ColumnarDynBuffer) -> Result<Self, odbc_api::Error> {
+        let block_cursor = cursor.bind_buffer(buffer)?;
+        Ok(Self { block_cursor })
+    }
+}
+
+impl<C> FetchBatch<C> for BorrowedBufferFetch<'_, C>
+where
+    C: Cursor,
+{
+    fn next_batch(&mut self) -> Result<Option<&ColumnarDynBuffer>, odbc_api::Error> {
+        let batch = self.block_cursor.fetch_with_truncation_check(true)?;
+        Ok(batch.map(|buffer| &**buffer))
+    }
+
+    fn max_batch_size_in_rows(&self) -> usize {
+        self.block_cursor.row_array_size()
+    }
+
+    fn into_cursor(self: Box<Self>) -> Result<C, odbc_api::Error> {
+        let (cursor, _buffer) = self.block_cursor.unbind()?;
+        Ok(cursor)
+    }
 }
 
 /// Use a concurrent cursor and an extra buffers. One buffers content is read and written into
@@ -131,9 +215,9 @@ where
     }
 }
 
-impl<C> FetchBatch for ConcurrentFetch<C>
+impl<C> FetchBatch<C> for ConcurrentFetch<C>
 where
-    C: Cursor,
+    C: Cursor + Send + 'static,
 {
     fn next_batch(&mut self) -> Result<Option<&ColumnarDynBuffer>, odbc_api::Error> {
         let batch = self.block_cursor.fetch()?;
@@ -149,4 +233,8 @@ where
     fn max_batch_size_in_rows(&self) -> usize {
         self.buffer.row_array_size()
     }
+
+    fn into_cursor(self: Box<Self>) -> Result<C, odbc_api::Error> {
+        self.block_cursor.into_cursor()
+    }
 }
@@ -3,6 +3,8 @@
 use std::marker::PhantomData;
 
 use anyhow::Error;
+use arrow_array::ArrayRef;
+use arrow_schema::DataType as ArrowDataType;
 use odbc_api::{
     buffers::{AnyColumnBufferSlice, BufferDesc, Item},
     Pod,
@@ -16,7 +18,7 @@ use parquet::{
 
 use crate::parquet_buffer::{BufferedDataType, ParquetBuffer};
 
-use super::ColumnStrategy;
+use super::{arrow_column::ArrowValue, ColumnStrategy};
 
 /// Copy identical optional data from ODBC to Parquet.
 pub struct IdenticalOptional<Pdt> {
@@ -44,7 +46,7 @@ impl<Pdt> IdenticalOptional<Pdt> {
 impl<Pdt> ColumnStrategy for IdenticalOptional<Pdt>
 where
     Pdt: DataType,
-    Pdt::T: Item + Pod + BufferedDataType,
+    Pdt::T: Item + Pod + BufferedDataType + ArrowValue,
 {
     fn parquet_type(&self, name: &str) -> Type {
         parquet_data_type::<Pdt>(name, self.logical_type.clone(), Repetition::OPTIONAL)
@@ -65,6 +67,15 @@ where
         parquet_buffer.write_optional(column_writer, it.map(|opt_ref| opt_ref.copied()))?;
         Ok(())
     }
+
+    fn copy_odbc_to_arrow(
+        &self,
+        data_type: &ArrowDataType,
+        column_view: AnyColumnBufferSlice,
+    ) -> Result<ArrayRef, Error> {
+        let it = column_view.as_nullable_slice::<Pdt::T>().unwrap();
+        Pdt::T::arrow_array(it.map(|opt_ref| opt_ref.copied()), data_type)
+    }
 }
 
 /// Optimized strategy if ODBC and Parquet type are identical, and we know the data source not to
@@ -92,7 +103,7 @@ impl<Pdt> IdenticalRequired<Pdt> {
 impl<Pdt> ColumnStrategy for IdenticalRequired<Pdt>
 where
     Pdt: DataType,
-    Pdt::T: Item + Pod + BufferedDataType,
+    Pdt::T: Item + Pod + BufferedDataType + ArrowValue,
 {
     fn parquet_type(&self, name: &str) -> Type {
         parquet_data_type::<Pdt>(name, self.logical_type.clone(), Repetition::REQUIRED)
@@ -104,7 +115,7 @@ where
 
     fn copy_odbc_to_parquet(
         &self,
-        _parquet_buffer: &mut ParquetBuffer,
+        parquet_buffer: &mut ParquetBuffer,
         column_writer: &mut ColumnWriter,
         column_view: AnyColumnBufferSlice,
     ) -> Result<(), Error> {
@@ -113,9 +124,18 @@ where
 
         let values = column_view.as_slice().unwrap();
         let column_writer = get_typed_column_writer_mut::<Pdt>(column_writer);
-        column_writer.write_batch(values, None, None)?;
+        parquet_buffer.write_required(column_writer, values)?;
         Ok(())
     }
+
+    fn copy_odbc_to_arrow(
+        &self,
+        data_type: &ArrowDataType,
+        column_view: AnyColumnBufferSlice,
+    ) -> Result<ArrayRef, Error> {
+        let values: &[Pdt::T] = column_view.as_slice().unwrap();
+        Pdt::T::arrow_array(values.iter().copied().map(Some), data_type)
+    }
 }
 
 fn parquet_data_type<Pdt>(
@@ -139,7 +159,7 @@ where
 pub fn fetch_identical<Pdt>(is_optional: bool) -> Box<dyn ColumnStrategy>
 where
     Pdt: DataType,
-    Pdt::T: Item + Pod + BufferedDataType,
+    Pdt::T: Item + Pod + BufferedDataType + ArrowValue,
 {
     if is_optional {
         Box::new(IdenticalOptional::<Pdt>::new())
@@ -154,7 +174,7 @@ pub fn fetch_identical_with_logical_type<Pdt>(
 ) -> Box<dyn ColumnStrategy>
 where
     Pdt: DataType,
-    Pdt::T: Item + Pod + BufferedDataType,
+    Pdt::T: Item + Pod + BufferedDataType + ArrowValue,
 {
     if is_optional {
         Box::new(IdenticalOptional::<Pdt>::with_logical_type(Some(
@@ -0,0 +1,15 @@
+/// Character used to quote identifiers. ODBC reports it via `SQL_IDENTIFIER_QUOTE_CHAR`, which is
+/// not exposed by `odbc-api`, so we go by the name of the database instead. MySQL and MariaDB only
+/// accept double quotes in `ANSI_QUOTES` mode, everyone else follows the standard.
+pub fn identifier_quote(db_name: &str) -> char {
+    match db_name {
+        "MySQL" | "MariaDB" => '`',
+        _ => '"',
+    }
+}
+
+/// Quotes an identifier, doubling any quote characters within it.
+pub fn quote_identifier(identifier: &str, quote: char) -> String {
+    let escaped = identifier.replace(quote, &format!("{quote}{quote}"));
+    format!("{quote}{escaped}{quote}")
+}
@@ -0,0 +1,147 @@
+use log::debug;
+use odbc_api::{
+    buffers::{BufferDesc, ColumnarBuffer, ColumnarDynBuffer},
+    Cursor, DataType, RowSetBuffer,
+};
+
+use super::{fetch_batch::FetchBatch, staged_rows::ColumnValues};
+
+/// `true` if values of the column may be larger than `threshold`, or their maximum length is not
+/// known at all, as is the case for e.g. `VARCHAR(MAX)`, `TEXT` or `BLOB`. For text the length is
+/// in characters, for binary data in bytes. Fixed size binary columns are never considered large
+/// objects, since they are written as fixed length byte arrays.
+pub fn is_lob(data_type: &DataType, threshold: usize) -> bool {
+    match data_type {
+        DataType::Char { length }
+        | DataType::WChar { length }
+        | DataType::Varchar { length }
+        | DataType::WVarchar { length }
+        | DataType::LongVarchar { length }
+        | DataType::WLongVarchar { length }
+        | DataType::Varbinary { length }
+        | DataType::LongVarbinary { length } => length.is_none_or(|len| len.get() > threshold),
+        _ => false,
+    }
+}
+
+/// Fetches one row at a time and retrieves each field with `SQLGetData`, rather than binding
+/// buffers to the cursor. Variadic values are retrieved in chunks until they are complete, so they
+/// are never truncated, regardless of their size. Rows are collected into batches, whose text and
+/// binary columns are just large enough to hold the largest value within them. A batch ends early
+/// if another row would make it exceed the memory a bound fetch buffer would have used, so a few
+/// huge values do not blow up the memory required for all other rows in the same batch.
+///
+/// All columns are fetched this way, not only the large ones. Most drivers support `SQLGetData`
+/// only for a row array size of one and only for columns after the last bound column.
+pub struct RowByRowFetch<C> {
+    cursor: C,
+    descs: Vec<BufferDesc>,
+    max_rows: usize,
+    max_bytes: usize,
+    /// Values of a row which did not fit into the last batch. It becomes the first row of the next
+    /// one.
+    carried_over: Option<Vec<ColumnValues>>,
+    batch: ColumnarDynBuffer,
+}
+
+impl<C> RowByRowFetch<C>
+where
+    C: Cursor,
+{
+    /// * `descs`: Buffer description for each column of the result set. Variadic columns grow to
+    ///   fit the values in a batch, so their maximum lengths are only used to calculate
+    ///   `max_bytes`.
+    /// * `max_rows`: Maximum number of rows in a batch.
+    /// * `max_bytes`: Memory a batch may use, before it is completed early.
+    pub fn new(cursor: C, descs: Vec<BufferDesc>, max_rows: usize, max_bytes: usize) -> Self {
+        Self {
+            cursor,
+            descs,
+            max_rows,
+            max_bytes,
+            carried_over: None,
+            batch: ColumnarBuffer::new(Vec::new()),
+        }
+    }
+}
+
+impl<C> FetchBatch<C> for RowByRowFetch<C>
+where
+    C: Cursor,
+{
+    fn max_batch_size_in_rows(&self) -> usize {
+        self.max_rows
+    }
+
+    fn next_batch(&mut self) -> Result<Option<&ColumnarDynBuffer>, odbc_api::Error> {
+        let (mut columns, mut num_rows) = match self.carried_over.take() {
+            Some(columns) => (columns, 1),
+            None => (
+                self.descs.iter().copied().map(ColumnValues::new).collect(),
+                0,
+            ),
+        };
+        while num_rows < self.max_rows {
+            let Some(mut row) = self.cursor.next_row()? else {
+                break;
+            };
+            for (index, column) in columns.iter_mut().enumerate() {
+                column.fetch_value(&mut row, (index + 1).try_into().unwrap())?;
+            }
+            num_rows += 1;
+            let bytes_with_row: usize = columns.iter().map(ColumnValues::buffer_size).sum();
+            // Every batch holds at least one row, no matter how large.
+            if num_rows != 1 && bytes_with_row > self.max_bytes {
+                debug!(num_rows, bytes_with_row; "Completing batch early to limit memory");
+                num_rows -= 1;
+                self.carried_over = Some(
+                    columns
+                        .iter_mut()
+                        .map(|column| column.split_off(num_rows))
+                        .collect(),
+                );
+                break;
+            }
+        }
+        if num_rows == 0 {
+            return Ok(None);
+        }
+        let columns = columns
+            .into_iter()
+            .enumerate()
+            .map(|(index, column)| ((index + 1).try_into().unwrap(), column.into_buffer()))
+            .collect();
+        self.batch = ColumnarBuffer::new(columns);
+        *self.batch.mut_num_fetch_rows() = num_rows;
+        Ok(Some(&self.batch))
+    }
+
+    fn into_cursor(self: Box<Self>) -> Result<C, odbc_api::Error> {
+        Ok(self.cursor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZeroUsize;
+
+    use odbc_api::DataType;
+
+    use super::is_lob;
+
+    #[test]
+    fn columns_larger_than_threshold_or_of_unknown_size_are_lobs() {
+        let length = NonZeroUsize::new;
+
+        assert!(is_lob(&DataType::Varchar { length: None }, 4096));
+        assert!(is_lob(
+            &DataType::LongVarbinary {
+                length: length(1_000_000)
+            },
+            4096
+        ));
+        assert!(!is_lob(&DataType::WVarchar { length: length(50) }, 4096));
+        assert!(!is_lob(&DataType::Binary { length: None }, 4096));
+        assert!(!is_lob(&DataType::Integer, 4096));
+    }
+}
@@ -0,0 +1,146 @@
+use std::{
+    fs::File,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use anyhow::{Context, Error};
+use log::info;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use tempfile::NamedTempFile;
+
+/// Machine readable list of all files produced by an extraction. It is only written once all files
+/// have been completed, so its existence signals a complete export to downstream loaders.
+#[derive(Clone)]
+pub struct Manifest {
+    path: PathBuf,
+    /// Files are recorded by the writers once they are finalized. Shared, since split and
+    /// partitioned extractions use several writers, possibly on different threads.
+    files: Arc<Mutex<Vec<ManifestFile>>>,
+}
+
+#[derive(Serialize)]
+struct ManifestFile {
+    path: String,
+    rows: u64,
+    bytes: u64,
+    row_groups: u32,
+    sha256: String,
+}
+
+#[derive(Serialize)]
+struct ManifestContent<'a> {
+    files: &'a [ManifestFile],
+    total: Total,
+}
+
+#[derive(Serialize)]
+struct Total {
+    files: usize,
+    rows: u64,
+    bytes: u64,
+}
+
+impl Manifest {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            files: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    /// Record a completed output file. Size and checksum are taken from the file on disk.
+    pub fn record(&self, path: &Path, rows: u64, row_groups: u32) -> Result<(), Error> {
+        let mut file = File::open(path).with_context(|| {
+            format!(
+                "Could not read output file '{}' to compute its checksum",
+                path.to_string_lossy()
+            )
+        })?;
+        let mut hasher = Sha256::new();
+        let bytes = io::copy(&mut file, &mut hasher)?;
+        let entry = ManifestFile {
+            path: path.to_string_lossy().into_owned(),
+            rows,
+            bytes,
+            row_groups,
+            sha256: format!("{:x}", hasher.finalize()),
+        };
+        self.files
+            .lock()
+            .expect("Manifest lock must not be poisoned")
+            .push(entry);
+        Ok(())
+    }
+
+    /// Persist the manifest. The file is replaced atomically, so readers never see a partially
+    /// written manifest.
+    pub fn write(&self) -> Result<(), Error> {
+        let mut files = self
+            .files
+            .lock()
+            .expect("Manifest lock must not be poisoned");
+        files.sort_by(|a, b| a.path.cmp(&b.path));
+        let content = ManifestContent {
+            files: &files,
+            total: Total {
+                files: files.len(),
+                rows: files.iter().map(|file| file.rows).sum(),
+                bytes: files.iter().map(|file| file.bytes).sum(),
+            },
+        };
+        let dir = match self.path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => ".".as_ref(),
+        };
+        let mut file = NamedTempFile::new_in(dir)?;
+        serde_json::to_writer_pretty(&mut file, &content)?;
+        file.write_all(b"\n")?;
+        file.persist(&self.path)
+            .with_context(|| format!("Could not write manifest {:?}", self.path))?;
+        info!(files = content.total.files, path:display = self.path.to_string_lossy();
+            "Manifest written");
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use serde_json::Value;
+    use tempfile::tempdir;
+
+    use super::Manifest;
+
+    #[test]
+    fn record_files_with_checksum_and_total() {
+        let dir = tempdir().unwrap();
+        let first = dir.path().join("out_01.csv");
+        let second = dir.path().join("out_02.csv");
+        fs::write(&first, "abc").unwrap();
+        fs::write(&second, "").unwrap();
+        let manifest = Manifest::new(dir.path().join("manifest.json"));
+
+        // Record out of order, like concurrent writers might
+        manifest.record(&second, 0, 0).unwrap();
+        manifest.record(&first, 3, 1).unwrap();
+        manifest.write().unwrap();
+
+        let content = fs::read_to_string(dir.path().join("manifest.json")).unwrap();
+        let content: Value = serde_json::from_str(&content).unwrap();
+        assert_eq!(first.to_str().unwrap(), content["files"][0]["path"]);
+        assert_eq!(3, content["files"][0]["rows"]);
+        assert_eq!(3, content["files"][0]["bytes"]);
+        assert_eq!(1, content["files"][0]["row_groups"]);
+        assert_eq!(
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+            content["files"][0]["sha256"]
+        );
+        assert_eq!(2, content["total"]["files"]);
+        assert_eq!(3, content["total"]["rows"]);
+        assert_eq!(3, content["total"]["bytes"]);
+    }
+}
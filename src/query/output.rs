@@ -0,0 +1,129 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use anyhow::{anyhow, format_err, Error};
+use io_arg::IoArg;
+use parquet::{file::metadata::KeyValue, schema::types::Type};
+
+use crate::enum_args::OutputFormat;
+
+use super::{
+    arrow_writer::arrow_output,
+    batch_size_limit::FileSizeLimit,
+    conversion_strategy::ColumnExporter,
+    manifest::Manifest,
+    parquet_writer::{parquet_output, ParquetWriterOptions},
+    text_writer::{text_output, Csv, JsonLines},
+};
+
+/// Options influencing the output independent of schema or row content.
+#[derive(Clone)]
+pub struct OutputOptions {
+    /// Format of the output.
+    pub format: OutputFormat,
+    /// Options only affecting parquet files. Ignored for other formats.
+    pub parquet: ParquetWriterOptions,
+    /// Key value metadata written into the footer of each parquet file, or the schema of Arrow
+    /// files. E.g. provenance information.
+    pub key_value_metadata: Vec<KeyValue>,
+    /// Number of digits in the suffix, appended to the end of a file in case they are numbered.
+    pub suffix_length: usize,
+    /// A fuzzy limit for file size, causing the rest of the query to be written into new files if a
+    /// threshold is passed.
+    pub file_size: FileSizeLimit,
+    /// Do not create a file if no row was in the result set.
+    pub no_empty_file: bool,
+    /// Write a hive style partitioned dataset into the output directory, rather than a single file.
+    pub partitioned: bool,
+    /// Records each completed file, if a manifest has been requested.
+    pub manifest: Option<Manifest>,
+}
+
+/// Writer for the format requested in `options`. `schema` describes the columns of the output in
+/// terms of parquet types. Other formats derive their representation of the columns from it.
+pub fn open_output(
+    output: IoArg,
+    schema: Arc<Type>,
+    options: OutputOptions,
+) -> Result<Box<dyn Output>, Error> {
+    match options.format {
+        OutputFormat::Parquet => parquet_output(output, schema, options),
+        OutputFormat::Csv => text_output(output, Box::new(Csv), schema, options),
+        OutputFormat::Jsonl => text_output(output, Box::new(JsonLines), schema, options),
+        OutputFormat::Arrow => arrow_output(output, schema, options),
+    }
+}
+
+/// Writes batches to the output, which could be either standard out, a single file or multiple
+/// files with incrementing number suffixes.
+pub trait Output {
+    /// Write the next batch of rows. May trigger creation of a new file if limit of the previous
+    /// one is reached.
+    ///
+    /// # Parameters
+    ///
+    /// * `num_batch`: 1-based index of the batch being written.
+    fn write_batch(&mut self, num_batch: u32, column_exporter: ColumnExporter)
+        -> Result<(), Error>;
+
+    /// Indicate that no further output is written. This triggers writing the file metadata and
+    /// potentially persists a temporary file.
+    fn close(self) -> Result<(), Error>;
+
+    fn close_box(self: Box<Self>) -> Result<(), Error>;
+}
+
+/// Appends `_{num_file}` to the file stem, zero padded to `suffix_length` digits.
+pub fn path_with_suffix(
+    path: &Path,
+    num_file: u32,
+    suffix_length: usize,
+) -> Result<PathBuf, Error> {
+    let suffix = format!("_{:0width$}", num_file, width = suffix_length);
+    let mut stem = path
+        .file_stem()
+        .ok_or_else(|| format_err!("Output needs To have a file stem."))?
+        .to_owned();
+    stem.push(suffix);
+    // Retain file extension (we can potentially simplify this once `Path::add_extension` is stable)
+    if let Some(extension) = path.extension() {
+        stem.push(format!(
+            ".{}",
+            extension
+                .to_str()
+                .ok_or(anyhow!("Output file extension is not valid UTF-8"))?
+        ));
+    }
+    let path_with_suffix = path.with_file_name(stem);
+    Ok(path_with_suffix)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::path::PathBuf;
+
+    use super::path_with_suffix;
+
+    #[test]
+    fn filenames_with_suffixes() {
+        let path = path_with_suffix(&PathBuf::from("test.par"), 1, 2).unwrap();
+        assert_eq!(path.to_str().unwrap(), "test_01.par");
+    }
+
+    /// See: <https://github.com/pacman82/odbc2parquet/issues/754>
+    #[test]
+    fn filenames_with_dot_in_filestem_and_suffix() {
+        let path = path_with_suffix(&PathBuf::from("server-name-schema.table.par"), 1, 2).unwrap();
+        assert_eq!(path.to_str().unwrap(), "server-name-schema.table_01.par");
+    }
+
+    /// See: <https://github.com/pacman82/odbc2parquet/issues/754>
+    #[test]
+    fn retain_path_before_file_name() {
+        let path = path_with_suffix(&PathBuf::from("./some_path/out.par"), 1, 2).unwrap();
+        assert_eq!(path, PathBuf::from("./some_path/out_01.par"));
+    }
+}
@@ -0,0 +1,295 @@
+use anyhow::{bail, Context, Error};
+use chrono::{DateTime, Datelike, NaiveDate, NaiveDateTime, Timelike};
+use odbc_api::{
+    parameter::{InputParameter, VarCharBox, WithDataType},
+    sys::{Date, Timestamp},
+    Bit, DataType,
+};
+use parquet::record::Field;
+
+/// Positional query parameter passed on the command line. By default parameters are bound as
+/// text. A prefix like `int:42` or `date:2024-01-01` binds them with the given type instead, since
+/// some drivers do not convert text implicitly, or only at the cost of ignoring indices. `null`
+/// binds `NULL`. `text:` can be used to pass text which would otherwise be interpreted as one of
+/// these, e.g. `text:null`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Parameter {
+    Text(String),
+    Integer(i64),
+    Float(f64),
+    /// Kept as text, so no precision is lost. Bound as text together with the precision and
+    /// scale of the decimal.
+    Decimal {
+        text: String,
+        precision: usize,
+        scale: i16,
+    },
+    Date(NaiveDate),
+    Timestamp(NaiveDateTime),
+    /// Only read from parameter files. Bound as `BIT`.
+    Boolean(bool),
+    Null,
+}
+
+impl Parameter {
+    pub fn parse(source: &str) -> Result<Self, Error> {
+        if source == "null" {
+            return Ok(Parameter::Null);
+        }
+        let Some((kind, value)) = source.split_once(':') else {
+            return Ok(Parameter::Text(source.to_owned()));
+        };
+        let parameter = match kind {
+            "text" => Parameter::Text(value.to_owned()),
+            "int" => Parameter::Integer(
+                value
+                    .parse()
+                    .with_context(|| format!("Invalid integer parameter '{value}'"))?,
+            ),
+            "float" => Parameter::Float(
+                value
+                    .parse()
+                    .with_context(|| format!("Invalid float parameter '{value}'"))?,
+            ),
+            "decimal" => decimal(value)?,
+            "date" => Parameter::Date(NaiveDate::parse_from_str(value, "%Y-%m-%d").with_context(
+                || format!("Invalid date parameter '{value}'. Expected format is YYYY-MM-DD."),
+            )?),
+            "ts" => Parameter::Timestamp(
+                NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S%.f")
+                    .or_else(|_| NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S%.f"))
+                    .with_context(|| {
+                        format!(
+                            "Invalid timestamp parameter '{value}'. Expected format is \
+                            YYYY-MM-DD HH:MM:SS[.fraction]."
+                        )
+                    })?,
+            ),
+            // Not a type prefix, e.g. a URL. Keep the entire text.
+            _ => Parameter::Text(source.to_owned()),
+        };
+        Ok(parameter)
+    }
+
+    /// Value of a parquet field, e.g. read from the file passed via `--parameters-from`.
+    pub fn from_field(field: &Field) -> Result<Self, Error> {
+        let parameter = match field {
+            Field::Null => Parameter::Null,
+            Field::Bool(b) => Parameter::Boolean(*b),
+            Field::Byte(i) => Parameter::Integer((*i).into()),
+            Field::Short(i) => Parameter::Integer((*i).into()),
+            Field::Int(i) => Parameter::Integer((*i).into()),
+            Field::Long(i) => Parameter::Integer(*i),
+            Field::UByte(i) => Parameter::Integer((*i).into()),
+            Field::UShort(i) => Parameter::Integer((*i).into()),
+            Field::UInt(i) => Parameter::Integer((*i).into()),
+            Field::ULong(i) => Parameter::Integer(
+                (*i).try_into()
+                    .with_context(|| format!("Parameter {i} is too large for a 64 Bit integer."))?,
+            ),
+            Field::Float(f) => Parameter::Float((*f).into()),
+            Field::Double(f) => Parameter::Float(*f),
+            Field::Decimal(d) => {
+                // Parquet renders decimals without fraction with a trailing point, e.g. `42.`
+                let text = field.to_string().trim_end_matches('.').to_owned();
+                Parameter::Decimal {
+                    text,
+                    precision: d.precision().try_into()?,
+                    scale: d.scale().try_into()?,
+                }
+            }
+            Field::Str(text) => Parameter::Text(text.clone()),
+            Field::Date(days) => Parameter::Date(
+                DateTime::UNIX_EPOCH
+                    .date_naive()
+                    .checked_add_signed(chrono::Duration::days((*days).into()))
+                    .with_context(|| format!("Date parameter out of range: {days} days"))?,
+            ),
+            Field::TimestampMillis(ms) => Parameter::Timestamp(
+                DateTime::from_timestamp_millis(*ms)
+                    .with_context(|| format!("Timestamp parameter out of range: {ms}ms"))?
+                    .naive_utc(),
+            ),
+            Field::TimestampMicros(us) => Parameter::Timestamp(
+                DateTime::from_timestamp_micros(*us)
+                    .with_context(|| format!("Timestamp parameter out of range: {us}us"))?
+                    .naive_utc(),
+            ),
+            other => bail!(
+                "Unsupported parameter '{other}'. Only booleans, integers, floats, decimals, text, \
+                dates and timestamps can be bound as parameters."
+            ),
+        };
+        Ok(parameter)
+    }
+
+    /// Parameter bound to the placeholder in the query.
+    pub fn to_parameter(&self) -> Box<dyn InputParameter> {
+        match self {
+            Parameter::Text(text) => Box::new(VarCharBox::from_string(text.clone())),
+            Parameter::Integer(i) => Box::new(*i),
+            Parameter::Float(f) => Box::new(*f),
+            Parameter::Decimal {
+                text,
+                precision,
+                scale,
+            } => Box::new(WithDataType::new(
+                VarCharBox::from_string(text.clone()),
+                DataType::Decimal {
+                    precision: *precision,
+                    scale: *scale,
+                },
+            )),
+            Parameter::Date(date) => date_parameter(date),
+            Parameter::Timestamp(ts) => timestamp_parameter(ts),
+            Parameter::Boolean(b) => Box::new(Bit::from_bool(*b)),
+            Parameter::Null => Box::new(VarCharBox::null()),
+        }
+    }
+}
+
+fn decimal(value: &str) -> Result<Parameter, Error> {
+    let digits = value.strip_prefix(['-', '+']).unwrap_or(value);
+    let (integer, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+    if (integer.is_empty() && fraction.is_empty())
+        || !integer
+            .chars()
+            .chain(fraction.chars())
+            .all(|c| c.is_ascii_digit())
+    {
+        bail!("Invalid decimal parameter '{value}'.")
+    }
+    Ok(Parameter::Decimal {
+        text: value.to_owned(),
+        precision: integer.len() + fraction.len(),
+        scale: fraction.len().try_into()?,
+    })
+}
+
+/// Binds the date as `DATE`.
+pub fn date_parameter(date: &NaiveDate) -> Box<dyn InputParameter> {
+    Box::new(Date {
+        year: date.year() as i16,
+        month: date.month() as u16,
+        day: date.day() as u16,
+    })
+}
+
+/// Binds the timestamp with as many fractional digits as required to not truncate it.
+pub fn timestamp_parameter(ts: &NaiveDateTime) -> Box<dyn InputParameter> {
+    let fraction = ts.nanosecond();
+    // Number of fractional digits required to bind the timestamp without truncation.
+    let mut precision = 9;
+    while precision > 0 && fraction.is_multiple_of(10u32.pow(10 - precision)) {
+        precision -= 1;
+    }
+    Box::new(WithDataType::new(
+        Timestamp {
+            year: ts.year() as i16,
+            month: ts.month() as u16,
+            day: ts.day() as u16,
+            hour: ts.hour() as u16,
+            minute: ts.minute() as u16,
+            second: ts.second() as u16,
+            fraction,
+        },
+        DataType::Timestamp {
+            precision: precision as i16,
+        },
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+    use parquet::{data_type::Decimal, record::Field};
+
+    use super::Parameter;
+
+    #[test]
+    fn parse_typed_parameters() {
+        assert_eq!(
+            Parameter::Text("Alice".to_owned()),
+            Parameter::parse("Alice").unwrap()
+        );
+        assert_eq!(Parameter::Integer(42), Parameter::parse("int:42").unwrap());
+        assert_eq!(
+            Parameter::Float(0.5),
+            Parameter::parse("float:0.5").unwrap()
+        );
+        assert_eq!(
+            Parameter::Decimal {
+                text: "-12.50".to_owned(),
+                precision: 4,
+                scale: 2
+            },
+            Parameter::parse("decimal:-12.50").unwrap()
+        );
+        assert_eq!(
+            Parameter::Date(NaiveDate::from_ymd_opt(2024, 1, 2).unwrap()),
+            Parameter::parse("date:2024-01-02").unwrap()
+        );
+        assert_eq!(
+            Parameter::Timestamp(
+                NaiveDate::from_ymd_opt(2024, 1, 2)
+                    .unwrap()
+                    .and_hms_milli_opt(3, 4, 5, 600)
+                    .unwrap()
+            ),
+            Parameter::parse("ts:2024-01-02 03:04:05.6").unwrap()
+        );
+        assert_eq!(Parameter::Null, Parameter::parse("null").unwrap());
+        assert_eq!(
+            Parameter::Text("null".to_owned()),
+            Parameter::parse("text:null").unwrap()
+        );
+        // Unknown prefixes are part of the text
+        assert_eq!(
+            Parameter::Text("http://example.com".to_owned()),
+            Parameter::parse("http://example.com").unwrap()
+        );
+    }
+
+    #[test]
+    fn reject_invalid_typed_parameters() {
+        assert!(Parameter::parse("int:4.2").is_err());
+        assert!(Parameter::parse("decimal:1e3").is_err());
+        assert!(Parameter::parse("decimal:.").is_err());
+        assert!(Parameter::parse("date:01/02/2024").is_err());
+        assert!(Parameter::parse("ts:2024-01-02").is_err());
+    }
+
+    #[test]
+    fn parameters_from_parquet_fields() {
+        assert_eq!(
+            Parameter::Decimal {
+                text: "-1.25".to_owned(),
+                precision: 5,
+                scale: 2
+            },
+            Parameter::from_field(&Field::Decimal(Decimal::from_i32(-125, 5, 2))).unwrap()
+        );
+        assert_eq!(
+            Parameter::Decimal {
+                text: "42".to_owned(),
+                precision: 3,
+                scale: 0
+            },
+            Parameter::from_field(&Field::Decimal(Decimal::from_i32(42, 3, 0))).unwrap()
+        );
+        assert_eq!(
+            Parameter::Date(NaiveDate::from_ymd_opt(1970, 1, 2).unwrap()),
+            Parameter::from_field(&Field::Date(1)).unwrap()
+        );
+        assert_eq!(
+            Parameter::Timestamp(
+                NaiveDate::from_ymd_opt(1970, 1, 1)
+                    .unwrap()
+                    .and_hms_milli_opt(0, 0, 1, 500)
+                    .unwrap()
+            ),
+            Parameter::from_field(&Field::TimestampMillis(1500)).unwrap()
+        );
+        assert!(Parameter::from_field(&Field::ULong(u64::MAX)).is_err());
+    }
+}
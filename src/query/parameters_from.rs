@@ -0,0 +1,259 @@
+use std::{fs::File, path::Path};
+
+use anyhow::{anyhow, bail, Context, Error};
+use io_arg::IoArg;
+use log::{debug, info};
+use odbc_api::{parameter::InputParameter, Connection, ResultSetMetadata};
+use parquet::file::reader::{FileReader, SerializedFileReader};
+
+use crate::parquet_buffer::ParquetBuffer;
+
+use super::{
+    batch_size_limit::BatchSizeLimit,
+    column_strategy::MappingOptions,
+    conversion_strategy::{ConversionStrategy, WriteProgress},
+    fetch_batch::{row_by_row_fetch, BorrowedBufferFetch},
+    output::{open_output, OutputOptions},
+    parameter::Parameter,
+    staged_rows::StagedRows,
+};
+
+/// Prepares the query once and executes it for every row in the parameter file. The results of all
+/// executions are appended to the same output. Rows of consecutive executions are combined into
+/// batches of up to the batch size, so many small result sets do not produce many tiny row groups.
+///
+/// Placeholders can reference columns of the parameter file by name, using the same `?name?`
+/// syntax as `exec`. If the query does not name its placeholders, the columns of the parameter
+/// file are bound positionally, in order.
+#[allow(clippy::too_many_arguments)]
+pub fn parameters_from_file_to_parquet(
+    conn: &Connection,
+    query: &str,
+    parameters_path: &Path,
+    query_timeout_sec: Option<usize>,
+    output: IoArg,
+    batch_size: BatchSizeLimit,
+    mapping_options: MappingOptions,
+    output_options: OutputOptions,
+    partition_by: &[String],
+) -> Result<(), Error> {
+    let file = File::open(parameters_path)
+        .with_context(|| format!("Could not open parameter file {parameters_path:?}"))?;
+    let reader = SerializedFileReader::new(file)?;
+    let column_names: Vec<_> = reader
+        .metadata()
+        .file_metadata()
+        .schema_descr()
+        .root_schema()
+        .get_fields()
+        .iter()
+        .map(|field| field.name().to_owned())
+        .collect();
+
+    let (statement_text, column_indices) = bind_columns(query, &column_names)?;
+    debug!(statement_text, column_indices:?; "Binding parameter columns");
+
+    let mut prepared = conn.prepare(&statement_text)?;
+    if let Some(timeout) = query_timeout_sec {
+        prepared.set_query_timeout_sec(timeout)?;
+    }
+    if prepared.num_result_cols()? == 0 {
+        bail!("Query does not return a result set.")
+    }
+    let mut table_strategy = ConversionStrategy::new(&mut prepared, mapping_options)?;
+    if !partition_by.is_empty() {
+        table_strategy.partition_by(partition_by)?;
+    }
+    let mem_usage_per_row =
+        table_strategy.fetch_buffer_size_per_row() + ParquetBuffer::MEMORY_USAGE_BYTES_PER_ROW;
+    let batch_size_row = batch_size.batch_size_in_rows(mem_usage_per_row)?;
+    // All executions share the same schema, so we can reuse the same fetch buffer for all of them.
+    // Not needed if large columns are fetched row by row.
+    let mut buffer = (!table_strategy.fetch_row_by_row())
+        .then(|| table_strategy.allocate_fetch_buffer(batch_size_row));
+    let mut writer = open_output(output, table_strategy.parquet_schema(), output_options)?;
+    let mut progress = WriteProgress::new(batch_size_row);
+    // Executions often return only a few rows each. Their rows are collected, so they are written
+    // in batches of full size, rather than one small row group per execution.
+    let descs = table_strategy
+        .buffer_descs()
+        .map(|(_name, desc)| desc)
+        .collect();
+    let mut staged = StagedRows::new(descs, batch_size_row);
+
+    let mut num_executions = 0;
+    for row in reader {
+        let fields = row?.into_columns();
+        num_executions += 1;
+        let parameters = column_indices
+            .iter()
+            .map(|&index| {
+                let (name, field) = &fields[index];
+                Parameter::from_field(field)
+                    .map(|parameter| parameter.to_parameter())
+                    .with_context(|| {
+                        format!(
+                            "Invalid value in column '{name}' of parameter row {num_executions}"
+                        )
+                    })
+            })
+            .collect::<Result<Vec<Box<dyn InputParameter>>, _>>()?;
+        let Some(cursor) = prepared.execute(parameters.as_slice())? else {
+            bail!("Execution for parameter row {num_executions} did not return a result set.")
+        };
+        if let Some(buffer) = &mut buffer {
+            let mut fetch = BorrowedBufferFetch::new(cursor, buffer)?;
+            table_strategy.stage_batches(&mut fetch, &mut staged, &mut writer, &mut progress)?;
+        } else {
+            let mut fetch = row_by_row_fetch(cursor, &table_strategy, batch_size)?;
+            table_strategy.stage_batches(&mut fetch, &mut staged, &mut writer, &mut progress)?;
+        }
+    }
+    table_strategy.write_staged(&mut staged, &mut writer, &mut progress)?;
+    writer.close_box()?;
+    info!(num_executions, total_rows_fetched = progress.total_rows_fetched(); "Done");
+    Ok(())
+}
+
+/// Statement text with positional placeholders and, for each placeholder, the index of the
+/// parameter column bound to it. Placeholders written as `?name?` must name columns of the
+/// parameter file. If the query uses plain `?` placeholders, it is used as is and the columns are
+/// bound in order, so there must be exactly one placeholder per column.
+fn bind_columns(query: &str, column_names: &[String]) -> Result<(String, Vec<usize>), Error> {
+    // Plain placeholders followed by another one, e.g. `a=? AND b=?`, must not be mistaken for the
+    // name ` AND b=`. Only text which could be a column name is treated as placeholder name.
+    let is_name = |name: &str| {
+        column_names.iter().any(|column| column == name)
+            || (!name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || c == '_'))
+    };
+    let (statement_text, placeholders) = scan_placeholders(query, is_name);
+    let names: Vec<&str> = placeholders.iter().flatten().map(String::as_str).collect();
+    if names.is_empty() {
+        if placeholders.len() != column_names.len() {
+            bail!(
+                "The query has {} placeholders, but the parameter file has {} columns. Without \
+                named placeholders, each column is bound to one placeholder, in order.",
+                placeholders.len(),
+                column_names.len()
+            )
+        }
+        return Ok((statement_text, (0..column_names.len()).collect()));
+    }
+    if names.len() != placeholders.len() {
+        bail!("The query mixes named (`?name?`) and positional (`?`) placeholders.")
+    }
+    let column_indices = names
+        .iter()
+        .map(|name| {
+            column_names
+                .iter()
+                .position(|column| column == name)
+                .ok_or_else(|| {
+                    anyhow!("placeholder '{name}' is not a column of the parameter file")
+                })
+        })
+        .collect::<Result<_, _>>()?;
+    Ok((statement_text, column_indices))
+}
+
+/// Finds the placeholders in `query`. `?` within string literals (`'...'`) and quoted identifiers
+/// (`"..."` or `` `...` ``) is not a placeholder. Returns the statement text with positional
+/// placeholders, and for each placeholder its name, or `None` if it is a plain `?`. Text between
+/// two `?` is only taken as name if `is_name` accepts it.
+fn scan_placeholders(query: &str, is_name: impl Fn(&str) -> bool) -> (String, Vec<Option<String>>) {
+    let mut statement_text = String::with_capacity(query.len());
+    let mut placeholders = Vec::new();
+    // Quote character of the literal or identifier we are currently in, if any. Quotes escaped by
+    // doubling them (`'it''s'`) end the quoted text and immediately start it again, so they need
+    // no special treatment.
+    let mut quote = None;
+    let mut rest = query;
+    while let Some(c) = rest.chars().next() {
+        rest = &rest[c.len_utf8()..];
+        statement_text.push(c);
+        match (quote, c) {
+            (Some(open), c) if c == open => quote = None,
+            (Some(_), _) => (),
+            (None, '\'' | '"' | '`') => quote = Some(c),
+            (None, '?') => {
+                let name = rest
+                    .find('?')
+                    .map(|end| &rest[..end])
+                    .filter(|&name| is_name(name));
+                if let Some(name) = name {
+                    rest = &rest[name.len() + 1..];
+                }
+                placeholders.push(name.map(str::to_owned));
+            }
+            (None, _) => (),
+        }
+    }
+    (statement_text, placeholders)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::bind_columns;
+
+    #[test]
+    fn bind_parameter_columns_by_name_or_position() {
+        let columns = ["id".to_owned(), "name".to_owned()];
+
+        assert_eq!(
+            (
+                "SELECT * FROM t WHERE a=? AND b=? AND c=?".to_owned(),
+                vec![1, 0, 1]
+            ),
+            bind_columns(
+                "SELECT * FROM t WHERE a=?name? AND b=?id? AND c=?name?",
+                &columns
+            )
+            .unwrap()
+        );
+        assert_eq!(
+            ("SELECT * FROM t WHERE a=? AND b=?".to_owned(), vec![0, 1]),
+            bind_columns("SELECT * FROM t WHERE a=? AND b=?", &columns).unwrap()
+        );
+    }
+
+    #[test]
+    fn unknown_placeholder_name() {
+        let columns = ["id".to_owned(), "name".to_owned()];
+
+        let error = bind_columns("SELECT * FROM t WHERE a=?nmae?", &columns).unwrap_err();
+
+        assert_eq!(
+            "placeholder 'nmae' is not a column of the parameter file",
+            error.to_string()
+        );
+    }
+
+    #[test]
+    fn number_of_positional_placeholders_must_match_columns() {
+        let columns = ["id".to_owned(), "name".to_owned()];
+
+        assert!(bind_columns("SELECT * FROM t WHERE a=?", &columns).is_err());
+        assert!(bind_columns("SELECT * FROM t WHERE a=? AND b=? AND c=?", &columns).is_err());
+    }
+
+    #[test]
+    fn question_marks_in_quoted_text_are_not_placeholders() {
+        let columns = ["id".to_owned()];
+
+        assert_eq!(
+            ("SELECT '?' FROM t WHERE a=?".to_owned(), vec![0]),
+            bind_columns("SELECT '?' FROM t WHERE a=?", &columns).unwrap()
+        );
+        assert_eq!(
+            (
+                "SELECT \"what?\" FROM t WHERE a=? AND b='it''s ?id?'".to_owned(),
+                vec![0]
+            ),
+            bind_columns(
+                "SELECT \"what?\" FROM t WHERE a=?id? AND b='it''s ?id?'",
+                &columns
+            )
+            .unwrap()
+        );
+    }
+}
@@ -1,86 +1,110 @@
 use std::{
+    collections::{BTreeMap, HashMap},
+    fs::create_dir_all,
     io::{stdout, Write},
     path::{Path, PathBuf},
     sync::Arc,
 };
 
-use anyhow::{anyhow, format_err, Error};
+use anyhow::{Context, Error};
 use io_arg::IoArg;
 use parquet::{
+    arrow::add_encoded_arrow_schema_to_metadata,
     basic::{Compression, Encoding},
     file::{
-        properties::{WriterProperties, WriterVersion},
+        properties::{EnabledStatistics, WriterProperties, WriterVersion},
         writer::SerializedFileWriter,
     },
     schema::types::{ColumnPath, Type},
 };
 
+use crate::enum_args::BloomFilterArgument;
+
 use super::{
-    batch_size_limit::FileSizeLimit, conversion_strategy::ColumnExporter, current_file::CurrentFile,
+    arrow_schema::arrow_schema,
+    batch_size_limit::FileSizeLimit,
+    conversion_strategy::ColumnExporter,
+    current_file::{append_completion_metadata, CurrentFile},
+    manifest::Manifest,
+    output::{path_with_suffix, Output, OutputOptions},
 };
 
-/// Options influencing the output parquet file independent of schema or row content.
+/// Options only affecting parquet files.
+#[derive(Clone)]
 pub struct ParquetWriterOptions {
     /// Directly correlated to the `--column-compression-default` command line option
     pub column_compression_default: Compression,
     /// Tuples of column name and encoding which control the encoding for the associated columns.
     pub column_encodings: Vec<(String, Encoding)>,
-    /// Number of digits in the suffix, appended to the end of a file in case they are numbered.
-    pub suffix_length: usize,
-    /// A fuzzy limit for file size, causing the rest of the query to be written into new files if a
-    /// threshold is passed.
-    pub file_size: FileSizeLimit,
-    /// Do not create a file if no row was in the result set.
-    pub no_empty_file: bool,
+    /// Tuples of column name and compression, overwriting the default compression.
+    pub column_compressions: Vec<(String, Compression)>,
+    /// Tuples of column name and whether or not dictionary encoding is enabled for the column.
+    pub column_dictionaries: Vec<(String, bool)>,
+    /// Tuples of column name and the level of statistics written for the column.
+    pub column_statistics: Vec<(String, EnabledStatistics)>,
+    /// Columns for which to write bloom filters.
+    pub bloom_filters: Vec<BloomFilterArgument>,
+    /// Store the equivalent Arrow schema in the key value metadata, so Arrow based readers can
+    /// recover the exact types.
+    pub arrow_schema: bool,
 }
 
 pub fn parquet_output(
     output: IoArg,
     schema: Arc<Type>,
-    options: ParquetWriterOptions,
-) -> Result<Box<dyn ParquetOutput>, Error> {
+    options: OutputOptions,
+) -> Result<Box<dyn Output>, Error> {
+    let parquet = &options.parquet;
     // Write properties
     // Seems to also work fine without setting the batch size explicitly, but what the heck. Just to
     // be on the safe side.
     let mut wpb = WriterProperties::builder()
         .set_writer_version(WriterVersion::PARQUET_2_0)
-        .set_compression(options.column_compression_default);
-    for (column_name, encoding) in options.column_encodings.clone() {
+        .set_compression(parquet.column_compression_default)
+        .set_key_value_metadata(Some(options.key_value_metadata.clone()));
+    for (column_name, encoding) in parquet.column_encodings.clone() {
         let col = ColumnPath::new(vec![column_name]);
         wpb = wpb.set_column_encoding(col, encoding)
     }
-    let properties = Arc::new(wpb.build());
+    for (column_name, compression) in parquet.column_compressions.clone() {
+        let col = ColumnPath::new(vec![column_name]);
+        wpb = wpb.set_column_compression(col, compression)
+    }
+    for (column_name, enabled) in parquet.column_dictionaries.clone() {
+        let col = ColumnPath::new(vec![column_name]);
+        wpb = wpb.set_column_dictionary_enabled(col, enabled)
+    }
+    for (column_name, statistics) in parquet.column_statistics.clone() {
+        let col = ColumnPath::new(vec![column_name]);
+        wpb = wpb.set_column_statistics_enabled(col, statistics)
+    }
+    for bloom_filter in &parquet.bloom_filters {
+        let col = ColumnPath::new(vec![bloom_filter.column.clone()]);
+        wpb = wpb.set_column_bloom_filter_enabled(col.clone(), true);
+        if let Some(fpp) = bloom_filter.fpp {
+            wpb = wpb.set_column_bloom_filter_fpp(col.clone(), fpp);
+        }
+        if let Some(ndv) = bloom_filter.ndv {
+            wpb = wpb.set_column_bloom_filter_max_ndv(col, ndv);
+        }
+    }
+    let mut properties = wpb.build();
+    if parquet.arrow_schema {
+        add_encoded_arrow_schema_to_metadata(&arrow_schema(schema.clone())?, &mut properties);
+    }
+    let properties = Arc::new(properties);
 
-    let writer: Box<dyn ParquetOutput> = match output {
+    let writer: Box<dyn Output> = match output {
         IoArg::StdStream => Box::new(StandardOut::new(schema, properties)?),
+        IoArg::File(path) if options.partitioned => Box::new(PartitionedFileWriter::new(
+            path, schema, options, properties,
+        )),
         IoArg::File(path) => Box::new(FileWriter::new(path, schema, options, properties)?),
     };
 
     Ok(writer)
 }
 
-/// Writes row groups to the output, which could be either standard out, a single parquet file or
-/// multiple parquet files with incrementing number suffixes.
-pub trait ParquetOutput {
-    /// Retrieve the next row group writer. May trigger creation of a new file if limit of the
-    /// previous one is reached.
-    ///
-    /// # Parameters
-    ///
-    /// * `num_batch`: 1-based index of the batch being written.
-    fn write_row_group(
-        &mut self,
-        num_batch: u32,
-        export_nth_column: ColumnExporter,
-    ) -> Result<(), Error>;
-
-    /// Indicate that no further output is written. this triggers writing the parquet meta data and
-    /// potentially persists a temporary file.
-    fn close(self) -> Result<(), Error>;
-
-    fn close_box(self: Box<Self>) -> Result<(), Error>;
-}
-
 /// Wraps parquet SerializedFileWriter. Handles splitting into new files after maximum amount of
 /// batches is reached.
 struct FileWriter {
@@ -95,13 +119,15 @@ struct FileWriter {
     /// closed, due to the size threshold, but a new row group has not yet been received from the
     /// database.
     current_file: Option<CurrentFile>,
+    /// Records each completed file, if a manifest has been requested.
+    manifest: Option<Manifest>,
 }
 
 impl FileWriter {
     pub fn new(
         path: PathBuf,
         schema: Arc<Type>,
-        options: ParquetWriterOptions,
+        options: OutputOptions,
         properties: Arc<WriterProperties>,
     ) -> Result<Self, Error> {
         let mut file_writer = Self {
@@ -112,6 +138,7 @@ impl FileWriter {
             num_file: 0,
             suffix_length: options.suffix_length,
             current_file: None,
+            manifest: options.manifest,
         };
 
         if !options.no_empty_file {
@@ -146,11 +173,11 @@ impl FileWriter {
     }
 }
 
-impl ParquetOutput for FileWriter {
-    fn write_row_group(
+impl Output for FileWriter {
+    fn write_batch(
         &mut self,
         num_batch: u32,
-        column_exporter: ColumnExporter,
+        mut column_exporter: ColumnExporter,
     ) -> Result<(), Error> {
         // There is no file. Let us create one so we can write the row group.
         if self.current_file.is_none() {
@@ -162,10 +189,13 @@ impl ParquetOutput for FileWriter {
             .current_file
             .as_mut()
             .unwrap()
-            .write_row_group(column_exporter)?;
+            .write_row_group(&mut column_exporter)?;
 
         if self.file_size.file_limit_reached(num_batch, file_size) {
-            self.current_file.take().unwrap().finalize()?;
+            self.current_file
+                .take()
+                .unwrap()
+                .finalize(self.manifest.as_ref())?;
         }
 
         Ok(())
@@ -178,7 +208,124 @@ impl ParquetOutput for FileWriter {
         // current file due to its size threshold it is `None`. In this case there is nothing to do
         // though.
         if let Some(open_file) = self.current_file {
-            open_file.finalize()?;
+            open_file.finalize(self.manifest.as_ref())?;
+        }
+        Ok(())
+    }
+
+    fn close_box(self: Box<Self>) -> Result<(), Error> {
+        self.close()
+    }
+}
+
+/// Writes a hive style partitioned dataset. The rows of each batch are routed into a file in the
+/// directory of their partition, e.g. `out_dir/region=EU/year=2024/part-01.parquet`. One file is
+/// kept open for each partition.
+struct PartitionedFileWriter {
+    base_dir: PathBuf,
+    schema: Arc<Type>,
+    properties: Arc<WriterProperties>,
+    file_size: FileSizeLimit,
+    /// Length of the suffix, appended to the end of the files in each partition.
+    suffix_length: usize,
+    /// State of each partition we have seen so far. Key is the relative path of the partition
+    /// directory.
+    partitions: HashMap<PathBuf, Partition>,
+    /// Records each completed file, if a manifest has been requested.
+    manifest: Option<Manifest>,
+}
+
+#[derive(Default)]
+struct Partition {
+    /// Number of files created in the partition directory so far.
+    num_file: u32,
+    /// Number of row groups written into the current file.
+    num_row_groups: u32,
+    /// File currently open for writing. `None` if the last one has been closed due to the size
+    /// limit.
+    current_file: Option<CurrentFile>,
+}
+
+impl PartitionedFileWriter {
+    pub fn new(
+        base_dir: PathBuf,
+        schema: Arc<Type>,
+        options: OutputOptions,
+        properties: Arc<WriterProperties>,
+    ) -> Self {
+        Self {
+            base_dir,
+            schema,
+            properties,
+            file_size: options.file_size,
+            suffix_length: options.suffix_length,
+            partitions: HashMap::new(),
+            manifest: options.manifest,
+        }
+    }
+}
+
+impl Output for PartitionedFileWriter {
+    fn write_batch(
+        &mut self,
+        _num_batch: u32,
+        mut column_exporter: ColumnExporter,
+    ) -> Result<(), Error> {
+        // Group the indices of the rows in this batch by partition.
+        let mut rows_by_partition: BTreeMap<PathBuf, Vec<usize>> = BTreeMap::new();
+        for (row, dir) in column_exporter.partition_dirs()?.into_iter().enumerate() {
+            rows_by_partition.entry(dir).or_default().push(row);
+        }
+
+        for (dir, rows) in rows_by_partition {
+            column_exporter.select_rows(rows);
+            let partition = self.partitions.entry(dir.clone()).or_default();
+            if partition.current_file.is_none() {
+                let dir = self.base_dir.join(dir);
+                create_dir_all(&dir).with_context(|| {
+                    format!(
+                        "Could not create partition directory '{}'",
+                        dir.to_string_lossy()
+                    )
+                })?;
+                partition.num_file += 1;
+                partition.num_row_groups = 0;
+                let file_name = format!(
+                    "part-{:0width$}.parquet",
+                    partition.num_file,
+                    width = self.suffix_length
+                );
+                partition.current_file = Some(CurrentFile::new(
+                    dir.join(file_name),
+                    self.schema.clone(),
+                    self.properties.clone(),
+                )?);
+            }
+            let file_size = partition
+                .current_file
+                .as_mut()
+                .unwrap()
+                .write_row_group(&mut column_exporter)?;
+            partition.num_row_groups += 1;
+            if self
+                .file_size
+                .file_limit_reached(partition.num_row_groups, file_size)
+            {
+                partition
+                    .current_file
+                    .take()
+                    .unwrap()
+                    .finalize(self.manifest.as_ref())?;
+            }
+        }
+        Ok(())
+    }
+
+    fn close(self) -> Result<(), Error> {
+        for partition in self.partitions.into_values() {
+            if let Some(open_file) = partition.current_file {
+                open_file.finalize(self.manifest.as_ref())?;
+            }
         }
         Ok(())
     }
@@ -191,6 +338,8 @@ impl ParquetOutput for FileWriter {
 /// Stream parquet directly to standard out
 struct StandardOut {
     writer: SerializedFileWriter<Box<dyn Write + Send>>,
+    /// Keep track of the total number of rows written so far.
+    total_num_rows: u64,
 }
 
 impl StandardOut {
@@ -198,12 +347,15 @@ impl StandardOut {
         let output: Box<dyn Write + Send> = Box::new(stdout());
         let writer = SerializedFileWriter::new(output, schema.clone(), properties.clone())?;
 
-        Ok(Self { writer })
+        Ok(Self {
+            writer,
+            total_num_rows: 0,
+        })
     }
 }
 
-impl ParquetOutput for StandardOut {
-    fn write_row_group(
+impl Output for StandardOut {
+    fn write_batch(
         &mut self,
         _num_batch: u32,
         mut column_exporter: ColumnExporter,
@@ -215,11 +367,13 @@ impl ParquetOutput for StandardOut {
             column_writer.close()?;
             col_index += 1;
         }
-        row_group_writer.close()?;
+        let metadata = row_group_writer.close()?;
+        self.total_num_rows += u64::try_from(metadata.num_rows()).unwrap();
         Ok(())
     }
 
-    fn close(self) -> Result<(), Error> {
+    fn close(mut self) -> Result<(), Error> {
+        append_completion_metadata(&mut self.writer, self.total_num_rows);
         self.writer.close()?;
         Ok(())
     }
@@ -228,51 +382,3 @@ impl ParquetOutput for StandardOut {
         self.close()
     }
 }
-
-fn path_with_suffix(path: &Path, num_file: u32, suffix_length: usize) -> Result<PathBuf, Error> {
-    let suffix = format!("_{:0width$}", num_file, width = suffix_length);
-    let mut stem = path
-        .file_stem()
-        .ok_or_else(|| format_err!("Output needs To have a file stem."))?
-        .to_owned();
-    stem.push(suffix);
-    // Retain file extension (we can potentially simplify this once `Path::add_extension` is stable)
-    if let Some(extension) = path.extension() {
-        stem.push(format!(
-            ".{}",
-            extension
-                .to_str()
-                .ok_or(anyhow!("Output file extension is not valid UTF-8"))?
-        ));
-    }
-    let path_with_suffix = path.with_file_name(stem);
-    Ok(path_with_suffix)
-}
-
-#[cfg(test)]
-mod tests {
-
-    use std::path::PathBuf;
-
-    use super::path_with_suffix;
-
-    #[test]
-    fn filenames_with_suffixes() {
-        let path = path_with_suffix(&PathBuf::from("test.par"), 1, 2).unwrap();
-        assert_eq!(path.to_str().unwrap(), "test_01.par");
-    }
-
-    /// See: <https://github.com/pacman82/odbc2parquet/issues/754>
-    #[test]
-    fn filenames_with_dot_in_filestem_and_suffix() {
-        let path = path_with_suffix(&PathBuf::from("server-name-schema.table.par"), 1, 2).unwrap();
-        assert_eq!(path.to_str().unwrap(), "server-name-schema.table_01.par");
-    }
-
-    /// See: <https://github.com/pacman82/odbc2parquet/issues/754>
-    #[test]
-    fn retain_path_before_file_name() {
-        let path = path_with_suffix(&PathBuf::from("./some_path/out.par"), 1, 2).unwrap();
-        assert_eq!(path, PathBuf::from("./some_path/out_01.par"));
-    }
-}
@@ -0,0 +1,148 @@
+use std::fmt::Write;
+
+use anyhow::{bail, Error};
+use odbc_api::{
+    buffers::{AnyColumnBufferSlice, BufferDesc},
+    sys::{Date, Time, Timestamp},
+    Bit, Pod,
+};
+
+/// Name of the directory for `NULL` or empty partition values. Same as used by Hive and Spark.
+const DEFAULT_PARTITION: &str = "__HIVE_DEFAULT_PARTITION__";
+
+/// Message we emmit if we hit a code path we expected to be unreachable.
+const BUG: &str = "Invalid Column view type. This is not supposed to happen. Please open a Bug at \
+                  https://github.com/pacman82/odbc2parquet/issues.";
+
+/// Text representation of the value in `row`, used to name its partition directory. `None` if the
+/// value is `NULL`.
+pub fn partition_value(
+    column: AnyColumnBufferSlice,
+    desc: BufferDesc,
+    row: usize,
+) -> Result<Option<String>, Error> {
+    let value = match desc {
+        BufferDesc::Text { .. } => column
+            .as_text()
+            .expect(BUG)
+            .get(row)
+            .map(|text| String::from_utf8_lossy(text).into_owned()),
+        BufferDesc::WText { .. } => column
+            .as_wide_text()
+            .expect(BUG)
+            .get(row)
+            .map(String::from_utf16_lossy),
+        BufferDesc::Binary { .. } => column.as_binary().expect(BUG).get(row).map(|bytes| {
+            bytes.iter().fold(String::new(), |mut hex, byte| {
+                write!(hex, "{byte:02x}").unwrap();
+                hex
+            })
+        }),
+        BufferDesc::I8 { nullable } => pod::<i8>(column, nullable, row).map(|n| n.to_string()),
+        BufferDesc::I16 { nullable } => pod::<i16>(column, nullable, row).map(|n| n.to_string()),
+        BufferDesc::I32 { nullable } => pod::<i32>(column, nullable, row).map(|n| n.to_string()),
+        BufferDesc::I64 { nullable } => pod::<i64>(column, nullable, row).map(|n| n.to_string()),
+        BufferDesc::U8 { nullable } => pod::<u8>(column, nullable, row).map(|n| n.to_string()),
+        BufferDesc::F32 { nullable } => pod::<f32>(column, nullable, row).map(|n| n.to_string()),
+        BufferDesc::F64 { nullable } => pod::<f64>(column, nullable, row).map(|n| n.to_string()),
+        BufferDesc::Bit { nullable } => {
+            pod::<Bit>(column, nullable, row).map(|bit| bit.as_bool().to_string())
+        }
+        BufferDesc::Date { nullable } => pod::<Date>(column, nullable, row).map(format_date),
+        BufferDesc::Time { nullable } => pod::<Time>(column, nullable, row).map(format_time),
+        BufferDesc::Timestamp { nullable } => pod::<Timestamp>(column, nullable, row).map(|ts| {
+            let date = Date {
+                year: ts.year,
+                month: ts.month,
+                day: ts.day,
+            };
+            let time = Time {
+                hour: ts.hour,
+                minute: ts.minute,
+                second: ts.second,
+            };
+            format!("{} {}", format_date(date), format_time(time))
+        }),
+        BufferDesc::Numeric => {
+            bail!("Partitioning by columns fetched as numeric is not supported.")
+        }
+    };
+    Ok(value)
+}
+
+fn pod<T>(column: AnyColumnBufferSlice, nullable: bool, row: usize) -> Option<T>
+where
+    T: Pod,
+{
+    if nullable {
+        column
+            .as_nullable_slice::<T>()
+            .expect(BUG)
+            .get(row)
+            .copied()
+    } else {
+        Some(column.as_slice::<T>().expect(BUG)[row])
+    }
+}
+
+fn format_date(date: Date) -> String {
+    format!("{:04}-{:02}-{:02}", date.year, date.month, date.day)
+}
+
+fn format_time(time: Time) -> String {
+    format!("{:02}:{:02}:{:02}", time.hour, time.minute, time.second)
+}
+
+/// Name of a hive style partition directory, e.g. `region=EU`. Characters which are not safe to use
+/// in paths are percent encoded the same way Hive does.
+pub fn partition_dir_name(column_name: &str, value: Option<&str>) -> String {
+    let value = match value {
+        None | Some("") => DEFAULT_PARTITION,
+        Some(value) => value,
+    };
+    format!(
+        "{}={}",
+        escape_path_name(column_name),
+        escape_path_name(value)
+    )
+}
+
+pub fn escape_path_name(name: &str) -> String {
+    let mut escaped = String::with_capacity(name.len());
+    for c in name.chars() {
+        if c.is_ascii_control()
+            || matches!(
+                c,
+                '"' | '#' | '%' | '\'' | '*' | '/' | ':' | '=' | '?' | '\\' | '{' | '[' | ']' | '^'
+            )
+        {
+            write!(escaped, "%{:02X}", c as u32).unwrap();
+        } else {
+            escaped.push(c);
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::partition_dir_name;
+
+    #[test]
+    fn hive_partition_dir_names() {
+        assert_eq!("region=EU", partition_dir_name("region", Some("EU")));
+        assert_eq!(
+            "ts=2024-01-02 03%3A04%3A05",
+            partition_dir_name("ts", Some("2024-01-02 03:04:05"))
+        );
+        assert_eq!("path=a%2Fb%3Dc", partition_dir_name("path", Some("a/b=c")));
+        assert_eq!(
+            "year=__HIVE_DEFAULT_PARTITION__",
+            partition_dir_name("year", None)
+        );
+        assert_eq!(
+            "year=__HIVE_DEFAULT_PARTITION__",
+            partition_dir_name("year", Some(""))
+        );
+    }
+}
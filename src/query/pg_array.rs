@@ -0,0 +1,394 @@
+use std::{num::NonZeroUsize, str::FromStr, sync::Arc};
+
+use anyhow::{anyhow, bail, Context, Error};
+use arrow_array::ArrayRef;
+use arrow_schema::DataType as ArrowDataType;
+use log::debug;
+use odbc_api::{
+    buffers::{AnyColumnBufferSlice, BufferDesc},
+    DataType as OdbcDataType, ResultSetMetadata,
+};
+use parquet::{
+    basic::{ConvertedType, IntType, LogicalType, Repetition, Type as PhysicalType},
+    column::writer::{get_typed_column_writer_mut, ColumnWriter},
+    data_type::{
+        BoolType, ByteArray, ByteArrayType, DataType, DoubleType, FloatType, Int32Type, Int64Type,
+    },
+    schema::types::Type,
+};
+
+use crate::parquet_buffer::{BufferedDataType, OptionalList, ParquetBuffer};
+
+use super::{
+    arrow_column::{list_array, ArrowValue},
+    column_strategy::ColumnStrategy,
+    type_name::column_type_name,
+};
+
+/// Elements of PostgreSQL arrays we know how to map onto a parquet `LIST`.
+#[derive(Clone, Copy, Debug)]
+enum Element {
+    SmallInt,
+    Integer,
+    BigInt,
+    Real,
+    Double,
+    Boolean,
+    Text,
+}
+
+impl Element {
+    /// PostgreSQL names array types after their element type with a leading underscore. E.g. the
+    /// type name of `int4[]` is `_int4`.
+    fn from_type_name(type_name: &str) -> Option<Self> {
+        let element = match type_name.strip_prefix('_')? {
+            "int2" => Element::SmallInt,
+            "int4" => Element::Integer,
+            "int8" => Element::BigInt,
+            "float4" => Element::Real,
+            "float8" => Element::Double,
+            "bool" => Element::Boolean,
+            "text" | "varchar" | "bpchar" | "name" => Element::Text,
+            _ => return None,
+        };
+        Some(element)
+    }
+
+    fn parquet_type(self) -> Type {
+        let integer = |bit_width| {
+            Some(LogicalType::Integer(IntType {
+                bit_width,
+                is_signed: true,
+            }))
+        };
+        let builder = match self {
+            Element::SmallInt => Type::primitive_type_builder("element", PhysicalType::INT32)
+                .with_logical_type(integer(16)),
+            Element::Integer => Type::primitive_type_builder("element", PhysicalType::INT32)
+                .with_logical_type(integer(32)),
+            Element::BigInt => Type::primitive_type_builder("element", PhysicalType::INT64),
+            Element::Real => Type::primitive_type_builder("element", PhysicalType::FLOAT),
+            Element::Double => Type::primitive_type_builder("element", PhysicalType::DOUBLE),
+            Element::Boolean => Type::primitive_type_builder("element", PhysicalType::BOOLEAN),
+            Element::Text => Type::primitive_type_builder("element", PhysicalType::BYTE_ARRAY)
+                .with_converted_type(ConvertedType::UTF8),
+        };
+        // Array elements in PostgreSQL can always be NULL.
+        builder
+            .with_repetition(Repetition::OPTIONAL)
+            .build()
+            .unwrap()
+    }
+}
+
+/// Strategy for PostgreSQL array columns, if the column at `index` is one, with an element type we
+/// support. `None` otherwise. The psqlODBC driver reports arrays as character data, so we can not
+/// tell them apart by their SQL data type and have to inspect the name of the type instead.
+pub fn postgres_array_strategy(
+    data_type: &OdbcDataType,
+    cursor: &mut impl ResultSetMetadata,
+    index: i16,
+    repetition: Repetition,
+    use_utf16: bool,
+    apply_length_limit: impl FnOnce(Option<NonZeroUsize>) -> usize,
+) -> Result<Option<Box<dyn ColumnStrategy>>, Error> {
+    let Some(type_name) = column_type_name(cursor, index.try_into().unwrap()) else {
+        return Ok(None);
+    };
+    let Some(element) = Element::from_type_name(&type_name) else {
+        return Ok(None);
+    };
+    debug!(
+        column_index = index, type_name, element:?; "Detected array => Mapping to parquet LIST"
+    );
+    let reported_length = if use_utf16 {
+        data_type.utf16_len()
+    } else {
+        data_type.utf8_len()
+    };
+    let reported_length = match reported_length {
+        Some(len) => Some(len),
+        None => cursor.col_display_size(index.try_into().unwrap())?,
+    };
+    let length = apply_length_limit(reported_length);
+    Ok(Some(Box::new(PostgresArray {
+        repetition,
+        element,
+        use_utf16,
+        length,
+    })))
+}
+
+/// Fetches the text representation of a one dimensional PostgreSQL array (e.g. `{1,2,NULL}`) and
+/// stores it as a three level parquet `LIST`.
+struct PostgresArray {
+    repetition: Repetition,
+    element: Element,
+    use_utf16: bool,
+    /// Length of the text representation in bytes or `u16`, depending on `use_utf16`.
+    length: usize,
+}
+
+impl ColumnStrategy for PostgresArray {
+    fn parquet_type(&self, name: &str) -> Type {
+        let list = Type::group_type_builder("list")
+            .with_repetition(Repetition::REPEATED)
+            .with_fields(vec![Arc::new(self.element.parquet_type())])
+            .build()
+            .unwrap();
+        Type::group_type_builder(name)
+            .with_logical_type(Some(LogicalType::List))
+            .with_repetition(self.repetition)
+            .with_fields(vec![Arc::new(list)])
+            .build()
+            .unwrap()
+    }
+
+    fn buffer_desc(&self) -> BufferDesc {
+        if self.use_utf16 {
+            BufferDesc::WText {
+                max_str_len: self.length,
+            }
+        } else {
+            BufferDesc::Text {
+                max_str_len: self.length,
+            }
+        }
+    }
+
+    fn copy_odbc_to_parquet(
+        &self,
+        parquet_buffer: &mut ParquetBuffer,
+        column_writer: &mut ColumnWriter,
+        column_view: AnyColumnBufferSlice,
+    ) -> Result<(), Error> {
+        let arrays = self.arrays(column_view)?;
+        let is_optional = self.repetition == Repetition::OPTIONAL;
+        let pb = parquet_buffer;
+        let cw = column_writer;
+        match self.element {
+            Element::SmallInt | Element::Integer => {
+                write_arrays::<Int32Type>(pb, cw, arrays, is_optional, |e| parse_element(&e))
+            }
+            Element::BigInt => {
+                write_arrays::<Int64Type>(pb, cw, arrays, is_optional, |e| parse_element(&e))
+            }
+            Element::Real => {
+                write_arrays::<FloatType>(pb, cw, arrays, is_optional, |e| parse_element(&e))
+            }
+            Element::Double => {
+                write_arrays::<DoubleType>(pb, cw, arrays, is_optional, |e| parse_element(&e))
+            }
+            Element::Boolean => write_arrays::<BoolType>(pb, cw, arrays, is_optional, parse_bool),
+            Element::Text => write_arrays::<ByteArrayType>(pb, cw, arrays, is_optional, |e| {
+                Ok(ByteArray::from(e.into_bytes()))
+            }),
+        }
+    }
+
+    fn copy_odbc_to_arrow(
+        &self,
+        data_type: &ArrowDataType,
+        column_view: AnyColumnBufferSlice,
+    ) -> Result<ArrayRef, Error> {
+        let arrays = self.arrays(column_view)?;
+        match self.element {
+            Element::SmallInt | Element::Integer => {
+                arrow_arrays::<i32>(arrays, data_type, |e| parse_element(&e))
+            }
+            Element::BigInt => arrow_arrays::<i64>(arrays, data_type, |e| parse_element(&e)),
+            Element::Real => arrow_arrays::<f32>(arrays, data_type, |e| parse_element(&e)),
+            Element::Double => arrow_arrays::<f64>(arrays, data_type, |e| parse_element(&e)),
+            Element::Boolean => arrow_arrays::<bool>(arrays, data_type, parse_bool),
+            Element::Text => arrow_arrays::<String>(arrays, data_type, Ok),
+        }
+    }
+}
+
+impl PostgresArray {
+    /// Parses the text representation of each array into its elements.
+    fn arrays(
+        &self,
+        column_view: AnyColumnBufferSlice,
+    ) -> Result<Vec<OptionalList<String>>, Error> {
+        let arrays = if self.use_utf16 {
+            let view = column_view.as_wide_text().expect(
+                "Invalid Column view type. This is not supposed to happen. Please open a Bug at \
+                https://github.com/pacman82/odbc2parquet/issues.",
+            );
+            view.iter()
+                .map(|item| {
+                    item.map(|text| parse_array_literal(&String::from_utf16(text.as_slice())?))
+                        .transpose()
+                })
+                .collect::<Result<Vec<_>, Error>>()?
+        } else {
+            let view = column_view.as_text().expect(
+                "Invalid Column view type. This is not supposed to happen. Please open a Bug at \
+                https://github.com/pacman82/odbc2parquet/issues.",
+            );
+            view.iter()
+                .map(|item| {
+                    item.map(|text| parse_array_literal(std::str::from_utf8(text)?))
+                        .transpose()
+                })
+                .collect::<Result<Vec<_>, Error>>()?
+        };
+        Ok(arrays)
+    }
+}
+
+fn write_arrays<Pdt>(
+    pb: &mut ParquetBuffer,
+    column_writer: &mut ColumnWriter,
+    arrays: Vec<OptionalList<String>>,
+    is_optional: bool,
+    parse: impl Fn(String) -> Result<Pdt::T, Error>,
+) -> Result<(), Error>
+where
+    Pdt: DataType,
+    Pdt::T: BufferedDataType + Default + Clone,
+{
+    let cw = get_typed_column_writer_mut::<Pdt>(column_writer);
+    pb.write_list(cw, parse_elements(arrays, parse), is_optional)
+}
+
+fn arrow_arrays<T>(
+    arrays: Vec<OptionalList<String>>,
+    data_type: &ArrowDataType,
+    parse: impl Fn(String) -> Result<T, Error>,
+) -> Result<ArrayRef, Error>
+where
+    T: ArrowValue,
+{
+    let lists = parse_elements(arrays, parse).collect::<Result<Vec<_>, _>>()?;
+    list_array(lists, data_type)
+}
+
+fn parse_elements<T>(
+    arrays: Vec<OptionalList<String>>,
+    parse: impl Fn(String) -> Result<T, Error>,
+) -> impl Iterator<Item = Result<OptionalList<T>, Error>> {
+    arrays.into_iter().map(move |array| {
+        array
+            .map(|elements| {
+                elements
+                    .into_iter()
+                    .map(|element| element.map(&parse).transpose())
+                    .collect::<Result<Vec<_>, Error>>()
+            })
+            .transpose()
+    })
+}
+
+fn parse_bool(text: String) -> Result<bool, Error> {
+    match text.as_str() {
+        "t" | "true" => Ok(true),
+        "f" | "false" => Ok(false),
+        _ => bail!("Invalid boolean array element: '{text}'"),
+    }
+}
+
+fn parse_element<T>(text: &str) -> Result<T, Error>
+where
+    T: FromStr,
+    T::Err: std::error::Error + Send + Sync + 'static,
+{
+    text.parse()
+        .with_context(|| format!("Invalid array element: '{text}'"))
+}
+
+/// Parses the text representation of a one dimensional PostgreSQL array, e.g. `{1,2,NULL}` or
+/// `{"a b","c\"d"}`. Unquoted `NULL` elements are mapped to `None`.
+fn parse_array_literal(text: &str) -> Result<Vec<Option<String>>, Error> {
+    let invalid = || anyhow!("Invalid array literal: '{text}'");
+    // Arrays with lower bounds other than one are prefixed with their dimensions, e.g.
+    // `[0:1]={1,2}`.
+    let literal = match text.find('=') {
+        Some(pos) if text.starts_with('[') => &text[pos + 1..],
+        _ => text,
+    };
+    let inner = literal
+        .trim()
+        .strip_prefix('{')
+        .and_then(|rest| rest.strip_suffix('}'))
+        .ok_or_else(invalid)?;
+
+    let mut elements = Vec::new();
+    if inner.trim().is_empty() {
+        return Ok(elements);
+    }
+    let mut chars = inner.chars().peekable();
+    loop {
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        let element = match chars.peek() {
+            Some('{') => bail!("Multidimensional arrays are not supported: '{text}'"),
+            Some('"') => {
+                chars.next();
+                let mut value = String::new();
+                loop {
+                    match chars.next().ok_or_else(invalid)? {
+                        '"' => break,
+                        '\\' => value.push(chars.next().ok_or_else(invalid)?),
+                        c => value.push(c),
+                    }
+                }
+                while chars.next_if(|c| c.is_whitespace()).is_some() {}
+                Some(value)
+            }
+            _ => {
+                let mut value = String::new();
+                while let Some(c) = chars.next_if(|&c| c != ',') {
+                    if c == '\\' {
+                        value.push(chars.next().ok_or_else(invalid)?);
+                    } else {
+                        value.push(c);
+                    }
+                }
+                let value = value.trim_end();
+                if value.eq_ignore_ascii_case("NULL") {
+                    None
+                } else {
+                    Some(value.to_owned())
+                }
+            }
+        };
+        elements.push(element);
+        match chars.next() {
+            Some(',') => (),
+            None => break,
+            Some(_) => return Err(invalid()),
+        }
+    }
+    Ok(elements)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_array_literal;
+
+    #[test]
+    fn parse_postgres_array_literals() {
+        let owned = |elements: &[Option<&str>]| {
+            elements
+                .iter()
+                .map(|e| e.map(str::to_owned))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(owned(&[]), parse_array_literal("{}").unwrap());
+        assert_eq!(
+            owned(&[Some("1"), None, Some("3")]),
+            parse_array_literal("{1,NULL,3}").unwrap()
+        );
+        assert_eq!(
+            owned(&[Some("a b"), Some("c\"d"), Some("NULL"), Some("e")]),
+            parse_array_literal(r#"{"a b","c\"d","NULL",e}"#).unwrap()
+        );
+        assert_eq!(
+            owned(&[Some("1"), Some("2")]),
+            parse_array_literal("[0:1]={1,2}").unwrap()
+        );
+        assert!(parse_array_literal("{{1,2},{3,4}}").is_err());
+        assert!(parse_array_literal("1,2").is_err());
+    }
+}
@@ -0,0 +1,213 @@
+use anyhow::{bail, Context, Error};
+use chrono::{DateTime, NaiveDate};
+use log::info;
+use odbc_api::{
+    sys::{Date, Timestamp},
+    Connection, Cursor, DataType, Nullable, ParameterCollectionRef, ResultSetMetadata,
+};
+
+use super::identifier::{identifier_quote, quote_identifier};
+
+/// Rewrites `query` into up to `parallelism` sub-queries, each of which selects a range of the
+/// values of the column `split_by`. Together the sub-queries return the same rows as the original
+/// query. Ranges are of (roughly) equal width between the minimum and maximum value of the column,
+/// which are discovered by querying the data source. Rows with `NULL` in the split column are
+/// returned by the first sub-query. `split_by` is quoted, so it must match the name of the column
+/// in the result set exactly.
+pub fn range_split_queries(
+    conn: &Connection,
+    query: &str,
+    params: impl ParameterCollectionRef,
+    split_by: &str,
+    parallelism: usize,
+    query_timeout_sec: Option<usize>,
+) -> Result<Vec<String>, Error> {
+    let query = query.trim_end().trim_end_matches(';');
+    let db_name = conn.database_management_system_name()?;
+    let column = quote_identifier(split_by, identifier_quote(&db_name));
+    let min_max = format!("SELECT MIN({column}), MAX({column}) FROM ({query}) split_query");
+    let cursor = conn
+        .execute(&min_max, params, query_timeout_sec)
+        .map_err(Error::from)
+        .with_context(|| {
+            if db_name == "Microsoft SQL Server" && query.to_uppercase().contains("ORDER BY") {
+                "`--split-by` wraps the query in a derived table. Microsoft SQL Server does not \
+                allow `ORDER BY` in derived tables, unless `TOP` or `OFFSET` is specified as well. \
+                Please remove the `ORDER BY` clause from the query."
+                    .to_owned()
+            } else {
+                format!("Failed to determine the range of split column '{split_by}'.")
+            }
+        })?;
+    let Some(mut cursor) = cursor else {
+        bail!("Query to determine the range of split column '{split_by}' returned no result set.")
+    };
+    let data_type = cursor.col_data_type(1)?;
+    let Some(mut row) = cursor.next_row()? else {
+        bail!("Query to determine the range of split column '{split_by}' returned no row.")
+    };
+    let boundaries = match data_type {
+        DataType::TinyInt
+        | DataType::SmallInt
+        | DataType::Integer
+        | DataType::BigInt
+        | DataType::Numeric { scale: 0, .. }
+        | DataType::Decimal { scale: 0, .. } => {
+            let mut min = Nullable::<i64>::null();
+            let mut max = Nullable::<i64>::null();
+            row.get_data(1, &mut min)?;
+            row.get_data(2, &mut max)?;
+            match (min.into_opt(), max.into_opt()) {
+                (Some(min), Some(max)) => split_points(min, max, parallelism)
+                    .into_iter()
+                    .map(|n| n.to_string())
+                    .collect(),
+                _ => Vec::new(),
+            }
+        }
+        DataType::Date => {
+            let mut min = Nullable::<Date>::null();
+            let mut max = Nullable::<Date>::null();
+            row.get_data(1, &mut min)?;
+            row.get_data(2, &mut max)?;
+            match (min.into_opt(), max.into_opt()) {
+                (Some(min), Some(max)) => {
+                    split_points(days_since_epoch(min)?, days_since_epoch(max)?, parallelism)
+                        .into_iter()
+                        .map(|days| {
+                            let date = DateTime::from_timestamp(days * 24 * 60 * 60, 0)
+                                .unwrap()
+                                .date_naive();
+                            format!("{{d '{}'}}", date.format("%Y-%m-%d"))
+                        })
+                        .collect()
+                }
+                _ => Vec::new(),
+            }
+        }
+        DataType::Timestamp { .. } => {
+            let mut min = Nullable::<Timestamp>::null();
+            let mut max = Nullable::<Timestamp>::null();
+            row.get_data(1, &mut min)?;
+            row.get_data(2, &mut max)?;
+            match (min.into_opt(), max.into_opt()) {
+                (Some(min), Some(max)) => split_points(
+                    seconds_since_epoch(min)?,
+                    seconds_since_epoch(max)?,
+                    parallelism,
+                )
+                .into_iter()
+                .map(|seconds| {
+                    let timestamp = DateTime::from_timestamp(seconds, 0).unwrap().naive_utc();
+                    format!("{{ts '{}'}}", timestamp.format("%Y-%m-%d %H:%M:%S"))
+                })
+                .collect(),
+                _ => Vec::new(),
+            }
+        }
+        other => bail!(
+            "Split column '{split_by}' must be an integer, date or timestamp column. Its type is \
+            {other:?}."
+        ),
+    };
+
+    let queries = range_predicates(&column, &boundaries)
+        .into_iter()
+        .map(|predicate| {
+            if let Some(predicate) = predicate {
+                format!("SELECT * FROM ({query}) split_query WHERE {predicate}")
+            } else {
+                query.to_owned()
+            }
+        })
+        .collect::<Vec<_>>();
+    for (index, query) in queries.iter().enumerate() {
+        info!(range = index + 1, query; "Range query");
+    }
+    Ok(queries)
+}
+
+fn days_since_epoch(date: Date) -> Result<i64, Error> {
+    let Some(date) = NaiveDate::from_ymd_opt(date.year.into(), date.month.into(), date.day.into())
+    else {
+        bail!("Invalid date in split column: {date:?}")
+    };
+    Ok(date.signed_duration_since(NaiveDate::default()).num_days())
+}
+
+fn seconds_since_epoch(ts: Timestamp) -> Result<i64, Error> {
+    let Some(timestamp) = NaiveDate::from_ymd_opt(ts.year.into(), ts.month.into(), ts.day.into())
+        .and_then(|date| date.and_hms_opt(ts.hour.into(), ts.minute.into(), ts.second.into()))
+    else {
+        bail!("Invalid timestamp in split column: {ts:?}")
+    };
+    Ok(timestamp.and_utc().timestamp())
+}
+
+/// Values splitting the interval `[min, max]` into up to `parallelism` ranges of equal width. The
+/// first range starts at `min` and ends before the first split point, the last one starts at the
+/// last split point and ends with `max` (inclusive).
+fn split_points(min: i64, max: i64, parallelism: usize) -> Vec<i64> {
+    let min = i128::from(min);
+    let span = i128::from(max) - min + 1;
+    let num_ranges = (parallelism as i128).min(span).max(1);
+    (1..num_ranges)
+        .map(|n| (min + span * n / num_ranges) as i64)
+        .collect()
+}
+
+/// Predicates for the ranges between the boundaries. The first range is open towards the bottom
+/// and includes `NULL`, the last one is open towards the top. This way each row is part of exactly
+/// one range, even if the boundaries have been rounded. `None` if there are no boundaries, i.e.
+/// there is only one range containing everything.
+fn range_predicates(column: &str, boundaries: &[String]) -> Vec<Option<String>> {
+    if boundaries.is_empty() {
+        return vec![None];
+    }
+    let mut predicates = Vec::with_capacity(boundaries.len() + 1);
+    predicates.push(Some(format!(
+        "{column} < {} OR {column} IS NULL",
+        boundaries[0]
+    )));
+    for window in boundaries.windows(2) {
+        predicates.push(Some(format!(
+            "{column} >= {} AND {column} < {}",
+            window[0], window[1]
+        )));
+    }
+    predicates.push(Some(format!(
+        "{column} >= {}",
+        boundaries[boundaries.len() - 1]
+    )));
+    predicates
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{range_predicates, split_points};
+
+    #[test]
+    fn split_interval_into_ranges() {
+        assert_eq!(vec![25, 50, 75], split_points(0, 99, 4));
+        assert_eq!(vec![4, 7], split_points(1, 10, 3));
+        // Fewer values than parallelism
+        assert_eq!(vec![2, 3], split_points(1, 3, 8));
+        assert!(split_points(5, 5, 8).is_empty());
+        // Does not overflow
+        assert_eq!(vec![0], split_points(i64::MIN, i64::MAX, 2));
+    }
+
+    #[test]
+    fn predicates_for_ranges() {
+        let boundaries = ["10".to_owned(), "20".to_owned()];
+        assert_eq!(
+            vec![
+                Some("id < 10 OR id IS NULL".to_owned()),
+                Some("id >= 10 AND id < 20".to_owned()),
+                Some("id >= 20".to_owned()),
+            ],
+            range_predicates("id", &boundaries)
+        );
+        assert_eq!(vec![None], range_predicates("id", &[]));
+    }
+}
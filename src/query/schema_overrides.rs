@@ -0,0 +1,363 @@
+use std::{cmp::max, collections::HashMap, fs, num::NonZeroUsize, path::Path};
+
+use anyhow::{bail, Context, Error};
+use log::warn;
+use odbc_api::{sys::SqlDataType, DataType, Nullability};
+use serde::Deserialize;
+
+use super::column_strategy::MappingOptions;
+
+/// Types of result set columns pinned by the user, rather than inferred from the column
+/// description reported by the ODBC driver. Read from a TOML file, with one table per column name.
+/// E.g.
+///
+/// ```toml
+/// [amount]
+/// type = "decimal"
+/// precision = 10
+/// scale = 2
+///
+/// [comment]
+/// length = 200
+/// nullable = true
+/// ```
+#[derive(Deserialize, Default)]
+#[serde(transparent)]
+pub struct SchemaOverrides {
+    columns: HashMap<String, ColumnOverride>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+struct ColumnOverride {
+    /// Type of the output column. If not specified the type reported by the driver is kept.
+    #[serde(rename = "type")]
+    column_type: Option<ColumnType>,
+    /// `false` maps the column to a `REQUIRED` parquet column, `true` to an `OPTIONAL` one.
+    nullable: Option<bool>,
+    /// Maximum element length of text (in characters) and binary (in bytes) columns.
+    length: Option<NonZeroUsize>,
+    /// Total number of digits of decimal columns.
+    precision: Option<u8>,
+    /// Number of digits to the right of the radix character of decimal columns.
+    scale: Option<i16>,
+    /// Unit of time and timestamp columns.
+    unit: Option<TimeUnit>,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+enum ColumnType {
+    Boolean,
+    Int8,
+    Int16,
+    Int32,
+    Int64,
+    Float,
+    Double,
+    Decimal,
+    Date,
+    Time,
+    Timestamp,
+    Text,
+    Binary,
+    Uuid,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy)]
+enum TimeUnit {
+    #[serde(rename = "ms")]
+    Milliseconds,
+    #[serde(rename = "us")]
+    Microseconds,
+    #[serde(rename = "ns")]
+    Nanoseconds,
+}
+
+impl TimeUnit {
+    /// Number of fractional second digits, which causes our mapping to pick this unit.
+    fn precision(self) -> i16 {
+        match self {
+            TimeUnit::Milliseconds => 3,
+            TimeUnit::Microseconds => 6,
+            TimeUnit::Nanoseconds => 9,
+        }
+    }
+}
+
+impl SchemaOverrides {
+    /// Parses the overrides from a TOML file.
+    pub fn from_file(path: &Path) -> Result<Self, Error> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Could not read schema overrides file {path:?}"))?;
+        Self::from_toml(&content)
+            .with_context(|| format!("Could not parse schema overrides file {path:?}"))
+    }
+
+    fn from_toml(content: &str) -> Result<Self, Error> {
+        Ok(toml::from_str(content)?)
+    }
+
+    /// Replaces the description of the column reported by the driver with the overrides specified
+    /// by the user. Descriptions of columns without overrides are returned unchanged.
+    pub fn apply(
+        &self,
+        name: &str,
+        data_type: DataType,
+        nullability: Nullability,
+    ) -> Result<(DataType, Nullability), Error> {
+        let Some(column_override) = self.columns.get(name) else {
+            return Ok((data_type, nullability));
+        };
+        let data_type = column_override
+            .data_type(data_type)
+            .with_context(|| format!("Invalid schema override for column '{name}'"))?;
+        let nullability = match column_override.nullable {
+            None => nullability,
+            Some(true) => Nullability::Nullable,
+            Some(false) => Nullability::NoNulls,
+        };
+        Ok((data_type, nullability))
+    }
+
+    /// Explicit choices for a column take precedence over global mapping options. I.e. an
+    /// overridden length is not capped by `--column-length-limit`, an overridden decimal type is
+    /// not avoided due to `--avoid-decimal` and an overridden unit replaces `--timestamp-unit`.
+    pub fn mapping_options<'a>(
+        &self,
+        name: &str,
+        mut mapping_options: MappingOptions<'a>,
+    ) -> MappingOptions<'a> {
+        if let Some(column_override) = self.columns.get(name) {
+            if let Some(length) = column_override.length {
+                mapping_options.column_length_limit =
+                    max(mapping_options.column_length_limit, length.get());
+            }
+            if matches!(column_override.column_type, Some(ColumnType::Decimal)) {
+                mapping_options.avoid_decimal = false;
+            }
+            if column_override.unit.is_some() {
+                mapping_options.timestamp_encoding = None;
+            }
+        }
+        mapping_options
+    }
+
+    /// Warns about overrides for columns, which are not part of the result set. These are likely
+    /// typos.
+    pub fn warn_about_unknown_columns<'a>(&self, column_names: impl Iterator<Item = &'a str>) {
+        let column_names: Vec<_> = column_names.collect();
+        for name in self.columns.keys() {
+            if !column_names.contains(&name.as_str()) {
+                warn!(
+                    "Schema override for column '{name}' is ignored, since it is not part of the \
+                    result set."
+                );
+            }
+        }
+    }
+}
+
+impl ColumnOverride {
+    fn data_type(&self, reported: DataType) -> Result<DataType, Error> {
+        let mut data_type = match self.column_type {
+            None => reported,
+            Some(ColumnType::Boolean) => DataType::Bit,
+            Some(ColumnType::Int8) => DataType::TinyInt,
+            Some(ColumnType::Int16) => DataType::SmallInt,
+            Some(ColumnType::Int32) => DataType::Integer,
+            Some(ColumnType::Int64) => DataType::BigInt,
+            Some(ColumnType::Float) => DataType::Real,
+            Some(ColumnType::Double) => DataType::Double,
+            Some(ColumnType::Date) => DataType::Date,
+            Some(ColumnType::Uuid) => DataType::Other {
+                data_type: SqlDataType::EXT_GUID,
+                column_size: None,
+                decimal_digits: 0,
+            },
+            // For the remaining types we keep the details reported by the driver, if it reported
+            // the same kind of type.
+            Some(ColumnType::Decimal) => match reported {
+                DataType::Numeric { .. } | DataType::Decimal { .. } => reported,
+                _ => DataType::Decimal {
+                    precision: 0,
+                    scale: 0,
+                },
+            },
+            Some(ColumnType::Time) => match reported {
+                DataType::Time { .. } => reported,
+                _ => DataType::Time { precision: 0 },
+            },
+            Some(ColumnType::Timestamp) => match reported {
+                DataType::Timestamp { .. } => reported,
+                _ => DataType::Timestamp { precision: 6 },
+            },
+            Some(ColumnType::Text) => match reported {
+                DataType::Char { .. }
+                | DataType::Varchar { .. }
+                | DataType::LongVarchar { .. }
+                | DataType::WChar { .. }
+                | DataType::WVarchar { .. }
+                | DataType::WLongVarchar { .. } => reported,
+                _ => DataType::Varchar { length: None },
+            },
+            Some(ColumnType::Binary) => match reported {
+                DataType::Binary { .. }
+                | DataType::Varbinary { .. }
+                | DataType::LongVarbinary { .. } => reported,
+                _ => DataType::Varbinary { length: None },
+            },
+        };
+
+        if let Some(new_length) = self.length {
+            match &mut data_type {
+                DataType::Char { length }
+                | DataType::Varchar { length }
+                | DataType::LongVarchar { length }
+                | DataType::WChar { length }
+                | DataType::WVarchar { length }
+                | DataType::WLongVarchar { length }
+                | DataType::Binary { length }
+                | DataType::Varbinary { length }
+                | DataType::LongVarbinary { length } => *length = Some(new_length),
+                _ => bail!("'length' can only be specified for text and binary columns."),
+            }
+        }
+
+        if let Some(unit) = self.unit {
+            match &mut data_type {
+                DataType::Time { precision } | DataType::Timestamp { precision } => {
+                    *precision = unit.precision()
+                }
+                _ => bail!("'unit' can only be specified for time and timestamp columns."),
+            }
+        }
+
+        if self.precision.is_some() || self.scale.is_some() {
+            match &mut data_type {
+                DataType::Numeric { precision, scale } | DataType::Decimal { precision, scale } => {
+                    if let Some(new_precision) = self.precision {
+                        *precision = new_precision.into();
+                    }
+                    if let Some(new_scale) = self.scale {
+                        *scale = new_scale;
+                    }
+                }
+                _ => bail!("'precision' and 'scale' can only be specified for decimal columns."),
+            }
+        }
+
+        if let DataType::Numeric { precision, scale } | DataType::Decimal { precision, scale } =
+            data_type
+        {
+            if precision == 0 {
+                bail!("Decimal columns require a 'precision'.")
+            }
+            if scale < 0 || scale as usize > precision {
+                bail!("'scale' must be between 0 and 'precision'.")
+            }
+        }
+
+        Ok(data_type)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZeroUsize;
+
+    use odbc_api::{DataType, Nullability};
+
+    use super::SchemaOverrides;
+
+    #[test]
+    fn override_reported_column_types() {
+        let overrides = SchemaOverrides::from_toml(
+            r#"
+            [amount]
+            type = "decimal"
+            precision = 10
+            scale = 2
+
+            [comment]
+            length = 200
+            nullable = false
+
+            [created]
+            unit = "ms"
+            "#,
+        )
+        .unwrap();
+
+        let amount = overrides
+            .apply(
+                "amount",
+                DataType::Numeric {
+                    precision: 0,
+                    scale: 0,
+                },
+                Nullability::Nullable,
+            )
+            .unwrap();
+        let comment = overrides
+            .apply(
+                "comment",
+                DataType::Varchar { length: None },
+                Nullability::Unknown,
+            )
+            .unwrap();
+        let created = overrides
+            .apply(
+                "created",
+                DataType::Timestamp { precision: 7 },
+                Nullability::Nullable,
+            )
+            .unwrap();
+        let other = overrides
+            .apply("other", DataType::Integer, Nullability::Nullable)
+            .unwrap();
+
+        assert_eq!(
+            (
+                DataType::Numeric {
+                    precision: 10,
+                    scale: 2
+                },
+                Nullability::Nullable
+            ),
+            amount
+        );
+        assert_eq!(
+            (
+                DataType::Varchar {
+                    length: NonZeroUsize::new(200)
+                },
+                Nullability::NoNulls
+            ),
+            comment
+        );
+        assert_eq!(
+            (DataType::Timestamp { precision: 3 }, Nullability::Nullable),
+            created
+        );
+        assert_eq!((DataType::Integer, Nullability::Nullable), other);
+    }
+
+    #[test]
+    fn reject_invalid_overrides() {
+        // Unknown key
+        assert!(SchemaOverrides::from_toml("[a]\nlenght = 10").is_err());
+        // Unknown type
+        assert!(SchemaOverrides::from_toml("[a]\ntype = \"int128\"").is_err());
+        // Length for integer column
+        let overrides = SchemaOverrides::from_toml("[a]\nlength = 10").unwrap();
+        assert!(overrides
+            .apply("a", DataType::Integer, Nullability::Nullable)
+            .is_err());
+        // Decimal without precision
+        let overrides = SchemaOverrides::from_toml("[a]\ntype = \"decimal\"").unwrap();
+        assert!(overrides
+            .apply("a", DataType::Integer, Nullability::Nullable)
+            .is_err());
+    }
+}
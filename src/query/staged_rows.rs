@@ -0,0 +1,409 @@
+use std::{
+    mem::{replace, size_of},
+    ops::Range,
+};
+
+use odbc_api::{
+    buffers::{
+        AnyColumnBufferSlice, BinColumn, BoxColumnBuffer, BufferDesc, ColumnBuffer, ColumnarBuffer,
+        ColumnarDynBuffer, TextColumn,
+    },
+    handles::{CData, CDataMut},
+    sys::{Date, Numeric, Time, Timestamp, NULL_DATA},
+    Bit, CursorRow, Nullable, Pod, RowSetBuffer,
+};
+
+/// Message we emmit if we hit a code path we expected to be unreachable.
+const BUG: &str = "Invalid Column view type. This is not supposed to happen. Please open a Bug at \
+                  https://github.com/pacman82/odbc2parquet/issues.";
+
+/// Rows copied out of fetch buffers. Fetch buffers are overwritten by the next fetch, so the rows
+/// of several small result sets can only be combined into one batch by copying them.
+pub struct StagedRows {
+    descs: Vec<BufferDesc>,
+    columns: Vec<ColumnValues>,
+    max_rows: usize,
+    num_rows: usize,
+    /// Holds the rows of the most recently completed batch.
+    batch: ColumnarDynBuffer,
+}
+
+impl StagedRows {
+    /// * `descs`: Buffer description for each column of the fetch buffers rows are copied from.
+    /// * `max_rows`: Maximum number of rows in a batch.
+    pub fn new(descs: Vec<BufferDesc>, max_rows: usize) -> Self {
+        let columns = descs.iter().copied().map(ColumnValues::new).collect();
+        Self {
+            descs,
+            columns,
+            max_rows,
+            num_rows: 0,
+            batch: ColumnarBuffer::new(Vec::new()),
+        }
+    }
+
+    /// Copies the rows of `buffer`, starting with the row at `offset`, until the batch is full.
+    /// Returns the number of rows copied.
+    pub fn append(&mut self, buffer: &ColumnarDynBuffer, offset: usize) -> usize {
+        let num_copied = (buffer.num_rows() - offset).min(self.max_rows - self.num_rows);
+        let rows = offset..offset + num_copied;
+        for (index, column) in self.columns.iter_mut().enumerate() {
+            column.push_rows(buffer.column(index), rows.clone());
+        }
+        self.num_rows += num_copied;
+        num_copied
+    }
+
+    /// `true` if no further rows fit into the batch.
+    pub fn is_full(&self) -> bool {
+        self.num_rows == self.max_rows
+    }
+
+    /// Moves the staged rows into a batch, so they can be written. `None` if there are no staged
+    /// rows.
+    pub fn take_batch(&mut self) -> Option<&ColumnarDynBuffer> {
+        if self.num_rows == 0 {
+            return None;
+        }
+        let fresh = self.descs.iter().copied().map(ColumnValues::new).collect();
+        let columns = replace(&mut self.columns, fresh)
+            .into_iter()
+            .enumerate()
+            .map(|(index, column)| ((index + 1).try_into().unwrap(), column.into_buffer()))
+            .collect();
+        self.batch = ColumnarBuffer::new(columns);
+        *self.batch.mut_num_fetch_rows() = self.num_rows;
+        self.num_rows = 0;
+        Some(&self.batch)
+    }
+}
+
+/// Values of a column, copied out of a column buffer or retrieved row by row. Variadic values are kept at their actual
+/// length, so the buffer they are moved into is just large enough to hold the largest of them.
+pub enum ColumnValues {
+    Text(Vec<Option<Vec<u8>>>),
+    WText(Vec<Option<Vec<u16>>>),
+    Binary(Vec<Option<Vec<u8>>>),
+    Fixed(Box<dyn FixedValues>),
+}
+
+impl ColumnValues {
+    pub fn new(desc: BufferDesc) -> Self {
+        match desc {
+            BufferDesc::Text { .. } => ColumnValues::Text(Vec::new()),
+            BufferDesc::WText { .. } => ColumnValues::WText(Vec::new()),
+            BufferDesc::Binary { .. } => ColumnValues::Binary(Vec::new()),
+            BufferDesc::F64 { nullable } => Values::<f64>::boxed(desc, nullable),
+            BufferDesc::F32 { nullable } => Values::<f32>::boxed(desc, nullable),
+            BufferDesc::Date { nullable } => Values::<Date>::boxed(desc, nullable),
+            BufferDesc::Time { nullable } => Values::<Time>::boxed(desc, nullable),
+            BufferDesc::Timestamp { nullable } => Values::<Timestamp>::boxed(desc, nullable),
+            BufferDesc::I8 { nullable } => Values::<i8>::boxed(desc, nullable),
+            BufferDesc::I16 { nullable } => Values::<i16>::boxed(desc, nullable),
+            BufferDesc::I32 { nullable } => Values::<i32>::boxed(desc, nullable),
+            BufferDesc::I64 { nullable } => Values::<i64>::boxed(desc, nullable),
+            BufferDesc::U8 { nullable } => Values::<u8>::boxed(desc, nullable),
+            BufferDesc::Bit { nullable } => Values::<Bit>::boxed(desc, nullable),
+            BufferDesc::Numeric => Values::<Numeric>::boxed(desc, false),
+        }
+    }
+
+    /// Appends the values of `rows` in `column`, which must have been allocated from the same
+    /// buffer description.
+    pub fn push_rows(&mut self, column: AnyColumnBufferSlice, rows: Range<usize>) {
+        match self {
+            ColumnValues::Text(values) => {
+                let column = column.as_text().expect(BUG);
+                values.extend(rows.map(|row| column.get(row).map(<[u8]>::to_vec)));
+            }
+            ColumnValues::WText(values) => {
+                let column = column.as_wide_text().expect(BUG);
+                values.extend(rows.map(|row| column.get(row).map(<[u16]>::to_vec)));
+            }
+            ColumnValues::Binary(values) => {
+                let column = column.as_binary().expect(BUG);
+                values.extend(rows.map(|row| column.get(row).map(<[u8]>::to_vec)));
+            }
+            ColumnValues::Fixed(values) => values.push_rows(column, rows),
+        }
+    }
+
+    /// Appends the value of the column with index `col_index` in the current row of a cursor,
+    /// retrieved with `SQLGetData`. Variadic values are retrieved in chunks until they are
+    /// complete, so they are never truncated.
+    pub fn fetch_value(
+        &mut self,
+        row: &mut CursorRow,
+        col_index: u16,
+    ) -> Result<(), odbc_api::Error> {
+        match self {
+            ColumnValues::Text(values) => {
+                let mut buf = Vec::new();
+                values.push(row.get_text(col_index, &mut buf)?.then_some(buf));
+            }
+            ColumnValues::WText(values) => {
+                let mut buf = Vec::new();
+                values.push(row.get_wide_text(col_index, &mut buf)?.then_some(buf));
+            }
+            ColumnValues::Binary(values) => {
+                let mut buf = Vec::new();
+                values.push(row.get_binary(col_index, &mut buf)?.then_some(buf));
+            }
+            ColumnValues::Fixed(values) => values.fetch_value(row, col_index)?,
+        }
+        Ok(())
+    }
+
+    /// Moves the values from index `at` onwards into a new instance.
+    pub fn split_off(&mut self, at: usize) -> Self {
+        match self {
+            ColumnValues::Text(values) => ColumnValues::Text(values.split_off(at)),
+            ColumnValues::WText(values) => ColumnValues::WText(values.split_off(at)),
+            ColumnValues::Binary(values) => ColumnValues::Binary(values.split_off(at)),
+            ColumnValues::Fixed(values) => ColumnValues::Fixed(values.split_off(at)),
+        }
+    }
+
+    /// Memory in bytes the buffer created by [`Self::into_buffer`] would require.
+    pub fn buffer_size(&self) -> usize {
+        let indicator = size_of::<isize>();
+        match self {
+            // Text is stored with a terminating zero
+            ColumnValues::Text(values) => (max_len(values) + 1 + indicator) * values.len(),
+            ColumnValues::WText(values) => ((max_len(values) + 1) * 2 + indicator) * values.len(),
+            ColumnValues::Binary(values) => (max_len(values) + indicator) * values.len(),
+            ColumnValues::Fixed(values) => values.buffer_size(),
+        }
+    }
+
+    /// Column buffer holding all values, with a capacity of exactly the number of values.
+    pub fn into_buffer(self) -> BoxColumnBuffer {
+        match self {
+            ColumnValues::Text(values) => {
+                let mut column = TextColumn::<u8>::new(values.len(), max_len(&values));
+                for (index, value) in values.iter().enumerate() {
+                    column.set_value(index, value.as_deref());
+                }
+                Box::new(column)
+            }
+            ColumnValues::WText(values) => {
+                let mut column = TextColumn::<u16>::new(values.len(), max_len(&values));
+                for (index, value) in values.iter().enumerate() {
+                    column.set_value(index, value.as_deref());
+                }
+                Box::new(column)
+            }
+            ColumnValues::Binary(values) => {
+                let mut column = BinColumn::new(values.len(), max_len(&values));
+                for (index, value) in values.iter().enumerate() {
+                    column.set_value(index, value.as_deref());
+                }
+                Box::new(column)
+            }
+            ColumnValues::Fixed(values) => values.into_buffer(),
+        }
+    }
+}
+
+/// Length of the largest value, at least one, since buffers can not hold empty elements.
+fn max_len<T>(values: &[Option<Vec<T>>]) -> usize {
+    values
+        .iter()
+        .flatten()
+        .map(Vec::len)
+        .max()
+        .unwrap_or(0)
+        .max(1)
+}
+
+/// Values of a fixed sized column. Implemented generically for all value types, so
+/// [`ColumnValues`] does not need a variant for each of them.
+pub trait FixedValues {
+    /// See [`ColumnValues::push_rows`].
+    fn push_rows(&mut self, column: AnyColumnBufferSlice, rows: Range<usize>);
+
+    /// See [`ColumnValues::fetch_value`].
+    fn fetch_value(&mut self, row: &mut CursorRow, col_index: u16) -> Result<(), odbc_api::Error>;
+
+    /// See [`ColumnValues::split_off`].
+    fn split_off(&mut self, at: usize) -> Box<dyn FixedValues>;
+
+    /// See [`ColumnValues::buffer_size`].
+    fn buffer_size(&self) -> usize;
+
+    /// See [`ColumnValues::into_buffer`].
+    fn into_buffer(self: Box<Self>) -> BoxColumnBuffer;
+}
+
+struct Values<T> {
+    desc: BufferDesc,
+    nullable: bool,
+    values: Vec<Option<T>>,
+}
+
+impl<T> Values<T>
+where
+    T: Pod,
+{
+    fn boxed(desc: BufferDesc, nullable: bool) -> ColumnValues {
+        ColumnValues::Fixed(Box::new(Self {
+            desc,
+            nullable,
+            values: Vec::new(),
+        }))
+    }
+}
+
+impl<T> FixedValues for Values<T>
+where
+    T: Pod,
+{
+    fn push_rows(&mut self, column: AnyColumnBufferSlice, rows: Range<usize>) {
+        if self.nullable {
+            let column = column.as_nullable_slice::<T>().expect(BUG);
+            self.values.extend(rows.map(|row| column.get(row).copied()));
+        } else {
+            let column = column.as_slice::<T>().expect(BUG);
+            self.values.extend(column[rows].iter().copied().map(Some));
+        }
+    }
+
+    fn fetch_value(&mut self, row: &mut CursorRow, col_index: u16) -> Result<(), odbc_api::Error> {
+        let value = if self.nullable {
+            let mut value = Nullable::<T>::null();
+            row.get_data(col_index, &mut value)?;
+            value.into_opt()
+        } else {
+            let mut value = T::default();
+            row.get_data(col_index, &mut value)?;
+            Some(value)
+        };
+        self.values.push(value);
+        Ok(())
+    }
+
+    fn split_off(&mut self, at: usize) -> Box<dyn FixedValues> {
+        Box::new(Self {
+            desc: self.desc,
+            nullable: self.nullable,
+            values: self.values.split_off(at),
+        })
+    }
+
+    fn buffer_size(&self) -> usize {
+        self.desc.bytes_per_row() * self.values.len()
+    }
+
+    fn into_buffer(self: Box<Self>) -> BoxColumnBuffer {
+        if !self.nullable {
+            let values: Vec<T> = self
+                .values
+                .into_iter()
+                .map(Option::unwrap_or_default)
+                .collect();
+            return Box::new(values);
+        }
+        // `odbc-api` offers no safe way to write into a nullable column buffer outside of bulk
+        // inserts, so we write into it the same way the driver does while fetching.
+        let mut buffer = self.desc.column_buffer(self.values.len());
+        assert_eq!(T::C_DATA_TYPE, buffer.cdata_type());
+        assert!(buffer.capacity() >= self.values.len());
+        let indicators = buffer.mut_indicator_ptr();
+        assert!(!indicators.is_null());
+        let values = buffer.mut_value_ptr() as *mut T;
+        for (index, value) in self.values.into_iter().enumerate() {
+            // SAFETY: As asserted above, the buffer holds values of type `T` and has an indicator
+            // for each of them. Its capacity is large enough to hold all values.
+            unsafe {
+                match value {
+                    Some(value) => {
+                        values.add(index).write(value);
+                        indicators.add(index).write(size_of::<T>() as isize);
+                    }
+                    None => indicators.add(index).write(NULL_DATA),
+                }
+            }
+        }
+        buffer
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use odbc_api::{
+        buffers::{BoxColumnBuffer, BufferDesc, ColumnarBuffer, TextColumn},
+        RowSetBuffer,
+    };
+
+    use super::{ColumnValues, StagedRows, Values};
+
+    #[test]
+    fn combine_rows_of_several_buffers_into_batches() {
+        let buffer = |texts: &[&str], numbers: Vec<i64>| {
+            let mut text_column = TextColumn::<u8>::new(texts.len(), 10);
+            for (index, text) in texts.iter().enumerate() {
+                text_column.set_value(index, Some(text.as_bytes()));
+            }
+            let text_column: BoxColumnBuffer = Box::new(text_column);
+            let number_column: BoxColumnBuffer = Box::new(numbers);
+            let mut buffer = ColumnarBuffer::new(vec![(1, text_column), (2, number_column)]);
+            *buffer.mut_num_fetch_rows() = texts.len();
+            buffer
+        };
+        let descs = vec![
+            BufferDesc::Text { max_str_len: 10 },
+            BufferDesc::I64 { nullable: false },
+        ];
+        let mut staged = StagedRows::new(descs, 3);
+
+        assert_eq!(2, staged.append(&buffer(&["a", "b"], vec![1, 2]), 0));
+        assert!(!staged.is_full());
+        let second = buffer(&["ccc", "d"], vec![3, 4]);
+        assert_eq!(1, staged.append(&second, 0));
+        assert!(staged.is_full());
+
+        let batch = staged.take_batch().unwrap();
+        assert_eq!(3, batch.num_rows());
+        let texts: Vec<_> = batch.column(0).as_text().unwrap().iter().collect();
+        assert_eq!(vec![Some(&b"a"[..]), Some(b"b"), Some(b"ccc")], texts);
+        assert_eq!(&[1, 2, 3], batch.column(1).as_slice::<i64>().unwrap());
+
+        assert_eq!(1, staged.append(&second, 1));
+        let batch = staged.take_batch().unwrap();
+        assert_eq!(&[4], batch.column(1).as_slice::<i64>().unwrap());
+        assert!(staged.take_batch().is_none());
+    }
+
+    #[test]
+    fn nullable_values_into_buffer() {
+        let desc = BufferDesc::I32 { nullable: true };
+        let values = ColumnValues::Fixed(Box::new(Values {
+            desc,
+            nullable: true,
+            values: vec![Some(42), None, Some(-1)],
+        }));
+
+        let mut buffer = ColumnarBuffer::new(vec![(1, values.into_buffer())]);
+        *buffer.mut_num_fetch_rows() = 3;
+
+        let actual: Vec<_> = buffer
+            .column(0)
+            .as_nullable_slice::<i32>()
+            .unwrap()
+            .map(|value| value.copied())
+            .collect();
+        assert_eq!(vec![Some(42), None, Some(-1)], actual);
+    }
+
+    #[test]
+    fn split_off_last_row_of_text_values() {
+        let mut values = ColumnValues::Text(vec![Some(b"ab".to_vec()), Some(b"abcdef".to_vec())]);
+
+        let last = values.split_off(1);
+
+        // Values are stored with a terminating zero and an indicator
+        let indicator = std::mem::size_of::<isize>();
+        assert_eq!(2 + 1 + indicator, values.buffer_size());
+        assert_eq!(6 + 1 + indicator, last.buffer_size());
+    }
+}
@@ -1,4 +1,8 @@
+use std::borrow::Cow;
+
 use anyhow::Error;
+use arrow_array::ArrayRef;
+use arrow_schema::DataType as ArrowDataType;
 use encoding_rs::mem::convert_utf16_to_str;
 use log::warn;
 use odbc_api::buffers::{AnyColumnBufferSlice, BufferDesc};
@@ -11,7 +15,7 @@ use parquet::{
 
 use crate::parquet_buffer::ParquetBuffer;
 
-use super::column_strategy::ColumnStrategy;
+use super::{arrow_column::ArrowValue, column_strategy::ColumnStrategy};
 
 pub fn text_strategy(
     use_utf16: bool,
@@ -60,6 +64,20 @@ impl ColumnStrategy for Utf16ToUtf8 {
     ) -> Result<(), Error> {
         write_utf16_to_utf8(parquet_buffer, column_writer, column_view)
     }
+
+    fn copy_odbc_to_arrow(
+        &self,
+        data_type: &ArrowDataType,
+        column_view: AnyColumnBufferSlice,
+    ) -> Result<ArrayRef, Error> {
+        let view = column_view.as_wide_text().unwrap();
+        let mut buf_utf8 = String::new();
+        String::arrow_array(
+            view.iter()
+                .map(|item| item.map(|ustr| utf16_to_utf8(ustr.as_slice(), &mut buf_utf8))),
+            data_type,
+        )
+    }
 }
 
 fn write_utf16_to_utf8(
@@ -75,23 +93,28 @@ fn write_utf16_to_utf8(
         cw,
         view.iter().map(|item| {
             item.map(|ustr| {
-                let utf16 = ustr.as_slice();
-                let max_utf8_len = utf16.len() * 3;
-                // Clearing is necessary. Otherwise we may slice the stream in between two bytes of
-                // a multi-byte character which would violate the invariant of String and cause a
-                // panic.
-                buf_utf8.clear();
-                for _ in 0..max_utf8_len {
-                    buf_utf8.push('\0');
-                }
-                let written = convert_utf16_to_str(utf16, &mut buf_utf8[..max_utf8_len]);
-                buf_utf8[..written].to_owned().into_bytes().into()
+                utf16_to_utf8(ustr.as_slice(), &mut buf_utf8)
+                    .into_bytes()
+                    .into()
             })
         }),
     )?;
     Ok(())
 }
 
+/// Transcodes UTF-16 into UTF-8. `buf_utf8` is reused between calls to avoid allocations.
+fn utf16_to_utf8(utf16: &[u16], buf_utf8: &mut String) -> String {
+    let max_utf8_len = utf16.len() * 3;
+    // Clearing is necessary. Otherwise we may slice the stream in between two bytes of a multi-byte
+    // character which would violate the invariant of String and cause a panic.
+    buf_utf8.clear();
+    for _ in 0..max_utf8_len {
+        buf_utf8.push('\0');
+    }
+    let written = convert_utf16_to_str(utf16, &mut buf_utf8[..max_utf8_len]);
+    buf_utf8[..written].to_owned()
+}
+
 pub struct Utf8 {
     repetition: Repetition,
     // Maximum string length in bytes
@@ -127,6 +150,15 @@ impl ColumnStrategy for Utf8 {
     ) -> Result<(), Error> {
         write_to_utf8(parquet_buffer, column_writer, column_view)
     }
+
+    fn copy_odbc_to_arrow(
+        &self,
+        data_type: &ArrowDataType,
+        column_view: AnyColumnBufferSlice,
+    ) -> Result<ArrayRef, Error> {
+        let view = column_view.as_text().unwrap();
+        Cow::<str>::arrow_array(view.iter().map(|item| item.map(utf8_text)), data_type)
+    }
 }
 
 fn write_to_utf8(
@@ -147,9 +179,13 @@ fn write_to_utf8(
 
 fn utf8_bytes_to_byte_array(bytes: &[u8]) -> ByteArray {
     // Allocate string into a ByteArray and make sure it is all UTF-8 characters.
-    if simdutf8::basic::from_utf8(bytes).is_ok() {
-        // Fast path: valid UTF-8, just copy the bytes directly
-        Vec::from(bytes).into()
+    utf8_text(bytes).into_owned().into_bytes().into()
+}
+
+fn utf8_text(bytes: &[u8]) -> Cow<'_, str> {
+    if let Ok(text) = simdutf8::basic::from_utf8(bytes) {
+        // Fast path: valid UTF-8, no need to copy the bytes
+        Cow::Borrowed(text)
     } else {
         // Slow path: contains invalid UTF-8, do lossy replacement
         let utf8_str = String::from_utf8_lossy(bytes);
@@ -158,6 +194,6 @@ fn utf8_bytes_to_byte_array(bytes: &[u8]) -> ByteArray {
             UTF-8 locale or try specifying `--encoding Utf16` on the command line. Value: {}",
             utf8_str
         );
-        utf8_str.into_owned().into_bytes().into()
+        utf8_str
     }
 }
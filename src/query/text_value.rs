@@ -0,0 +1,291 @@
+use std::{borrow::Cow, fmt::Write};
+
+use anyhow::{bail, Error};
+use arrow_array::{
+    cast::AsArray,
+    types::{
+        ArrowTemporalType, Date32Type, Decimal128Type, Decimal256Type, DecimalType, Float32Type,
+        Float64Type, Int16Type, Int32Type, Int64Type, Int8Type, Time32MillisecondType,
+        Time32SecondType, Time64MicrosecondType, Time64NanosecondType, TimestampMicrosecondType,
+        TimestampMillisecondType, TimestampNanosecondType, TimestampSecondType, UInt16Type,
+        UInt32Type, UInt64Type, UInt8Type,
+    },
+    Array, ArrowPrimitiveType,
+};
+use arrow_schema::{
+    extension::{ExtensionType, Uuid},
+    DataType, Field, TimeUnit,
+};
+use chrono::NaiveDateTime;
+
+/// A value of a column rendered as text. Used to write text output formats. The variants allow
+/// typed formats like JSON to tell numbers, booleans and lists apart from strings.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TextValue {
+    /// Finite integer or floating point number.
+    Number(String),
+    Boolean(bool),
+    /// Everything else, including dates, times and decimals. Decimals are rendered as text, so
+    /// readers do not lose precision by parsing them into floating point numbers.
+    Text(String),
+    /// Elements of an array column. `None` elements are `NULL`.
+    List(Vec<Option<TextValue>>),
+}
+
+impl TextValue {
+    /// Text of the value. Lists are rendered as JSON arrays, e.g. `[1,null,3]`.
+    pub fn as_text(&self) -> Cow<'_, str> {
+        match self {
+            TextValue::Number(text) | TextValue::Text(text) => Cow::Borrowed(text),
+            TextValue::Boolean(true) => Cow::Borrowed("true"),
+            TextValue::Boolean(false) => Cow::Borrowed("false"),
+            TextValue::List(_) => {
+                let mut json = Vec::new();
+                self.write_json(&mut json);
+                Cow::Owned(String::from_utf8(json).unwrap())
+            }
+        }
+    }
+
+    /// Appends the value to `out` as a JSON literal. Numbers, booleans and lists are written as
+    /// their JSON equivalent, everything else as a string.
+    pub fn write_json(&self, out: &mut Vec<u8>) {
+        match self {
+            TextValue::Number(text) => out.extend_from_slice(text.as_bytes()),
+            TextValue::Boolean(true) => out.extend_from_slice(b"true"),
+            TextValue::Boolean(false) => out.extend_from_slice(b"false"),
+            TextValue::Text(text) => serde_json::to_writer(&mut *out, text).unwrap(),
+            TextValue::List(elements) => {
+                out.push(b'[');
+                for (index, element) in elements.iter().enumerate() {
+                    if index != 0 {
+                        out.push(b',');
+                    }
+                    match element {
+                        None => out.extend_from_slice(b"null"),
+                        Some(element) => element.write_json(out),
+                    }
+                }
+                out.push(b']');
+            }
+        }
+    }
+}
+
+/// Text representation of each value in `array`. `None` if the value is `NULL`. `field` describes
+/// the column the array belongs to. The array holds the values after the conversions of the column
+/// strategies, so the text output shows the same values as the parquet and Arrow output.
+pub fn text_values(array: &dyn Array, field: &Field) -> Result<Vec<Option<TextValue>>, Error> {
+    let is_uuid = field.extension_type_name() == Some(Uuid::NAME);
+    (0..array.len())
+        .map(|row| {
+            if array.is_null(row) {
+                Ok(None)
+            } else {
+                text_value(array, is_uuid, row).map(Some)
+            }
+        })
+        .collect()
+}
+
+fn text_value(array: &dyn Array, is_uuid: bool, row: usize) -> Result<TextValue, Error> {
+    let value = match array.data_type() {
+        DataType::Boolean => TextValue::Boolean(array.as_boolean().value(row)),
+        DataType::Int8 => integer::<Int8Type>(array, row),
+        DataType::Int16 => integer::<Int16Type>(array, row),
+        DataType::Int32 => integer::<Int32Type>(array, row),
+        DataType::Int64 => integer::<Int64Type>(array, row),
+        DataType::UInt8 => integer::<UInt8Type>(array, row),
+        DataType::UInt16 => integer::<UInt16Type>(array, row),
+        DataType::UInt32 => integer::<UInt32Type>(array, row),
+        DataType::UInt64 => integer::<UInt64Type>(array, row),
+        DataType::Float32 => {
+            let f = array.as_primitive::<Float32Type>().value(row);
+            float(f.is_finite(), f)
+        }
+        DataType::Float64 => {
+            let f = array.as_primitive::<Float64Type>().value(row);
+            float(f.is_finite(), f)
+        }
+        &DataType::Decimal128(precision, scale) => TextValue::Text(Decimal128Type::format_decimal(
+            array.as_primitive::<Decimal128Type>().value(row),
+            precision,
+            scale,
+        )),
+        &DataType::Decimal256(precision, scale) => TextValue::Text(Decimal256Type::format_decimal(
+            array.as_primitive::<Decimal256Type>().value(row),
+            precision,
+            scale,
+        )),
+        DataType::Utf8 => TextValue::Text(array.as_string::<i32>().value(row).to_owned()),
+        DataType::Binary => TextValue::Text(hex(array.as_binary::<i32>().value(row))),
+        DataType::FixedSizeBinary(_) => {
+            let bytes = array.as_fixed_size_binary().value(row);
+            if is_uuid {
+                TextValue::Text(format_uuid(bytes))
+            } else {
+                TextValue::Text(hex(bytes))
+            }
+        }
+        DataType::Date32 => {
+            let date = array.as_primitive::<Date32Type>().value_as_date(row);
+            let Some(date) = date else {
+                bail!("Date is out of range.")
+            };
+            TextValue::Text(date.format("%Y-%m-%d").to_string())
+        }
+        DataType::Time32(TimeUnit::Second) => time::<Time32SecondType>(array, row)?,
+        DataType::Time32(TimeUnit::Millisecond) => time::<Time32MillisecondType>(array, row)?,
+        DataType::Time64(TimeUnit::Microsecond) => time::<Time64MicrosecondType>(array, row)?,
+        DataType::Time64(TimeUnit::Nanosecond) => time::<Time64NanosecondType>(array, row)?,
+        DataType::Timestamp(unit, time_zone) => {
+            let timestamp = match unit {
+                TimeUnit::Second => timestamp::<TimestampSecondType>(array, row),
+                TimeUnit::Millisecond => timestamp::<TimestampMillisecondType>(array, row),
+                TimeUnit::Microsecond => timestamp::<TimestampMicrosecondType>(array, row),
+                TimeUnit::Nanosecond => timestamp::<TimestampNanosecondType>(array, row),
+            };
+            let Some(timestamp) = timestamp else {
+                bail!("Timestamp is out of range.")
+            };
+            let mut text = format_timestamp(timestamp);
+            // Instants are stored in UTC.
+            if time_zone.is_some() {
+                text.push_str("+00:00");
+            }
+            TextValue::Text(text)
+        }
+        DataType::List(element) => {
+            let elements = array.as_list::<i32>().value(row);
+            TextValue::List(text_values(&elements, element)?)
+        }
+        other => bail!("Rendering values of Arrow type {other} as text is not supported."),
+    };
+    Ok(value)
+}
+
+fn integer<T>(array: &dyn Array, row: usize) -> TextValue
+where
+    T: ArrowPrimitiveType,
+    T::Native: ToString,
+{
+    TextValue::Number(array.as_primitive::<T>().value(row).to_string())
+}
+
+/// `NaN` and infinity are not numbers in the sense of most text formats, so they are rendered as
+/// text.
+fn float(is_finite: bool, f: impl ToString) -> TextValue {
+    if is_finite {
+        TextValue::Number(f.to_string())
+    } else {
+        TextValue::Text(f.to_string())
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().fold(String::new(), |mut hex, byte| {
+        write!(hex, "{byte:02x}").unwrap();
+        hex
+    })
+}
+
+/// Canonical form of a UUID, e.g. `936da01f-9abd-4d9d-80c7-02af85c822a8`.
+fn format_uuid(bytes: &[u8]) -> String {
+    let hex = hex(bytes);
+    if hex.len() != 32 {
+        return hex;
+    }
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..]
+    )
+}
+
+fn time<T>(array: &dyn Array, row: usize) -> Result<TextValue, Error>
+where
+    T: ArrowTemporalType,
+    i64: From<T::Native>,
+{
+    let Some(time) = array.as_primitive::<T>().value_as_time(row) else {
+        bail!("Time is out of range.")
+    };
+    Ok(TextValue::Text(time.format("%H:%M:%S%.f").to_string()))
+}
+
+fn timestamp<T>(array: &dyn Array, row: usize) -> Option<NaiveDateTime>
+where
+    T: ArrowTemporalType,
+    i64: From<T::Native>,
+{
+    array.as_primitive::<T>().value_as_datetime(row)
+}
+
+/// E.g. `2024-01-02 03:04:05.678`. Fractional seconds are only rendered if they are not zero.
+fn format_timestamp(timestamp: NaiveDateTime) -> String {
+    timestamp.format("%Y-%m-%d %H:%M:%S%.f").to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow_array::{
+        types::Int32Type, Array, Decimal128Array, FixedSizeBinaryArray, ListArray,
+        TimestampMillisecondArray,
+    };
+    use arrow_schema::{extension::Uuid, DataType, Field};
+    use chrono::{NaiveDate, Timelike};
+
+    use super::{format_timestamp, text_values, TextValue};
+
+    #[test]
+    fn timestamps_with_and_without_fraction() {
+        let ts = NaiveDate::from_ymd_opt(2024, 1, 2)
+            .unwrap()
+            .and_hms_opt(3, 4, 5)
+            .unwrap();
+        assert_eq!("2024-01-02 03:04:05", format_timestamp(ts));
+        let ts = ts.with_nanosecond(678_000_000).unwrap();
+        assert_eq!("2024-01-02 03:04:05.678", format_timestamp(ts));
+        let ts = ts.with_nanosecond(1).unwrap();
+        assert_eq!("2024-01-02 03:04:05.000000001", format_timestamp(ts));
+    }
+
+    #[test]
+    fn render_converted_values() {
+        let decimals = Decimal128Array::from(vec![Some(-12345), None])
+            .with_precision_and_scale(10, 2)
+            .unwrap();
+        let field = Field::new("d", decimals.data_type().clone(), true);
+        assert_eq!(
+            vec![Some(TextValue::Text("-123.45".to_owned())), None],
+            text_values(&decimals, &field).unwrap()
+        );
+
+        let instants = TimestampMillisecondArray::from(vec![1_000]).with_timezone("UTC");
+        let field = Field::new("ts", instants.data_type().clone(), true);
+        assert_eq!(
+            vec![Some(TextValue::Text(
+                "1970-01-01 00:00:01+00:00".to_owned()
+            ))],
+            text_values(&instants, &field).unwrap()
+        );
+
+        let bytes: [u8; 16] = 0x936da01f_9abd_4d9d_80c7_02af85c822a8_u128.to_be_bytes();
+        let uuids = FixedSizeBinaryArray::try_from_iter([bytes].into_iter()).unwrap();
+        let field = Field::new("id", DataType::FixedSizeBinary(16), true).with_extension_type(Uuid);
+        assert_eq!(
+            vec![Some(TextValue::Text(
+                "936da01f-9abd-4d9d-80c7-02af85c822a8".to_owned()
+            ))],
+            text_values(&uuids, &field).unwrap()
+        );
+
+        let lists = ListArray::from_iter_primitive::<Int32Type, _, _>([Some(vec![Some(1), None])]);
+        let field = Field::new("a", lists.data_type().clone(), true);
+        let values = text_values(&lists, &field).unwrap();
+        assert_eq!("[1,null]", values[0].as_ref().unwrap().as_text());
+    }
+}
@@ -0,0 +1,416 @@
+use std::{
+    fs::File,
+    io::{stdout, BufWriter, Stdout, Write},
+    path::PathBuf,
+    sync::Arc,
+};
+
+use anyhow::{Context, Error};
+use arrow_schema::Fields;
+use bytesize::ByteSize;
+use io_arg::IoArg;
+use log::info;
+use parquet::schema::types::Type;
+use tempfile::TempPath;
+
+use super::{
+    arrow_schema::arrow_schema,
+    batch_size_limit::FileSizeLimit,
+    conversion_strategy::ColumnExporter,
+    manifest::Manifest,
+    output::{path_with_suffix, Output, OutputOptions},
+    text_value::TextValue,
+};
+
+/// Encodes rows into a line oriented text format.
+pub trait RowEncoder {
+    /// Written at the start of each file.
+    fn encode_header(&self, column_names: &[String], out: &mut Vec<u8>);
+
+    /// Appends one row to `out`. `values` holds one element per column, `None` for `NULL`.
+    fn encode_row(&self, column_names: &[String], values: &[Option<&TextValue>], out: &mut Vec<u8>);
+}
+
+/// Comma separated values with a header line holding the column names. Fields are quoted as
+/// described in RFC 4180. `NULL` is an empty field, while an empty string is an empty quoted field.
+pub struct Csv;
+
+impl RowEncoder for Csv {
+    fn encode_header(&self, column_names: &[String], out: &mut Vec<u8>) {
+        for (index, name) in column_names.iter().enumerate() {
+            if index != 0 {
+                out.push(b',');
+            }
+            csv_field(name, out);
+        }
+        out.push(b'\n');
+    }
+
+    fn encode_row(
+        &self,
+        _column_names: &[String],
+        values: &[Option<&TextValue>],
+        out: &mut Vec<u8>,
+    ) {
+        for (index, value) in values.iter().enumerate() {
+            if index != 0 {
+                out.push(b',');
+            }
+            if let Some(value) = value {
+                csv_field(&value.as_text(), out);
+            }
+        }
+        out.push(b'\n');
+    }
+}
+
+fn csv_field(text: &str, out: &mut Vec<u8>) {
+    if text.is_empty() || text.contains([',', '"', '\n', '\r']) {
+        out.push(b'"');
+        out.extend_from_slice(text.replace('"', "\"\"").as_bytes());
+        out.push(b'"');
+    } else {
+        out.extend_from_slice(text.as_bytes());
+    }
+}
+
+/// One JSON object per line, with one member per column. Integers and floating point numbers are
+/// written as JSON numbers, booleans as JSON booleans, array columns as JSON arrays and everything
+/// else as strings.
+pub struct JsonLines;
+
+impl RowEncoder for JsonLines {
+    fn encode_header(&self, _column_names: &[String], _out: &mut Vec<u8>) {}
+
+    fn encode_row(
+        &self,
+        column_names: &[String],
+        values: &[Option<&TextValue>],
+        out: &mut Vec<u8>,
+    ) {
+        out.push(b'{');
+        for (index, (name, value)) in column_names.iter().zip(values).enumerate() {
+            if index != 0 {
+                out.push(b',');
+            }
+            serde_json::to_writer(&mut *out, name).unwrap();
+            out.push(b':');
+            match value {
+                None => out.extend_from_slice(b"null"),
+                Some(value) => value.write_json(out),
+            }
+        }
+        out.extend_from_slice(b"}\n");
+    }
+}
+
+/// Writes the rows to standard out, or into one or more text files, depending on `output`.
+pub fn text_output(
+    output: IoArg,
+    encoder: Box<dyn RowEncoder>,
+    schema: Arc<Type>,
+    options: OutputOptions,
+) -> Result<Box<dyn Output>, Error> {
+    let columns = TextColumns::new(schema)?;
+    let writer: Box<dyn Output> = match output {
+        IoArg::StdStream => Box::new(TextStandardOut::new(encoder, columns)?),
+        IoArg::File(path) => Box::new(TextFileWriter::new(path, encoder, columns, options)?),
+    };
+    Ok(writer)
+}
+
+/// Names and Arrow representation of the columns written to the output. Values are converted into
+/// Arrow arrays first and then rendered as text, so they are identical to the values written into
+/// parquet files.
+struct TextColumns {
+    names: Vec<String>,
+    fields: Fields,
+}
+
+impl TextColumns {
+    fn new(parquet_schema: Arc<Type>) -> Result<Self, Error> {
+        let fields = arrow_schema(parquet_schema)?.fields;
+        let names = fields.iter().map(|field| field.name().to_owned()).collect();
+        Ok(Self { names, fields })
+    }
+
+    /// Encodes all rows of the batch. Returns the number of rows encoded.
+    fn encode_batch(
+        &self,
+        encoder: &dyn RowEncoder,
+        column_exporter: &ColumnExporter,
+        out: &mut Vec<u8>,
+    ) -> Result<u64, Error> {
+        let columns = self
+            .fields
+            .iter()
+            .enumerate()
+            .map(|(index, field)| column_exporter.text_column(index, field))
+            .collect::<Result<Vec<_>, _>>()?;
+        let num_rows = column_exporter.num_rows();
+        let mut values = Vec::with_capacity(columns.len());
+        for row in 0..num_rows {
+            values.clear();
+            values.extend(columns.iter().map(|column| column[row].as_ref()));
+            encoder.encode_row(&self.names, &values, out);
+        }
+        Ok(num_rows as u64)
+    }
+}
+
+/// Writes text files. Handles splitting into new files after the file size limit is reached.
+struct TextFileWriter {
+    base_path: PathBuf,
+    encoder: Box<dyn RowEncoder>,
+    columns: TextColumns,
+    file_size: FileSizeLimit,
+    num_file: u32,
+    /// Length of the suffix, appended to the end of a file in case they are numbered.
+    suffix_length: usize,
+    /// Current file open for writing. `None`, if we are in between files.
+    current_file: Option<CurrentTextFile>,
+    /// Reused between batches to avoid allocations.
+    encoded: Vec<u8>,
+    /// Records each completed file, if a manifest has been requested.
+    manifest: Option<Manifest>,
+}
+
+impl TextFileWriter {
+    fn new(
+        path: PathBuf,
+        encoder: Box<dyn RowEncoder>,
+        columns: TextColumns,
+        options: OutputOptions,
+    ) -> Result<Self, Error> {
+        let mut writer = Self {
+            base_path: path,
+            encoder,
+            columns,
+            file_size: options.file_size,
+            num_file: 0,
+            suffix_length: options.suffix_length,
+            current_file: None,
+            encoded: Vec::new(),
+            manifest: options.manifest,
+        };
+        if !options.no_empty_file {
+            writer.next_file()?;
+        }
+        Ok(writer)
+    }
+
+    fn next_file(&mut self) -> Result<(), Error> {
+        let path = if self.file_size.output_is_splitted() {
+            path_with_suffix(&self.base_path, self.num_file + 1, self.suffix_length)?
+        } else {
+            self.base_path.clone()
+        };
+        let mut header = Vec::new();
+        self.encoder.encode_header(&self.columns.names, &mut header);
+        self.current_file = Some(CurrentTextFile::new(path, &header)?);
+        self.num_file += 1;
+        Ok(())
+    }
+}
+
+impl Output for TextFileWriter {
+    fn write_batch(
+        &mut self,
+        num_batch: u32,
+        column_exporter: ColumnExporter,
+    ) -> Result<(), Error> {
+        if self.current_file.is_none() {
+            self.next_file()?
+        }
+
+        self.encoded.clear();
+        let num_rows = self.columns.encode_batch(
+            self.encoder.as_ref(),
+            &column_exporter,
+            &mut self.encoded,
+        )?;
+        let file_size = self
+            .current_file
+            .as_mut()
+            .unwrap()
+            .write(&self.encoded, num_rows)?;
+
+        if self.file_size.file_limit_reached(num_batch, file_size) {
+            self.current_file
+                .take()
+                .unwrap()
+                .finalize(self.manifest.as_ref())?;
+        }
+        Ok(())
+    }
+
+    fn close(self) -> Result<(), Error> {
+        if let Some(open_file) = self.current_file {
+            open_file.finalize(self.manifest.as_ref())?;
+        }
+        Ok(())
+    }
+
+    fn close_box(self: Box<Self>) -> Result<(), Error> {
+        self.close()
+    }
+}
+
+struct CurrentTextFile {
+    writer: BufWriter<File>,
+    /// Path to the file currently being written to. Removed again, should we fail before it is
+    /// finalized.
+    path: TempPath,
+    /// Number of bytes written so far.
+    file_size: ByteSize,
+    /// Number of rows written so far.
+    total_num_rows: u64,
+    /// Number of batches written so far.
+    num_batches: u32,
+}
+
+impl CurrentTextFile {
+    fn new(path: PathBuf, header: &[u8]) -> Result<Self, Error> {
+        let file = File::create(&path).with_context(|| {
+            format!("Could not create output file '{}'", path.to_string_lossy())
+        })?;
+        let path = TempPath::try_from_path(path).context("Unable to create temporary file")?;
+        let mut writer = BufWriter::new(file);
+        writer.write_all(header)?;
+        Ok(Self {
+            writer,
+            path,
+            file_size: ByteSize::b(header.len() as u64),
+            total_num_rows: 0,
+            num_batches: 0,
+        })
+    }
+
+    /// Returns the size of the file after the write.
+    fn write(&mut self, encoded: &[u8], num_rows: u64) -> Result<ByteSize, Error> {
+        self.writer.write_all(encoded)?;
+        self.file_size += ByteSize::b(encoded.len() as u64);
+        self.total_num_rows += num_rows;
+        self.num_batches += 1;
+        Ok(self.file_size)
+    }
+
+    fn finalize(mut self, manifest: Option<&Manifest>) -> Result<(), Error> {
+        self.writer.flush()?;
+        let path = self.path.keep()?;
+        info!(
+            rows = self.total_num_rows,
+            size:display = self.file_size,
+            path:display = path.to_string_lossy();
+            "File complete"
+        );
+        if let Some(manifest) = manifest {
+            manifest.record(&path, self.total_num_rows, self.num_batches)?;
+        }
+        Ok(())
+    }
+}
+
+/// Stream text directly to standard out.
+struct TextStandardOut {
+    writer: BufWriter<Stdout>,
+    encoder: Box<dyn RowEncoder>,
+    columns: TextColumns,
+    /// Reused between batches to avoid allocations.
+    encoded: Vec<u8>,
+}
+
+impl TextStandardOut {
+    fn new(encoder: Box<dyn RowEncoder>, columns: TextColumns) -> Result<Self, Error> {
+        let mut header = Vec::new();
+        encoder.encode_header(&columns.names, &mut header);
+        let mut writer = BufWriter::new(stdout());
+        writer.write_all(&header)?;
+        Ok(Self {
+            writer,
+            encoder,
+            columns,
+            encoded: Vec::new(),
+        })
+    }
+}
+
+impl Output for TextStandardOut {
+    fn write_batch(
+        &mut self,
+        _num_batch: u32,
+        column_exporter: ColumnExporter,
+    ) -> Result<(), Error> {
+        self.encoded.clear();
+        self.columns
+            .encode_batch(self.encoder.as_ref(), &column_exporter, &mut self.encoded)?;
+        self.writer.write_all(&self.encoded)?;
+        Ok(())
+    }
+
+    fn close(mut self) -> Result<(), Error> {
+        self.writer.flush()?;
+        Ok(())
+    }
+
+    fn close_box(self: Box<Self>) -> Result<(), Error> {
+        self.close()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Csv, JsonLines, RowEncoder};
+    use crate::query::text_value::TextValue;
+
+    #[test]
+    fn encode_csv() {
+        let names = ["id".to_owned(), "text".to_owned()];
+        let mut out = Vec::new();
+        Csv.encode_header(&names, &mut out);
+        for text in [Some("a,b"), Some("say \"hi\""), Some(""), None] {
+            let text = text.map(|text| TextValue::Text(text.to_owned()));
+            Csv.encode_row(
+                &names,
+                &[Some(&TextValue::Number("1".to_owned())), text.as_ref()],
+                &mut out,
+            );
+        }
+
+        assert_eq!(
+            "id,text\n1,\"a,b\"\n1,\"say \"\"hi\"\"\"\n1,\"\"\n1,\n",
+            String::from_utf8(out).unwrap()
+        );
+    }
+
+    #[test]
+    fn encode_json_lines() {
+        let names = [
+            "n".to_owned(),
+            "b".to_owned(),
+            "t".to_owned(),
+            "x".to_owned(),
+            "l".to_owned(),
+        ];
+        let mut out = Vec::new();
+        JsonLines.encode_row(
+            &names,
+            &[
+                Some(&TextValue::Number("1.5".to_owned())),
+                Some(&TextValue::Boolean(true)),
+                Some(&TextValue::Text("say \"hi\"".to_owned())),
+                None,
+                Some(&TextValue::List(vec![
+                    Some(TextValue::Number("1".to_owned())),
+                    None,
+                ])),
+            ],
+            &mut out,
+        );
+
+        assert_eq!(
+            "{\"n\":1.5,\"b\":true,\"t\":\"say \\\"hi\\\"\",\"x\":null,\"l\":[1,null]}\n",
+            String::from_utf8(out).unwrap()
+        );
+    }
+}
@@ -1,19 +1,41 @@
-use std::ops::{Add, Div, Mul};
+use std::ops::{Add, Div, Mul, Range};
 
-use anyhow::Error;
+use anyhow::{anyhow, Error};
+use arrow_array::ArrayRef;
+use arrow_schema::DataType as ArrowDataType;
 use atoi::FromRadix10;
 use chrono::{NaiveTime, Timelike};
-use odbc_api::buffers::{AnyColumnBufferSlice, BufferDesc};
+use odbc_api::{
+    buffers::{AnyColumnBufferSlice, BufferDesc},
+    sys::Time as OdbcTime,
+};
 use parquet::{
     basic::{LogicalType, Repetition, TimeType, TimeUnit, Type as PhysicalType},
-    column::writer::ColumnWriter,
+    column::writer::{get_typed_column_writer_mut, ColumnWriter},
     data_type::{DataType, Int32Type, Int64Type},
     schema::types::Type,
 };
 
 use crate::parquet_buffer::{BufferedDataType, ParquetBuffer};
 
-use super::column_strategy::ColumnStrategy;
+use super::{arrow_column::ArrowValue, column_strategy::ColumnStrategy};
+
+/// MySQL and MariaDB use `TIME` for durations like `-838:59:59`, rather than for the time of the
+/// day. We can not store these as parquet `TIME` and fetch them as text instead.
+pub fn is_time_of_day(db_name: &str) -> bool {
+    !matches!(db_name, "MySQL" | "MariaDB")
+}
+
+/// Choose how to fetch a relational `TIME` column. ODBC `SQL_TIME_STRUCT` has no field for
+/// fractional seconds, so we can only bind it directly if the column has a precision of zero. For
+/// any other precision we fetch the time as text and parse it ourselves.
+pub fn time_strategy(repetition: Repetition, precision: u8) -> Box<dyn ColumnStrategy> {
+    if precision == 0 {
+        Box::new(TimeFromStruct::new(repetition))
+    } else {
+        time_from_text(repetition, precision)
+    }
+}
 
 /// Parse wallclock time with fractional seconds from text into time. E.g. 16:04:12.0000000
 pub fn time_from_text(repetition: Repetition, precision: u8) -> Box<dyn ColumnStrategy> {
@@ -75,6 +97,77 @@ impl ColumnStrategy for TimeFromText {
             _ => write_time_ns(parquet_buffer, column_writer, column_view),
         }
     }
+
+    fn copy_odbc_to_arrow(
+        &self,
+        data_type: &ArrowDataType,
+        column_view: AnyColumnBufferSlice,
+    ) -> Result<ArrayRef, Error> {
+        match self.precision {
+            0..=3 => i32::try_arrow_array(time_values(column_view, 1_000, 1_000_000), data_type),
+            4..=6 => i64::try_arrow_array(time_values(column_view, 1_000_000, 1_000), data_type),
+            _ => i64::try_arrow_array(time_values(column_view, 1_000_000_000, 1), data_type),
+        }
+    }
+}
+
+/// Bind `SQL_TIME_STRUCT` and convert it into milliseconds since midnight.
+struct TimeFromStruct {
+    repetition: Repetition,
+}
+
+impl TimeFromStruct {
+    pub fn new(repetition: Repetition) -> Self {
+        Self { repetition }
+    }
+}
+
+impl ColumnStrategy for TimeFromStruct {
+    fn parquet_type(&self, name: &str) -> Type {
+        Type::primitive_type_builder(name, PhysicalType::INT32)
+            .with_logical_type(Some(LogicalType::Time(TimeType {
+                is_adjusted_to_u_t_c: false,
+                unit: TimeUnit::MILLIS,
+            })))
+            .with_repetition(self.repetition)
+            .build()
+            .unwrap()
+    }
+
+    fn buffer_desc(&self) -> BufferDesc {
+        BufferDesc::Time { nullable: true }
+    }
+
+    fn copy_odbc_to_parquet(
+        &self,
+        parquet_buffer: &mut ParquetBuffer,
+        column_writer: &mut ColumnWriter,
+        column_view: AnyColumnBufferSlice,
+    ) -> Result<(), Error> {
+        let it = column_view.as_nullable_slice::<OdbcTime>().unwrap();
+        let column_writer = get_typed_column_writer_mut::<Int32Type>(column_writer);
+        parquet_buffer.write_optional(
+            column_writer,
+            it.map(|time| time.map(milliseconds_since_midnight)),
+        )?;
+        Ok(())
+    }
+
+    fn copy_odbc_to_arrow(
+        &self,
+        data_type: &ArrowDataType,
+        column_view: AnyColumnBufferSlice,
+    ) -> Result<ArrayRef, Error> {
+        let it = column_view.as_nullable_slice::<OdbcTime>().unwrap();
+        i32::arrow_array(
+            it.map(|time| time.map(milliseconds_since_midnight)),
+            data_type,
+        )
+    }
+}
+
+fn milliseconds_since_midnight(time: &OdbcTime) -> i32 {
+    (time.hour as i32 * 3_600 + time.minute as i32 * 60 + time.second as i32) * 1_000
 }
 
 fn write_time_ns(
@@ -118,34 +211,57 @@ where
         + Copy,
     <Pdt::T as TryFrom<u32>>::Error: std::fmt::Debug,
 {
-    let from = column_reader.as_text().unwrap();
     let into = Pdt::get_column_writer_mut(column_writer).unwrap();
-    pb.write_optional(
-        into,
-        from.iter().map(|field| {
-            field.map(|text| {
-                let time = parse_time(text);
-                let seconds = time.num_seconds_from_midnight();
-                let nanoseconds = time.nanosecond();
-                let seconds: Pdt::T = seconds.try_into().unwrap();
-                let nanoseconds: Pdt::T = nanoseconds.try_into().unwrap();
-                seconds * s_factor + nanoseconds as Pdt::T / ns_divisor
-            })
-        }),
-    )?;
+    pb.write_optional_fallible(into, time_values(column_reader, s_factor, ns_divisor))?;
     Ok(())
 }
 
-/// Parse timestamp from representation HH:MM:SS[.FFF]
-fn parse_time(bytes: &[u8]) -> NaiveTime {
-    // From radix ten also returns the number of bytes extracted. We don't care. Should always
-    // be two, for hour, min and sec.
-    let (hour, _) = u32::from_radix_10(&bytes[0..2]);
-    let (min, _) = u32::from_radix_10(&bytes[3..5]);
-    let (sec, _) = u32::from_radix_10(&bytes[6..8]);
+/// Parses the text of each field into the time since midnight, in the unit implied by `s_factor`
+/// and `ns_divisor`.
+fn time_values<'a, T>(
+    column_reader: AnyColumnBufferSlice<'a>,
+    s_factor: T,
+    ns_divisor: T,
+) -> impl Iterator<Item = Result<Option<T>, Error>> + 'a
+where
+    T: 'a + TryFrom<u32> + Mul<Output = T> + Div<Output = T> + Add<Output = T> + Copy,
+    <T as TryFrom<u32>>::Error: std::fmt::Debug,
+{
+    let from = column_reader.as_text().unwrap();
+    from.iter().map(move |field| {
+        field
+            .map(|text| {
+                let (seconds, nanoseconds) = parse_time(text)?;
+                let seconds: T = seconds.try_into().unwrap();
+                let nanoseconds: T = nanoseconds.try_into().unwrap();
+                Ok(seconds * s_factor + nanoseconds / ns_divisor)
+            })
+            .transpose()
+    })
+}
+
+/// Parse time from representation HH:MM:SS[.FFF] into seconds since midnight and nanoseconds.
+/// `24:00:00` (PostgreSQL) is accepted as the end of the day.
+fn parse_time(bytes: &[u8]) -> Result<(u32, u32), Error> {
+    let invalid = || anyhow!("Invalid time of day: '{}'", String::from_utf8_lossy(bytes));
+    let two_digits = |range: Range<usize>| match bytes.get(range) {
+        Some(&[high, low]) if high.is_ascii_digit() && low.is_ascii_digit() => {
+            Ok(u32::from(high - b'0') * 10 + u32::from(low - b'0'))
+        }
+        _ => Err(invalid()),
+    };
+    let hour = two_digits(0..2)?;
+    let min = two_digits(3..5)?;
+    let sec = two_digits(6..8)?;
+    if bytes[2] != b':' || bytes[5] != b':' {
+        return Err(invalid());
+    }
     // If a fractional part is present, we parse it.
     let nano = if bytes.len() > 9 {
         let (fraction, precision) = u32::from_radix_10(&bytes[9..]);
+        if precision != bytes.len() - 9 {
+            return Err(invalid());
+        }
         match precision {
             0..=8 => {
                 // Pad value with `0` to represent nanoseconds
@@ -161,28 +277,46 @@ fn parse_time(bytes: &[u8]) -> NaiveTime {
     } else {
         0
     };
-    NaiveTime::from_hms_nano_opt(hour, min, sec, nano).unwrap()
+    if (hour, min, sec, nano) == (24, 0, 0, 0) {
+        return Ok((24 * 3_600, 0));
+    }
+    let time = NaiveTime::from_hms_nano_opt(hour, min, sec, nano).ok_or_else(invalid)?;
+    Ok((time.num_seconds_from_midnight(), time.nanosecond()))
 }
 
 #[cfg(test)]
 mod tests {
-    use chrono::NaiveTime;
+    use odbc_api::sys::Time;
 
-    use crate::query::time::parse_time;
+    use crate::query::time::{milliseconds_since_midnight, parse_time};
 
     #[test]
     fn parse_timestamps() {
+        assert_eq!(parse_time(b"16:04:12").unwrap(), (57_852, 0));
+        assert_eq!(parse_time(b"16:04:12.0000000").unwrap(), (57_852, 0));
         assert_eq!(
-            parse_time(b"16:04:12"),
-            NaiveTime::from_hms_opt(16, 4, 12).unwrap()
-        );
-        assert_eq!(
-            parse_time(b"16:04:12.0000000"),
-            NaiveTime::from_hms_opt(16, 4, 12).unwrap()
-        );
-        assert_eq!(
-            parse_time(b"16:04:12.123456"),
-            NaiveTime::from_hms_micro_opt(16, 4, 12, 123456).unwrap()
+            parse_time(b"16:04:12.123456").unwrap(),
+            (57_852, 123_456_000)
         );
+        assert_eq!(parse_time(b"24:00:00").unwrap(), (86_400, 0));
+    }
+
+    #[test]
+    fn reject_times_which_are_not_a_time_of_day() {
+        assert!(parse_time(b"100:00:00").is_err());
+        assert!(parse_time(b"-838:59:59").is_err());
+        assert!(parse_time(b"24:00:01").is_err());
+        assert!(parse_time(b"16:04").is_err());
+        assert!(parse_time(b"").is_err());
+    }
+
+    #[test]
+    fn time_struct_to_milliseconds() {
+        let time = Time {
+            hour: 16,
+            minute: 4,
+            second: 12,
+        };
+        assert_eq!(57_852_000, milliseconds_since_midnight(&time));
     }
 }
@@ -1,41 +1,45 @@
 use anyhow::Error;
+use arrow_array::ArrayRef;
+use arrow_schema::DataType as ArrowDataType;
 use odbc_api::{
     buffers::{AnyColumnBufferSlice, BufferDesc},
     sys::Timestamp,
 };
 use parquet::{
-    basic::{LogicalType, Repetition, TimestampType},
+    basic::Repetition,
     column::writer::ColumnWriter,
-    data_type::{DataType, Int64Type},
+    data_type::{DataType, Int64Type, Int96Type},
     schema::types::Type,
 };
 
 use crate::parquet_buffer::ParquetBuffer;
 
-use super::{column_strategy::ColumnStrategy, timestamp_precision::TimestampPrecision};
+use super::{
+    arrow_column::ArrowValue,
+    column_strategy::ColumnStrategy,
+    timestamp_precision::{
+        datetime_to_int96, naive_datetime, TimestampEncoding, TimestampPrecision,
+    },
+};
 
-pub fn timestamp_without_tz(repetition: Repetition, precision: u8) -> Box<dyn ColumnStrategy> {
-    Box::new(TimestampToI64 {
+pub fn timestamp_without_tz(
+    repetition: Repetition,
+    encoding: TimestampEncoding,
+) -> Box<dyn ColumnStrategy> {
+    Box::new(NaiveTimestamp {
         repetition,
-        precision: TimestampPrecision::new(precision),
+        encoding,
     })
 }
 
-struct TimestampToI64 {
+struct NaiveTimestamp {
     repetition: Repetition,
-    precision: TimestampPrecision,
+    encoding: TimestampEncoding,
 }
 
-impl ColumnStrategy for TimestampToI64 {
+impl ColumnStrategy for NaiveTimestamp {
     fn parquet_type(&self, name: &str) -> Type {
-        Type::primitive_type_builder(name, Int64Type::get_physical_type())
-            .with_logical_type(Some(LogicalType::Timestamp(TimestampType {
-                is_adjusted_to_u_t_c: false,
-                unit: self.precision.as_time_unit(),
-            })))
-            .with_repetition(self.repetition)
-            .build()
-            .unwrap()
+        self.encoding.parquet_type(name, self.repetition, false)
     }
 
     fn buffer_desc(&self) -> BufferDesc {
@@ -48,7 +52,27 @@ impl ColumnStrategy for TimestampToI64 {
         column_writer: &mut ColumnWriter,
         column_view: AnyColumnBufferSlice,
     ) -> Result<(), Error> {
-        write_timestamp_col(parquet_buffer, column_writer, column_view, self.precision)
+        match self.encoding {
+            TimestampEncoding::Int64(precision) => {
+                write_timestamp_col(parquet_buffer, column_writer, column_view, precision)
+            }
+            TimestampEncoding::Int96 => {
+                write_int96_timestamp_col(parquet_buffer, column_writer, column_view)
+            }
+        }
+    }
+
+    fn copy_odbc_to_arrow(
+        &self,
+        data_type: &ArrowDataType,
+        column_view: AnyColumnBufferSlice,
+    ) -> Result<ArrayRef, Error> {
+        let precision = self.encoding.arrow_precision();
+        let from = column_view.as_nullable_slice::<Timestamp>().unwrap();
+        i64::try_arrow_array(
+            from.map(|option| option.map(|ts| precision.timestamp_to_i64(ts)).transpose()),
+            data_type,
+        )
     }
 }
 
@@ -64,3 +88,19 @@ fn write_timestamp_col(
     pb.write_optional_fallible(into, from)?;
     Ok(())
 }
+
+fn write_int96_timestamp_col(
+    pb: &mut ParquetBuffer,
+    column_writer: &mut ColumnWriter,
+    column_reader: AnyColumnBufferSlice,
+) -> Result<(), Error> {
+    let from = column_reader.as_nullable_slice::<Timestamp>().unwrap();
+    let into = Int96Type::get_column_writer_mut(column_writer).unwrap();
+    let from = from.map(|option| {
+        option
+            .map(|ts| datetime_to_int96(&naive_datetime(ts)))
+            .transpose()
+    });
+    pb.write_optional_fallible(into, from)?;
+    Ok(())
+}
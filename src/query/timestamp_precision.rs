@@ -1,7 +1,13 @@
 use anyhow::{anyhow, Error};
-use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc};
 use odbc_api::sys::Timestamp;
-use parquet::basic::TimeUnit;
+use parquet::{
+    basic::{LogicalType, Repetition, TimeUnit, TimestampType, Type as PhysicalType},
+    data_type::Int96,
+    schema::types::Type,
+};
+
+use crate::enum_args::TimestampUnitArgument;
 
 /// Relational types communicate the precision of timestamps in number of fraction digits, while
 /// parquet uses time units (milli, micro, nano). This enumeration stores the decision which time
@@ -32,15 +38,7 @@ impl TimestampPrecision {
 
     /// Convert an ODBC timestamp struct into nano, milli or microseconds based on precision.
     pub fn timestamp_to_i64(self, ts: &Timestamp) -> Result<i64, Error> {
-        let datetime = NaiveDate::from_ymd_opt(ts.year as i32, ts.month as u32, ts.day as u32)
-            .unwrap()
-            .and_hms_nano_opt(
-                ts.hour as u32,
-                ts.minute as u32,
-                ts.second as u32,
-                ts.fraction,
-            )
-            .unwrap();
+        let datetime = naive_datetime(ts);
 
         let ret = match self {
             TimestampPrecision::Milliseconds => datetime.and_utc().timestamp_millis(),
@@ -66,6 +64,111 @@ impl TimestampPrecision {
     }
 }
 
+impl From<TimestampUnitArgument> for TimestampPrecision {
+    fn from(unit: TimestampUnitArgument) -> Self {
+        match unit {
+            TimestampUnitArgument::Millis => TimestampPrecision::Milliseconds,
+            TimestampUnitArgument::Micros => TimestampPrecision::Microseconds,
+            TimestampUnitArgument::Nanos => TimestampPrecision::Nanoseconds,
+        }
+    }
+}
+
+/// Physical representation of timestamp columns in the output.
+#[derive(Clone, Copy)]
+pub enum TimestampEncoding {
+    /// `INT64` annotated with a logical timestamp type of the given unit.
+    Int64(TimestampPrecision),
+    /// Deprecated `INT96` representation, consisting of the nanoseconds within the day and the
+    /// julian day. Still expected by older versions of Spark, Hive and Impala.
+    Int96,
+}
+
+impl TimestampEncoding {
+    /// Encoding of all timestamp columns chosen on the command line. `None` if the unit should be
+    /// chosen based on the precision of each column.
+    pub fn from_arguments(unit: Option<TimestampUnitArgument>, int96: bool) -> Option<Self> {
+        if int96 {
+            Some(TimestampEncoding::Int96)
+        } else {
+            unit.map(|unit| TimestampEncoding::Int64(unit.into()))
+        }
+    }
+
+    /// Encoding for a timestamp with `precision` fractional digits. `forced` is the encoding chosen
+    /// by the user for all timestamp columns, if any.
+    pub fn new(precision: u8, forced: Option<TimestampEncoding>) -> Self {
+        forced.unwrap_or_else(|| TimestampEncoding::Int64(TimestampPrecision::new(precision)))
+    }
+
+    /// Unit of the equivalent Arrow timestamps. Arrow readers represent `INT96` timestamps with
+    /// nanoseconds.
+    pub fn arrow_precision(self) -> TimestampPrecision {
+        match self {
+            TimestampEncoding::Int64(precision) => precision,
+            TimestampEncoding::Int96 => TimestampPrecision::Nanoseconds,
+        }
+    }
+
+    pub fn parquet_type(
+        self,
+        name: &str,
+        repetition: Repetition,
+        is_adjusted_to_utc: bool,
+    ) -> Type {
+        let builder = match self {
+            TimestampEncoding::Int64(precision) => {
+                Type::primitive_type_builder(name, PhysicalType::INT64).with_logical_type(Some(
+                    LogicalType::Timestamp(TimestampType {
+                        is_adjusted_to_u_t_c: is_adjusted_to_utc,
+                        unit: precision.as_time_unit(),
+                    }),
+                ))
+            }
+            // INT96 is not annotated with a logical type
+            TimestampEncoding::Int96 => Type::primitive_type_builder(name, PhysicalType::INT96),
+        };
+        builder.with_repetition(repetition).build().unwrap()
+    }
+}
+
+/// Date and time of an ODBC timestamp struct.
+pub fn naive_datetime(ts: &Timestamp) -> NaiveDateTime {
+    NaiveDate::from_ymd_opt(ts.year as i32, ts.month as u32, ts.day as u32)
+        .unwrap()
+        .and_hms_nano_opt(
+            ts.hour as u32,
+            ts.minute as u32,
+            ts.second as u32,
+            ts.fraction,
+        )
+        .unwrap()
+}
+
+/// Legacy `INT96` representation of a timestamp: Nanoseconds within the day in the first eight
+/// bytes, followed by the julian day in the last four bytes.
+pub fn datetime_to_int96(datetime: &NaiveDateTime) -> Result<Int96, Error> {
+    // Julian day of 1970-01-01
+    const JULIAN_DAY_OF_EPOCH: i64 = 2_440_588;
+    let days_since_epoch = datetime
+        .date()
+        .signed_duration_since(DateTime::UNIX_EPOCH.date_naive())
+        .num_days();
+    let julian_day: u32 = (days_since_epoch + JULIAN_DAY_OF_EPOCH)
+        .try_into()
+        .map_err(|_| {
+            anyhow!("Invalid timestamp: {datetime}. It can not be represented as INT96.")
+        })?;
+    let nanos_of_day = datetime
+        .time()
+        .signed_duration_since(NaiveTime::MIN)
+        .num_nanoseconds()
+        .unwrap() as u64;
+    let mut int96 = Int96::new();
+    int96.set_data(nanos_of_day as u32, (nanos_of_day >> 32) as u32, julian_day);
+    Ok(int96)
+}
+
 fn nanoseconds_precision_error(value: &NaiveDateTime) -> Error {
     // The valid time ranges for parquet and datetime align. Normally this could be considered
     // incidental and should not be relied upon. However, both interfaces are shaped by what is
@@ -79,3 +182,36 @@ fn nanoseconds_precision_error(value: &NaiveDateTime) -> Error {
         value
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::datetime_to_int96;
+
+    #[test]
+    fn timestamp_to_int96() {
+        let datetime = NaiveDate::from_ymd_opt(2024, 1, 2)
+            .unwrap()
+            .and_hms_nano_opt(3, 4, 5, 123_456_789)
+            .unwrap();
+
+        let int96 = datetime_to_int96(&datetime).unwrap();
+
+        assert_eq!(
+            datetime.and_utc().timestamp_nanos_opt().unwrap(),
+            int96.to_nanos()
+        );
+        // Julian day of 2024-01-02
+        assert_eq!(2_460_312, int96.data()[2]);
+
+        let before_epoch = NaiveDate::from_ymd_opt(1900, 1, 1)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap();
+        assert_eq!(
+            before_epoch.and_utc().timestamp_nanos_opt().unwrap(),
+            datetime_to_int96(&before_epoch).unwrap().to_nanos()
+        );
+    }
+}
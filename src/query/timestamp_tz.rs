@@ -1,21 +1,34 @@
-use anyhow::{Context, Error};
-use chrono::{DateTime, Utc};
+use anyhow::{anyhow, bail, Context, Error};
+use arrow_array::ArrayRef;
+use arrow_schema::DataType as ArrowDataType;
+use chrono::{DateTime, FixedOffset, NaiveDateTime, Offset, TimeZone, Utc};
+use chrono_tz::{Tz, TZ_VARIANTS};
 use odbc_api::buffers::{AnyColumnBufferSlice, BufferDesc};
 use parquet::{
-    basic::{LogicalType, Repetition, TimestampType, Type as PhysicalType},
+    basic::Repetition,
     column::writer::{get_typed_column_writer_mut, ColumnWriter},
-    data_type::Int64Type,
+    data_type::{Int64Type, Int96Type},
     schema::types::Type,
 };
 
 use crate::parquet_buffer::ParquetBuffer;
 
-use super::{column_strategy::ColumnStrategy, timestamp_precision::TimestampPrecision};
+use super::{
+    arrow_column::ArrowValue,
+    column_strategy::ColumnStrategy,
+    timestamp_precision::{datetime_to_int96, TimestampEncoding},
+};
+
+/// Length of the longest time zone we expect in the text representation, including the separating
+/// space. Long enough for IANA names like ` America/Argentina/ComodRivadavia`.
+const MAX_TIME_ZONE_LEN: usize = 40;
 
-pub fn timestamp_tz(precision: u8, repetition: Repetition) -> Result<Box<TimestampTz>, Error> {
-    Ok(Box::new(TimestampTz::with_bytes_length(
-        repetition, precision,
-    )))
+pub fn timestamp_tz(
+    precision: u8,
+    encoding: TimestampEncoding,
+    repetition: Repetition,
+) -> Box<TimestampTz> {
+    Box::new(TimestampTz::new(repetition, precision, encoding))
 }
 
 pub struct TimestampTz {
@@ -23,40 +36,36 @@ pub struct TimestampTz {
     // We store digit precision, rather than TimestampPrecision, in order to be able to adequately
     // calculate ODBC text buffer length.
     precision: u8,
+    encoding: TimestampEncoding,
 }
 
 impl TimestampTz {
-    pub fn with_bytes_length(repetition: Repetition, precision: u8) -> Self {
+    pub fn new(repetition: Repetition, precision: u8, encoding: TimestampEncoding) -> Self {
         Self {
             repetition,
             precision,
+            encoding,
         }
     }
 }
 
 impl ColumnStrategy for TimestampTz {
     fn parquet_type(&self, name: &str) -> Type {
-        Type::primitive_type_builder(name, PhysicalType::INT64)
-            .with_logical_type(Some(LogicalType::Timestamp(TimestampType {
-                is_adjusted_to_u_t_c: true,
-                unit: TimestampPrecision::new(self.precision).as_time_unit(),
-            })))
-            .with_repetition(self.repetition)
-            .build()
-            .unwrap()
+        self.encoding.parquet_type(name, self.repetition, true)
     }
 
     fn buffer_desc(&self) -> BufferDesc {
-        // Text representation looks like e.g. 2022-09-07 16:04:12 +02:00
-        // Text representation looks like e.g. 2022-09-07 16:04:12.123 +02:00
+        // Text representation looks like e.g. 2022-09-07 16:04:12 +02:00 (Microsoft SQL Server),
+        // 2022-09-07 16:04:12.123+02 (PostgreSQL) or 2022-09-07 16:04:12.123 Europe/Berlin
+        // (Oracle, with the `NLS_TIMESTAMP_TZ_FORMAT` set after connecting).
 
-        let max_str_len = 26
-            + if self.precision == 0 {
+        let max_str_len =
+            19 + if self.precision == 0 {
                 0
             } else {
                 // Radix character `.` and precision.
                 1 + self.precision as usize
-            };
+            } + MAX_TIME_ZONE_LEN;
         BufferDesc::Text { max_str_len }
     }
 
@@ -66,7 +75,26 @@ impl ColumnStrategy for TimestampTz {
         column_writer: &mut ColumnWriter,
         column_view: AnyColumnBufferSlice,
     ) -> Result<(), Error> {
-        write_timestamp_tz(parquet_buffer, column_writer, column_view, self.precision)
+        write_timestamp_tz(parquet_buffer, column_writer, column_view, self.encoding)
+    }
+
+    fn copy_odbc_to_arrow(
+        &self,
+        data_type: &ArrowDataType,
+        column_view: AnyColumnBufferSlice,
+    ) -> Result<ArrayRef, Error> {
+        let precision = self.encoding.arrow_precision();
+        let view = column_view.as_text().expect(
+            "Invalid Column view type. This is not supposed to happen. Please open a Bug at \
+            https://github.com/pacman82/odbc2parquet/issues.",
+        );
+        i64::try_arrow_array(
+            view.iter().map(|item| {
+                item.map(|text| precision.datetime_to_i64(&to_utc(text)?))
+                    .transpose()
+            }),
+            data_type,
+        )
     }
 }
 
@@ -74,35 +102,153 @@ fn write_timestamp_tz(
     pb: &mut ParquetBuffer,
     column_writer: &mut ColumnWriter,
     column_reader: AnyColumnBufferSlice,
-    precision: u8,
+    encoding: TimestampEncoding,
 ) -> Result<(), Error> {
     let view = column_reader.as_text().expect(
         "Invalid Column view type. This is not supposed to happen. Please open a Bug at \
         https://github.com/pacman82/odbc2parquet/issues.",
     );
-    let cw = get_typed_column_writer_mut::<Int64Type>(column_writer);
-    pb.write_optional_fallible(
-        cw,
-        view.iter()
-            .map(|item| item.map(|text| to_utc_epoch(text, precision)).transpose()),
-    )?;
+    let utc = view.iter().map(|item| item.map(to_utc).transpose());
+    match encoding {
+        TimestampEncoding::Int64(precision) => {
+            let cw = get_typed_column_writer_mut::<Int64Type>(column_writer);
+            pb.write_optional_fallible(
+                cw,
+                utc.map(|utc| utc?.map(|utc| precision.datetime_to_i64(&utc)).transpose()),
+            )?;
+        }
+        TimestampEncoding::Int96 => {
+            let cw = get_typed_column_writer_mut::<Int96Type>(column_writer);
+            pb.write_optional_fallible(
+                cw,
+                utc.map(|utc| {
+                    utc?.map(|utc| datetime_to_int96(&utc.naive_utc()))
+                        .transpose()
+                }),
+            )?;
+        }
+    }
     Ok(())
 }
 
-fn to_utc_epoch(bytes: &[u8], precision: u8) -> Result<i64, Error> {
-    // Text representation looks like e.g. 2022-09-07 16:04:12 +02:00
+fn to_utc(bytes: &[u8]) -> Result<DateTime<Utc>, Error> {
     let utf8 = String::from_utf8_lossy(bytes);
+    parse_timestamp_tz(&utf8)
+        .with_context(|| format!("Failed to parse timestamp with timezone from string: {utf8}"))
+}
 
-    // Parse to datetime
-    let date_time =
-        DateTime::parse_from_str(&utf8, "%Y-%m-%d %H:%M:%S%.f %:z").with_context(|| {
-            format!(
-                "Failed to parse timestamp with timezone from string: {}",
-                utf8
-            )
-        })?;
-    // let utc = date_time.naive_utc();
-    let utc = date_time.with_timezone(&Utc);
-    let integer = TimestampPrecision::new(precision).datetime_to_i64(&utc)?;
-    Ok(integer)
+/// Parses the text representation of a timestamp with time zone into the instant it refers to. The
+/// date and time may be separated by a space or `T`. The time zone follows, optionally separated
+/// by a space, either as offset (`+02`, `+0200`, `+02:00`, `Z`) or as IANA name
+/// (`Europe/Berlin`).
+fn parse_timestamp_tz(text: &str) -> Result<DateTime<Utc>, Error> {
+    let text = text.trim();
+    let (naive, zone) = NaiveDateTime::parse_and_remainder(text, "%Y-%m-%d %H:%M:%S%.f")
+        .or_else(|_| NaiveDateTime::parse_and_remainder(text, "%Y-%m-%dT%H:%M:%S%.f"))?;
+    let zone = zone.trim_start();
+    let utc = if let Some(offset) = parse_offset(zone)? {
+        offset
+            .from_local_datetime(&naive)
+            .single()
+            .map(|date_time| date_time.with_timezone(&Utc))
+    } else {
+        // Oracle renders region names in upper case, e.g. `EUROPE/BERLIN`.
+        let time_zone = zone
+            .parse::<Tz>()
+            .ok()
+            .or_else(|| {
+                TZ_VARIANTS
+                    .iter()
+                    .copied()
+                    .find(|time_zone| time_zone.name().eq_ignore_ascii_case(zone))
+            })
+            .ok_or_else(|| anyhow!("Unknown time zone '{zone}'"))?;
+        // Local times within a daylight saving transition are ambiguous. Pick the earlier one.
+        time_zone
+            .from_local_datetime(&naive)
+            .earliest()
+            .map(|date_time| date_time.with_timezone(&Utc))
+    };
+    utc.ok_or_else(|| anyhow!("Local time does not exist in time zone '{zone}'"))
+}
+
+/// Offset from UTC like `Z`, `+02`, `+0200`, `+02:00` or `-05:30:15`. `None` if `zone` is not an
+/// offset, but e.g. the name of a time zone.
+fn parse_offset(zone: &str) -> Result<Option<FixedOffset>, Error> {
+    if zone == "Z" {
+        return Ok(Some(Utc.fix()));
+    }
+    let (sign, digits) = match zone.as_bytes().first() {
+        Some(b'+') => (1, &zone[1..]),
+        Some(b'-') => (-1, &zone[1..]),
+        _ => return Ok(None),
+    };
+    let digits = digits.replace(':', "");
+    if !digits.bytes().all(|c| c.is_ascii_digit()) || ![2, 4, 6].contains(&digits.len()) {
+        bail!("Invalid offset from UTC '{zone}'")
+    }
+    let seconds: i32 = digits
+        .as_bytes()
+        .chunks(2)
+        .zip([3600, 60, 1])
+        .map(|(pair, factor)| i32::from((pair[0] - b'0') * 10 + pair[1] - b'0') * factor)
+        .sum();
+    let offset = FixedOffset::east_opt(sign * seconds)
+        .ok_or_else(|| anyhow!("Offset from UTC '{zone}' is out of range"))?;
+    Ok(Some(offset))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, Utc};
+
+    use super::parse_timestamp_tz;
+
+    fn utc(text: &str) -> DateTime<Utc> {
+        text.parse().unwrap()
+    }
+
+    #[test]
+    fn parse_timestamps_with_time_zone_of_different_databases() {
+        // Microsoft SQL Server
+        assert_eq!(
+            utc("2022-09-07T14:04:12Z"),
+            parse_timestamp_tz("2022-09-07 16:04:12 +02:00").unwrap()
+        );
+        // PostgreSQL
+        assert_eq!(
+            utc("2024-01-01T10:00:00Z"),
+            parse_timestamp_tz("2024-01-01 12:00:00+02").unwrap()
+        );
+        assert_eq!(
+            utc("2024-01-01T17:29:01.123456Z"),
+            parse_timestamp_tz("2024-01-01 12:00:00.123456-05:29:01").unwrap()
+        );
+        // Oracle with region name, winter and summer time
+        assert_eq!(
+            utc("2024-01-01T11:00:00Z"),
+            parse_timestamp_tz("2024-01-01 12:00:00.000000 Europe/Berlin").unwrap()
+        );
+        assert_eq!(
+            utc("2024-07-01T10:00:00Z"),
+            parse_timestamp_tz("2024-07-01 12:00:00 Europe/Berlin").unwrap()
+        );
+        assert_eq!(
+            utc("2024-07-01T10:00:00Z"),
+            parse_timestamp_tz("2024-07-01 12:00:00.000000 EUROPE/BERLIN").unwrap()
+        );
+        assert_eq!(
+            utc("2024-07-01T12:00:00Z"),
+            parse_timestamp_tz("2024-07-01T12:00:00Z").unwrap()
+        );
+    }
+
+    #[test]
+    fn reject_timestamps_without_valid_time_zone() {
+        assert!(parse_timestamp_tz("2024-01-01 12:00:00").is_err());
+        assert!(parse_timestamp_tz("2024-01-01 12:00:00 Mars/Olympus").is_err());
+        assert!(parse_timestamp_tz("2024-01-01 12:00:00+2").is_err());
+        // Skipped by the switch to summer time
+        assert!(parse_timestamp_tz("2024-03-31 02:30:00 Europe/Berlin").is_err());
+    }
 }
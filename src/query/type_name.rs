@@ -0,0 +1,99 @@
+use std::ptr::null_mut;
+
+use odbc_api::{
+    handles::Statement,
+    sys::{Desc, SQLColAttribute, SqlReturn},
+    ResultSetMetadata,
+};
+
+/// Buffer length used for the first attempt. Type names are usually short, so this avoids a second
+/// roundtrip in almost all cases.
+const INITIAL_BUFFER_LEN: usize = 64;
+
+/// Database specific name of the column type (`SQL_DESC_TYPE_NAME`), e.g. `_int4` or
+/// `TIMESTAMP(6) WITH TIME ZONE`. `None` if the driver does not report one.
+///
+/// `odbc-api` does not expose this attribute, so this is the one place we call into ODBC directly.
+pub fn column_type_name(cursor: &mut impl ResultSetMetadata, column_number: u16) -> Option<String> {
+    let stmt = cursor.as_stmt_ref();
+    read_string_attribute(|buf| {
+        let mut string_length: i16 = 0;
+        // SAFETY: The statement handle is valid for the lifetime of `stmt`. We pass the length of
+        // `buf` to the driver, so it will not write past its end. The numeric attribute pointer
+        // may be null for character attributes.
+        let ret = unsafe {
+            SQLColAttribute(
+                stmt.as_sys(),
+                column_number,
+                Desc::TypeName,
+                buf.as_mut_ptr() as _,
+                buf.len().try_into().unwrap_or(i16::MAX),
+                &mut string_length,
+                null_mut(),
+            )
+        };
+        matches!(ret, SqlReturn::SUCCESS | SqlReturn::SUCCESS_WITH_INFO)
+            .then(|| string_length.max(0) as usize)
+    })
+}
+
+/// Reads a zero terminated character attribute. `fetch` fills the buffer and returns the length of
+/// the complete attribute, excluding the terminating zero, or `None` in case of an error. Should the
+/// attribute have been truncated, it is fetched again with a buffer large enough to hold it.
+fn read_string_attribute(mut fetch: impl FnMut(&mut [u8]) -> Option<usize>) -> Option<String> {
+    let mut buf = vec![0u8; INITIAL_BUFFER_LEN];
+    let mut len = fetch(&mut buf)?;
+    if len >= buf.len() {
+        // Plus one for the terminating zero
+        buf.resize(len + 1, 0);
+        len = fetch(&mut buf)?;
+    }
+    // Clamp, in case the driver reports a different length the second time around.
+    let len = len.min(buf.len() - 1);
+    Some(String::from_utf8_lossy(&buf[..len]).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{read_string_attribute, INITIAL_BUFFER_LEN};
+
+    /// Mimics a driver writing a zero terminated, possibly truncated, attribute.
+    fn driver(value: &str) -> impl FnMut(&mut [u8]) -> Option<usize> + '_ {
+        |buf| {
+            let n = value.len().min(buf.len() - 1);
+            buf[..n].copy_from_slice(&value.as_bytes()[..n]);
+            buf[n] = 0;
+            Some(value.len())
+        }
+    }
+
+    #[test]
+    fn read_short_attribute() {
+        assert_eq!(
+            Some("timestamptz".to_owned()),
+            read_string_attribute(driver("timestamptz"))
+        );
+    }
+
+    #[test]
+    fn read_attribute_longer_than_initial_buffer() {
+        let long = "x".repeat(INITIAL_BUFFER_LEN * 3);
+        let mut num_calls = 0;
+        let mut fetch = driver(&long);
+        let actual = read_string_attribute(|buf| {
+            num_calls += 1;
+            fetch(buf)
+        });
+        assert_eq!(Some(long.clone()), actual);
+        assert_eq!(2, num_calls);
+
+        // Exactly as long as the initial buffer, which leaves no space for the terminating zero.
+        let long = "x".repeat(INITIAL_BUFFER_LEN);
+        assert_eq!(Some(long.clone()), read_string_attribute(driver(&long)));
+    }
+
+    #[test]
+    fn error_reading_attribute() {
+        assert_eq!(None, read_string_attribute(|_| None));
+    }
+}
@@ -0,0 +1,146 @@
+use anyhow::{anyhow, Error};
+use arrow_array::ArrayRef;
+use arrow_schema::DataType as ArrowDataType;
+use odbc_api::buffers::{AnyColumnBufferSlice, BufferDesc};
+use parquet::{
+    basic::{LogicalType, Repetition, Type as PhysicalType},
+    column::writer::{get_typed_column_writer_mut, ColumnWriter},
+    data_type::{ByteArray, FixedLenByteArray, FixedLenByteArrayType},
+    schema::types::Type,
+};
+
+use crate::parquet_buffer::ParquetBuffer;
+
+use super::{arrow_column::ArrowValue, column_strategy::ColumnStrategy};
+
+/// Number of characters in the canonical text representation of a UUID. E.g.
+/// `6f9619ff-8b86-d011-b42d-00c04fc964ff`
+pub(crate) const UUID_TEXT_LENGTH: usize = 36;
+
+/// Fetches `SQL_GUID` columns as text and stores them as 16 byte `FIXED_LEN_BYTE_ARRAY` with
+/// logical type `UUID`. We bind text rather than `SQL_C_GUID`, since the byte order of the latter
+/// depends on the platform, while the text representation is the same for every driver.
+pub struct Uuid {
+    repetition: Repetition,
+}
+
+impl Uuid {
+    pub fn new(repetition: Repetition) -> Self {
+        Self { repetition }
+    }
+}
+
+impl ColumnStrategy for Uuid {
+    fn parquet_type(&self, name: &str) -> Type {
+        Type::primitive_type_builder(name, PhysicalType::FIXED_LEN_BYTE_ARRAY)
+            .with_length(16)
+            .with_logical_type(Some(LogicalType::Uuid))
+            .with_repetition(self.repetition)
+            .build()
+            .unwrap()
+    }
+
+    fn buffer_desc(&self) -> BufferDesc {
+        BufferDesc::Text {
+            max_str_len: UUID_TEXT_LENGTH,
+        }
+    }
+
+    fn copy_odbc_to_parquet(
+        &self,
+        parquet_buffer: &mut ParquetBuffer,
+        column_writer: &mut ColumnWriter,
+        column_view: AnyColumnBufferSlice,
+    ) -> Result<(), Error> {
+        let view = column_view.as_text().expect(
+            "Invalid Column view type. This is not supposed to happen. Please open a Bug at \
+            https://github.com/pacman82/odbc2parquet/issues.",
+        );
+        let cw = get_typed_column_writer_mut::<FixedLenByteArrayType>(column_writer);
+        parquet_buffer.write_optional_fallible(
+            cw,
+            view.iter().map(|item| {
+                item.map(|text| {
+                    let bytes: ByteArray = parse_uuid(text)?.to_vec().into();
+                    Ok::<FixedLenByteArray, Error>(bytes.into())
+                })
+                .transpose()
+            }),
+        )?;
+        Ok(())
+    }
+
+    fn copy_odbc_to_arrow(
+        &self,
+        data_type: &ArrowDataType,
+        column_view: AnyColumnBufferSlice,
+    ) -> Result<ArrayRef, Error> {
+        let view = column_view.as_text().expect(
+            "Invalid Column view type. This is not supposed to happen. Please open a Bug at \
+            https://github.com/pacman82/odbc2parquet/issues.",
+        );
+        let uuids = view
+            .iter()
+            .map(|item| item.map(parse_uuid).transpose())
+            .collect::<Result<Vec<_>, _>>()?;
+        <&[u8]>::arrow_array(
+            uuids
+                .iter()
+                .map(|uuid| uuid.as_ref().map(|bytes| &bytes[..])),
+            data_type,
+        )
+    }
+}
+
+/// Parses the text representation of a UUID into its 16 bytes in big endian order. Hyphens and
+/// surrounding curly braces are ignored.
+fn parse_uuid(text: &[u8]) -> Result<[u8; 16], Error> {
+    let invalid = || {
+        anyhow!(
+            "Invalid UUID: '{}'. Expected 32 hexadecimal digits.",
+            String::from_utf8_lossy(text)
+        )
+    };
+    let mut bytes = [0u8; 16];
+    let mut num_digits = 0;
+    for &c in text {
+        if matches!(c, b'-' | b'{' | b'}') {
+            continue;
+        }
+        let digit = (c as char).to_digit(16).ok_or_else(invalid)? as u8;
+        if num_digits == 32 {
+            return Err(invalid());
+        }
+        // Even digits are the high nibble, odd ones the low nibble of a byte.
+        let shift = if num_digits % 2 == 0 { 4 } else { 0 };
+        bytes[num_digits / 2] |= digit << shift;
+        num_digits += 1;
+    }
+    if num_digits != 32 {
+        return Err(invalid());
+    }
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_uuid;
+
+    #[test]
+    fn parse_uuid_from_text() {
+        let expected = [
+            0x6f, 0x96, 0x19, 0xff, 0x8b, 0x86, 0xd0, 0x11, 0xb4, 0x2d, 0x00, 0xc0, 0x4f, 0xc9,
+            0x64, 0xff,
+        ];
+        assert_eq!(
+            expected,
+            parse_uuid(b"6F9619FF-8B86-D011-B42D-00C04FC964FF").unwrap()
+        );
+        assert_eq!(
+            expected,
+            parse_uuid(b"{6f9619ff-8b86-d011-b42d-00c04fc964ff}").unwrap()
+        );
+        assert!(parse_uuid(b"6F9619FF-8B86-D011-B42D").is_err());
+        assert!(parse_uuid(b"6F9619FF-8B86-D011-B42D-00C04FC964FG").is_err());
+    }
+}
@@ -0,0 +1,320 @@
+use std::{
+    fs,
+    io::{ErrorKind, Write},
+    path::PathBuf,
+};
+
+use anyhow::{bail, format_err, Context, Error};
+use chrono::{NaiveDate, NaiveDateTime};
+use odbc_api::{
+    buffers::{AnyColumnBufferSlice, BufferDesc},
+    parameter::InputParameter,
+    sys::{Date, Timestamp},
+    DataType, IntoParameter, Pod,
+};
+use serde::{Deserialize, Serialize};
+use tempfile::NamedTempFile;
+
+use super::parameter::{date_parameter, timestamp_parameter};
+
+/// Message we emmit if we hit a code path we expected to be unreachable.
+const BUG: &str = "Invalid Column view type. This is not supposed to happen. Please open a Bug at \
+                  https://github.com/pacman82/odbc2parquet/issues.";
+
+/// Largest value of the incremental column seen so far. Rows with values larger than the watermark
+/// of the last successful run are considered new.
+#[derive(Debug, Clone, PartialEq, PartialOrd, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Watermark {
+    Integer(i64),
+    Float(f64),
+    Date(NaiveDate),
+    Timestamp(NaiveDateTime),
+    Text(String),
+}
+
+impl Watermark {
+    /// Fails if the maximum of the column can not be determined from its fetched values. Text
+    /// is compared character by character, so this is only correct for character data. Decimals or
+    /// vendor specific types fetched as text (e.g. `datetimeoffset`) would compare wrongly, e.g.
+    /// `9.50` would be larger than `10.00`.
+    pub fn check_column(name: &str, data_type: &DataType, desc: BufferDesc) -> Result<(), Error> {
+        let is_text = matches!(desc, BufferDesc::Text { .. } | BufferDesc::WText { .. });
+        let is_character_data = matches!(
+            data_type,
+            DataType::Char { .. }
+                | DataType::WChar { .. }
+                | DataType::Varchar { .. }
+                | DataType::WVarchar { .. }
+                | DataType::LongVarchar { .. }
+                | DataType::WLongVarchar { .. }
+        );
+        if is_text && !is_character_data {
+            bail!(
+                "Incremental column '{name}' of type {data_type:?} is fetched as text, so its \
+                values can not be compared. Please use a column of integer, floating point, date, \
+                timestamp or character type, or cast it to one in the query."
+            )
+        }
+        Ok(())
+    }
+
+    /// Largest value in the fetched column. `None` if the batch is empty or all values are `NULL`.
+    pub fn batch_max(
+        column: AnyColumnBufferSlice,
+        desc: BufferDesc,
+    ) -> Result<Option<Watermark>, Error> {
+        let max = match desc {
+            BufferDesc::I8 { nullable } => {
+                pod_max(column, nullable, |&i: &i8| Ok(Watermark::Integer(i.into())))?
+            }
+            BufferDesc::I16 { nullable } => pod_max(column, nullable, |&i: &i16| {
+                Ok(Watermark::Integer(i.into()))
+            })?,
+            BufferDesc::I32 { nullable } => pod_max(column, nullable, |&i: &i32| {
+                Ok(Watermark::Integer(i.into()))
+            })?,
+            BufferDesc::I64 { nullable } => {
+                pod_max(column, nullable, |&i: &i64| Ok(Watermark::Integer(i)))?
+            }
+            BufferDesc::U8 { nullable } => {
+                pod_max(column, nullable, |&i: &u8| Ok(Watermark::Integer(i.into())))?
+            }
+            BufferDesc::F32 { nullable } => {
+                pod_max(column, nullable, |&f: &f32| Ok(Watermark::Float(f.into())))?
+            }
+            BufferDesc::F64 { nullable } => {
+                pod_max(column, nullable, |&f: &f64| Ok(Watermark::Float(f)))?
+            }
+            BufferDesc::Date { nullable } => pod_max(column, nullable, |date: &Date| {
+                naive_date(date.year, date.month, date.day).map(Watermark::Date)
+            })?,
+            BufferDesc::Timestamp { nullable } => pod_max(column, nullable, |ts: &Timestamp| {
+                naive_date(ts.year, ts.month, ts.day)?
+                    .and_hms_nano_opt(
+                        ts.hour.into(),
+                        ts.minute.into(),
+                        ts.second.into(),
+                        ts.fraction,
+                    )
+                    .map(Watermark::Timestamp)
+                    .ok_or_else(|| format_err!("Invalid timestamp in incremental column: {ts:?}"))
+            })?,
+            BufferDesc::Text { .. } => column
+                .as_text()
+                .expect(BUG)
+                .iter()
+                .flatten()
+                .max()
+                .map(|text| Watermark::Text(String::from_utf8_lossy(text).into_owned())),
+            BufferDesc::WText { .. } => column
+                .as_wide_text()
+                .expect(BUG)
+                .iter()
+                .flatten()
+                .map(|text| String::from_utf16_lossy(text.as_slice()))
+                .max()
+                .map(Watermark::Text),
+            BufferDesc::Binary { .. }
+            | BufferDesc::Bit { .. }
+            | BufferDesc::Time { .. }
+            | BufferDesc::Numeric => bail!(
+                "Incremental column must be fetched as integer, floating point, date, timestamp or \
+                text."
+            ),
+        };
+        Ok(max)
+    }
+
+    /// Larger of two optional watermarks.
+    pub fn max(left: Option<Watermark>, right: Option<Watermark>) -> Option<Watermark> {
+        match (left, right) {
+            (Some(left), Some(right)) => Some(if right > left { right } else { left }),
+            (left, None) => left,
+            (None, right) => right,
+        }
+    }
+
+    /// Parameter bound to the placeholder comparing the incremental column with the watermark.
+    pub fn to_parameter(&self) -> Box<dyn InputParameter> {
+        match self {
+            Watermark::Integer(i) => Box::new(*i),
+            Watermark::Float(f) => Box::new(*f),
+            Watermark::Date(date) => date_parameter(date),
+            Watermark::Timestamp(ts) => timestamp_parameter(ts),
+            Watermark::Text(text) => Box::new(text.clone().into_parameter()),
+        }
+    }
+}
+
+fn naive_date(year: i16, month: u16, day: u16) -> Result<NaiveDate, Error> {
+    NaiveDate::from_ymd_opt(year.into(), month.into(), day.into()).ok_or_else(|| {
+        format_err!("Invalid date in incremental column: {year:04}-{month:02}-{day:02}")
+    })
+}
+
+/// Largest non `NULL` value of a column of plain old data, after converting it to a watermark.
+fn pod_max<T>(
+    column: AnyColumnBufferSlice,
+    nullable: bool,
+    to_watermark: impl Fn(&T) -> Result<Watermark, Error>,
+) -> Result<Option<Watermark>, Error>
+where
+    T: Pod,
+{
+    let mut max = None;
+    if nullable {
+        for value in column.as_nullable_slice::<T>().expect(BUG).flatten() {
+            max = Watermark::max(max, Some(to_watermark(value)?));
+        }
+    } else {
+        for value in column.as_slice::<T>().expect(BUG) {
+            max = Watermark::max(max, Some(to_watermark(value)?));
+        }
+    }
+    Ok(max)
+}
+
+/// Persisted state of an incremental extraction.
+#[derive(Serialize, Deserialize)]
+struct State {
+    /// Name of the incremental column. Used to detect state files mixed up between extractions.
+    column: String,
+    watermark: Watermark,
+}
+
+/// File remembering the watermark between runs of an incremental extraction.
+pub struct StateFile {
+    path: PathBuf,
+    column: String,
+}
+
+impl StateFile {
+    pub fn new(path: PathBuf, column: String) -> Self {
+        Self { path, column }
+    }
+
+    /// Name of the column compared against the watermark.
+    pub fn column(&self) -> &str {
+        &self.column
+    }
+
+    /// Watermark of the last successful run. `None` if the state file does not exist yet, i.e.
+    /// this is the first run.
+    pub fn load(&self) -> Result<Option<Watermark>, Error> {
+        let content = match fs::read_to_string(&self.path) {
+            Ok(content) => content,
+            Err(error) if error.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(error) => {
+                return Err(error)
+                    .with_context(|| format!("Could not read state file {:?}", self.path))
+            }
+        };
+        let state: State = serde_json::from_str(&content)
+            .with_context(|| format!("Could not parse state file {:?}", self.path))?;
+        if state.column != self.column {
+            bail!(
+                "State file {:?} tracks the column '{}', but the incremental column is '{}'.",
+                self.path,
+                state.column,
+                self.column
+            )
+        }
+        Ok(Some(state.watermark))
+    }
+
+    /// Persist the watermark for the next run. The file is replaced atomically, so a crash can not
+    /// leave behind a corrupted state.
+    pub fn commit(&self, watermark: Watermark) -> Result<(), Error> {
+        let state = State {
+            column: self.column.clone(),
+            watermark,
+        };
+        let dir = match self.path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => ".".as_ref(),
+        };
+        let mut file = NamedTempFile::new_in(dir)?;
+        serde_json::to_writer_pretty(&mut file, &state)?;
+        file.write_all(b"\n")?;
+        file.persist(&self.path)
+            .with_context(|| format!("Could not write state file {:?}", self.path))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZeroUsize;
+
+    use chrono::NaiveDate;
+    use odbc_api::{buffers::BufferDesc, DataType};
+    use tempfile::tempdir;
+
+    use super::{StateFile, Watermark};
+
+    #[test]
+    fn commit_and_load_watermark() {
+        let dir = tempdir().unwrap();
+        let state_file = StateFile::new(dir.path().join("state.json"), "updated_at".to_owned());
+        assert_eq!(None, state_file.load().unwrap());
+
+        let watermark = Watermark::Timestamp(
+            NaiveDate::from_ymd_opt(2024, 1, 2)
+                .unwrap()
+                .and_hms_milli_opt(3, 4, 5, 678)
+                .unwrap(),
+        );
+        state_file.commit(watermark.clone()).unwrap();
+
+        assert_eq!(Some(watermark), state_file.load().unwrap());
+    }
+
+    #[test]
+    fn reject_state_of_other_column() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("state.json");
+        StateFile::new(path.clone(), "id".to_owned())
+            .commit(Watermark::Integer(42))
+            .unwrap();
+
+        let result = StateFile::new(path, "updated_at".to_owned()).load();
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn max_of_watermarks() {
+        assert_eq!(
+            Some(Watermark::Integer(2)),
+            Watermark::max(Some(Watermark::Integer(2)), Some(Watermark::Integer(1)))
+        );
+        assert_eq!(
+            Some(Watermark::Integer(1)),
+            Watermark::max(None, Some(Watermark::Integer(1)))
+        );
+        assert_eq!(None, Watermark::max(None, None));
+    }
+
+    #[test]
+    fn reject_decimal_fetched_as_text() {
+        let decimal = DataType::Decimal {
+            precision: 10,
+            scale: 2,
+        };
+        let text = BufferDesc::Text { max_str_len: 12 };
+
+        assert!(Watermark::check_column("price", &decimal, text).is_err());
+        assert!(
+            Watermark::check_column("price", &decimal, BufferDesc::F64 { nullable: true }).is_ok()
+        );
+        assert!(Watermark::check_column(
+            "name",
+            &DataType::Varchar {
+                length: NonZeroUsize::new(12)
+            },
+            text
+        )
+        .is_ok());
+    }
+}
@@ -1,5 +1,5 @@
 use std::{
-    fs::File,
+    fs::{self, File},
     io::{ErrorKind, Write},
     path::{Path, PathBuf},
     str,
@@ -13,12 +13,15 @@ use odbc_api::{
     ConnectionOptions, Cursor, Environment, IntoParameter,
 };
 use parquet::{
+    arrow::arrow_reader::ParquetRecordBatchReaderBuilder,
+    basic::Compression,
     column::writer::ColumnWriter,
     data_type::{ByteArray, FixedLenByteArray},
     file::{
         properties::WriterProperties, reader::FileReader, serialized_reader::SerializedFileReader,
         writer::SerializedFileWriter,
     },
+    record::Field,
     schema::parser::parse_message_type,
 };
 use predicates::{ord::eq, str::contains};
@@ -230,6 +233,165 @@ fn parameters_in_query() {
     parquet_read_out(out_str).stdout(eq(expected));
 }
 
+#[test]
+fn typed_parameters_in_query() {
+    // Given
+    let table_name = "TypedParametersInQuery";
+    let mut table = TableMssql::new(table_name, &["DATE", "INTEGER"]);
+    table.insert_rows_as_text(&[
+        [Some("2024-01-01"), Some("5")],
+        [Some("2024-02-01"), Some("42")],
+        [Some("2024-03-01"), None],
+    ]);
+    let out_dir = tempdir().unwrap();
+    let out_path = out_dir.path().join("out.par");
+    let out_str = out_path.to_str().expect("Temporary file path must be utf8");
+    let query =
+        format!("SELECT a,b FROM {table_name} WHERE a > ? AND b = ? OR b IS NULL AND ? IS NULL");
+
+    // When
+    cargo_bin_cmd!()
+        .args([
+            "-vvvv",
+            "query",
+            out_str,
+            "--connection-string",
+            MSSQL,
+            &query,
+            "date:2024-01-15",
+            "int:42",
+            "null",
+        ])
+        .assert()
+        .success();
+
+    // Then
+    let expected = "\
+        {a: 2024-02-01, b: 42}\n\
+        {a: 2024-03-01, b: null}\n\
+    ";
+    parquet_read_out(out_str).stdout(eq(expected));
+}
+
+#[test]
+pub fn reject_invalid_typed_parameter() {
+    cargo_bin_cmd!()
+        .args([
+            "query",
+            "--connection-string",
+            "FakeConnectionString",
+            "out.par",
+            "SELECT * FROM Birthdays WHERE year > ?",
+            "int:nineteen",
+        ])
+        .assert()
+        .failure()
+        .stderr(contains("Invalid integer parameter 'nineteen'"));
+}
+
+#[test]
+fn query_timeout_cancels_statement_and_retries() {
+    // Given
+    let out_dir = tempdir().unwrap();
+    let out_path = out_dir.path().join("out.par");
+    let out_str = out_path.to_str().expect("Temporary file path must be utf8");
+
+    // When
+    let assert = cargo_bin_cmd!()
+        .args([
+            "-vv",
+            "query",
+            out_str,
+            "--connection-string",
+            MSSQL,
+            "--query-timeout",
+            "1",
+            "--retries",
+            "1",
+            "--retry-backoff",
+            "0",
+            "WAITFOR DELAY '00:00:03'; SELECT 42 AS a",
+        ])
+        .assert();
+
+    // Then
+    assert
+        .failure()
+        .stderr(contains("Transient error, retrying"))
+        .stderr(contains("HYT00"));
+}
+
+#[test]
+pub fn reject_retries_when_writing_to_stdout() {
+    cargo_bin_cmd!()
+        .args([
+            "-vvvv",
+            "query",
+            "--connection-string",
+            "FakeConnectionString",
+            "--retries",
+            "3",
+            "-",
+            "SELECT a FROM FakeTableName",
+        ])
+        .assert()
+        .failure()
+        .stderr(contains(
+            "retries conflicts with specifying stdout ('-') as output.",
+        ));
+}
+
+#[test]
+fn query_once_per_row_of_parameter_file() {
+    // Given
+    let table_name = "QueryOncePerRowOfParameterFile";
+    let mut table = TableMssql::new(table_name, &["INTEGER", "VARCHAR(10)"]);
+    table.insert_rows_as_text(&[
+        [Some("1"), Some("one")],
+        [Some("2"), Some("two")],
+        [Some("3"), Some("three")],
+    ]);
+    let message_type = "
+        message schema {
+            OPTIONAL BYTE_ARRAY name (UTF8);
+            OPTIONAL INT32 id;
+        }
+    ";
+    let names: [Option<ByteArray>; 2] = [Some("three".into()), Some("one".into())];
+    let params = TmpParquetFile::with_2_dim(message_type, &names, &[Some(3i32), Some(1i32)]);
+    let out_dir = tempdir().unwrap();
+    let out_path = out_dir.path().join("out.par");
+    let out_str = out_path.to_str().expect("Temporary file path must be utf8");
+    // Placeholders reference the columns by name, so their order does not matter
+    let query = format!("SELECT a,b FROM {table_name} WHERE a = ?id? AND b = ?name?");
+
+    // When
+    cargo_bin_cmd!()
+        .args([
+            "-vvvv",
+            "query",
+            out_str,
+            "--connection-string",
+            MSSQL,
+            "--parameters-from",
+            params.path_as_str(),
+            &query,
+        ])
+        .assert()
+        .success();
+
+    // Then results are appended in order of the parameter rows
+    let expected = "\
+        {a: 3, b: \"three\"}\n\
+        {a: 1, b: \"one\"}\n\
+    ";
+    parquet_read_out(out_str).stdout(eq(expected));
+    // Rows of both executions are written into the same row group
+    let file = File::open(&out_path).unwrap();
+    let reader = SerializedFileReader::new(file).unwrap();
+    assert_eq!(1, reader.metadata().num_row_groups());
+}
+
 #[test]
 fn should_error_on_truncation_utf_8() {
     // Setup table for test
@@ -310,80 +472,686 @@ fn should_error_on_truncation_utf_16() {
 }
 
 #[test]
-fn should_error_on_truncation_for_sequential_fetch() {
-    // Setup table for test
-    let table_name = "ErrorOnTruncationForSequentialFetch";
-    let mut table = TableMssql::new(table_name, &["VARCHAR(10)"]);
-    table.insert_rows_as_text(&[["0123456789"]]);
-
-    // A temporary directory, to be removed at the end of the test.
-    let out_dir = tempdir().unwrap();
-    // The name of the output parquet file we are going to write. Since it is in a temporary
-    // directory it will not outlive the end of the test.
-    let out_path = out_dir.path().join("out.par");
-    // We need to pass the output path as a string argument.
-    let out_str = out_path.to_str().expect("Temporary file path must be utf8");
-
+fn should_error_on_truncation_for_sequential_fetch() {
+    // Setup table for test
+    let table_name = "ErrorOnTruncationForSequentialFetch";
+    let mut table = TableMssql::new(table_name, &["VARCHAR(10)"]);
+    table.insert_rows_as_text(&[["0123456789"]]);
+
+    // A temporary directory, to be removed at the end of the test.
+    let out_dir = tempdir().unwrap();
+    // The name of the output parquet file we are going to write. Since it is in a temporary
+    // directory it will not outlive the end of the test.
+    let out_path = out_dir.path().join("out.par");
+    // We need to pass the output path as a string argument.
+    let out_str = out_path.to_str().expect("Temporary file path must be utf8");
+
+    let query = format!("SELECT a FROM {table_name}");
+
+    let assertion = cargo_bin_cmd!()
+        .args([
+            "-vvvv",
+            "query",
+            out_str,
+            "--connection-string",
+            MSSQL,
+            "--sequential-fetching",
+            "--column-length-limit",
+            "5",
+            &query,
+        ])
+        .assert();
+
+    #[cfg(target_os = "windows")]
+    let expectation = "A field exceeds the maximum element length of a column buffer. You can \
+        use the `--column-length-limit` option to increase the maximum element size of columns. \
+        Sadly the driver did not return a length indicator for the value, so you will have to \
+        guess its actual length. The error occurred for column a.";
+    #[cfg(not(target_os = "windows"))]
+    let expectation = "A field exceeds the maximum element length of a column buffer. You can \
+        use the `--column-length-limit` option to increase the maximum element size of columns. \
+        The driver indicated an actual length of 10. The error occurred for column a.";
+    assertion.failure().stderr(contains(expectation));
+}
+
+/// A column length limit for an individual column takes precedence over the global one.
+#[test]
+fn column_length_limit_for_individual_column() {
+    // Setup table for test
+    let table_name = "ColumnLengthLimitForIndividualColumn";
+    let mut table = TableMssql::new(table_name, &["VARCHAR(10)", "VARCHAR(10)"]);
+    table.insert_rows_as_text(&[["0123456789", "01234"]]);
+    // A temporary directory, to be removed at the end of the test.
+    let out_dir = tempdir().unwrap();
+    let out_path = out_dir.path().join("out.par");
+    let out_str = out_path.to_str().expect("Temporary file path must be utf8");
+
+    let query = format!("SELECT a, b FROM {table_name}");
+
+    cargo_bin_cmd!()
+        .args([
+            "-vvvv",
+            "query",
+            out_str,
+            "--connection-string",
+            MSSQL,
+            "--column-length-limit",
+            "5",
+            "--column-length-limit",
+            "a:10",
+            &query,
+        ])
+        .assert()
+        .success();
+
+    let expected = "{a: \"0123456789\", b: \"01234\"}\n";
+    parquet_read_out(out_str).stdout(eq(expected));
+}
+
+#[test]
+fn should_allow_specifying_explicit_compression_level() {
+    // Setup table for test
+    let table_name = "ShouldAllowSpecifyingExplicitCompressionLevel";
+    let mut table = TableMssql::new(table_name, &["VARCHAR(10)"]);
+    table.insert_rows_as_text(&[["Hello"], ["World"]]);
+    // A temporary directory, to be removed at the end of the test.
+    let out_dir = tempdir().unwrap();
+    // The name of the output parquet file we are going to write. Since it is in a temporary
+    // directory it will not outlive the end of the test.
+    let out_path = out_dir.path().join("out.par");
+    // We need to pass the output path as a string argument.
+    let out_str = out_path.to_str().expect("Temporary file path must be utf8");
+
+    let query = format!("SELECT a FROM {table_name}");
+
+    cargo_bin_cmd!()
+        .args([
+            "-vvvv",
+            "query",
+            "--connection-string",
+            MSSQL,
+            "--column-compression-default",
+            "gzip",
+            "--column-compression-level-default",
+            "9",
+            out_str,
+            &query,
+            "42",
+        ])
+        .assert()
+        .success();
+}
+
+#[test]
+fn per_column_compression_dictionary_and_statistics() {
+    // Given
+    let table_name = "PerColumnCompressionDictionaryAndStatistics";
+    let mut table = TableMssql::new(table_name, &["VARCHAR(50)", "INTEGER"]);
+    table.insert_rows_as_text(&[["Hello", "1"], ["World", "2"]]);
+    let out_dir = tempdir().unwrap();
+    let out_path = out_dir.path().join("out.par");
+    let out_str = out_path.to_str().expect("Temporary file path must be utf8");
+
+    let query = format!("SELECT a, b FROM {table_name}");
+
+    // When
+    cargo_bin_cmd!()
+        .args([
+            "-vvvv",
+            "query",
+            "--connection-string",
+            MSSQL,
+            "--parquet-column-compression",
+            "a:zstd(9)",
+            "--parquet-column-compression",
+            "b:snappy",
+            "--parquet-column-dictionary",
+            "a:off",
+            "--parquet-column-statistics",
+            "a:none",
+            out_str,
+            &query,
+        ])
+        .assert()
+        .success();
+
+    // Then
+    let file = File::open(&out_path).unwrap();
+    let reader = SerializedFileReader::new(file).unwrap();
+    let row_group = reader.metadata().row_group(0);
+    let a = row_group.column(0);
+    let b = row_group.column(1);
+    // Compression levels are not stored in the file, only the codec.
+    assert!(matches!(a.compression(), Compression::ZSTD(_)));
+    assert_eq!(Compression::SNAPPY, b.compression());
+    assert!(a.dictionary_page_offset().is_none());
+    assert!(b.dictionary_page_offset().is_some());
+    assert!(a.statistics().is_none());
+    assert!(b.statistics().is_some());
+}
+
+#[test]
+fn write_bloom_filter() {
+    // Given
+    let table_name = "WriteBloomFilter";
+    let mut table = TableMssql::new(table_name, &["INTEGER", "INTEGER"]);
+    table.insert_rows_as_text(&[["1", "1"], ["2", "2"]]);
+    let out_dir = tempdir().unwrap();
+    let out_path = out_dir.path().join("out.par");
+    let out_str = out_path.to_str().expect("Temporary file path must be utf8");
+
+    let query = format!("SELECT a, b FROM {table_name}");
+
+    // When
+    cargo_bin_cmd!()
+        .args([
+            "-vvvv",
+            "query",
+            "--connection-string",
+            MSSQL,
+            "--bloom-filter",
+            "a:0.01:1000",
+            out_str,
+            &query,
+        ])
+        .assert()
+        .success();
+
+    // Then
+    let file = File::open(&out_path).unwrap();
+    let reader = SerializedFileReader::new(file).unwrap();
+    let row_group = reader.metadata().row_group(0);
+    assert!(row_group.column(0).bloom_filter_offset().is_some());
+    assert!(row_group.column(1).bloom_filter_offset().is_none());
+}
+
+#[test]
+fn provenance_and_custom_key_value_metadata() {
+    // Given
+    let table_name = "ProvenanceAndCustomKeyValueMetadata";
+    let mut table = TableMssql::new(table_name, &["INTEGER"]);
+    table.insert_rows_as_text(&[["1"], ["2"]]);
+    let out_dir = tempdir().unwrap();
+    let out_path = out_dir.path().join("out.par");
+    let out_str = out_path.to_str().expect("Temporary file path must be utf8");
+
+    let query = format!("SELECT a FROM {table_name} WHERE a > ?");
+
+    // When
+    cargo_bin_cmd!()
+        .args([
+            "-vvvv",
+            "query",
+            "--connection-string",
+            MSSQL,
+            "--metadata",
+            "team=finance",
+            out_str,
+            &query,
+            "0",
+        ])
+        .assert()
+        .success();
+
+    // Then
+    let file = File::open(&out_path).unwrap();
+    let reader = SerializedFileReader::new(file).unwrap();
+    let key_value_metadata = reader
+        .metadata()
+        .file_metadata()
+        .key_value_metadata()
+        .unwrap();
+    let value_of = |key: &str| {
+        key_value_metadata
+            .iter()
+            .find(|kv| kv.key == key)
+            .and_then(|kv| kv.value.clone())
+    };
+    assert_eq!(Some(query.clone()), value_of("odbc2parquet.query"));
+    assert_eq!(
+        Some("[\"0\"]".to_owned()),
+        value_of("odbc2parquet.parameters")
+    );
+    assert_eq!(
+        Some("Microsoft SQL Server".to_owned()),
+        value_of("odbc2parquet.dbms")
+    );
+    assert_eq!(Some("2".to_owned()), value_of("odbc2parquet.row_count"));
+    assert!(value_of("odbc2parquet.version").is_some());
+    assert!(value_of("odbc2parquet.start_time").is_some());
+    assert!(value_of("odbc2parquet.end_time").is_some());
+    assert_eq!(Some("finance".to_owned()), value_of("team"));
+}
+
+#[test]
+fn write_arrow_schema_hint() {
+    // Given
+    let table_name = "WriteArrowSchemaHint";
+    let mut table = TableMssql::new(table_name, &["SMALLINT"]);
+    table.insert_rows_as_text(&[["1"]]);
+    let out_dir = tempdir().unwrap();
+    let out_path = out_dir.path().join("out.par");
+    let out_str = out_path.to_str().expect("Temporary file path must be utf8");
+
+    let query = format!("SELECT a FROM {table_name}");
+
+    // When
+    cargo_bin_cmd!()
+        .args([
+            "-vvvv",
+            "query",
+            "--connection-string",
+            MSSQL,
+            "--arrow-schema",
+            out_str,
+            &query,
+        ])
+        .assert()
+        .success();
+
+    // Then
+    let file = File::open(&out_path).unwrap();
+    let reader = SerializedFileReader::new(file).unwrap();
+    let has_arrow_schema = reader
+        .metadata()
+        .file_metadata()
+        .key_value_metadata()
+        .unwrap()
+        .iter()
+        .any(|kv| kv.key == "ARROW:schema" && kv.value.is_some());
+    assert!(has_arrow_schema);
+    // Without the hint, Arrow readers would widen SMALLINT to a 32 Bit integer.
+    let file = File::open(&out_path).unwrap();
+    let builder = ParquetRecordBatchReaderBuilder::try_new(file).unwrap();
+    assert_eq!(
+        &arrow_schema::DataType::Int16,
+        builder.schema().field(0).data_type()
+    );
+}
+
+#[test]
+fn write_csv_to_stdout() {
+    // Given
+    let table_name = "WriteCsvToStdout";
+    let mut table = TableMssql::new(table_name, &["INT", "VARCHAR(20)", "DATETIME2(3)"]);
+    table.insert_rows_as_text(&[
+        ["1", "Hello, World", "2024-01-02 03:04:05.678"],
+        ["2", "say \"hi\"", "2024-01-02 03:04:05"],
+    ]);
+    let query = format!("SELECT a, b, c FROM {table_name} ORDER BY id");
+
+    // When
+    let output = cargo_bin_cmd!()
+        .args([
+            "-vvvv",
+            "query",
+            "--connection-string",
+            MSSQL,
+            "--format",
+            "csv",
+            "-",
+            &query,
+        ])
+        .assert()
+        .success();
+
+    // Then
+    let expected = "\
+        a,b,c\n\
+        1,\"Hello, World\",2024-01-02 03:04:05.678\n\
+        2,\"say \"\"hi\"\"\",2024-01-02 03:04:05\n\
+    ";
+    output.stdout(eq(expected));
+}
+
+#[test]
+fn write_json_lines_split_into_files() {
+    // Given
+    let table_name = "WriteJsonLinesSplitIntoFiles";
+    let mut table = TableMssql::new(table_name, &["INT", "VARCHAR(20)"]);
+    table.insert_rows_as_text(&[["1", "one"], ["2", "two"]]);
+    let out_dir = tempdir().unwrap();
+    let out_path = out_dir.path().join("out.jsonl");
+    let out_str = out_path.to_str().expect("Temporary file path must be utf8");
+    let query = format!("SELECT a, b FROM {table_name} ORDER BY id");
+
+    // When
+    cargo_bin_cmd!()
+        .args([
+            "-vvvv",
+            "query",
+            "--connection-string",
+            MSSQL,
+            "--format",
+            "jsonl",
+            "--batch-size-row",
+            "1",
+            "--row-groups-per-file",
+            "1",
+            out_str,
+            &query,
+        ])
+        .assert()
+        .success();
+
+    // Then
+    assert_eq!(
+        "{\"a\":1,\"b\":\"one\"}\n",
+        fs::read_to_string(out_dir.path().join("out_01.jsonl")).unwrap()
+    );
+    assert_eq!(
+        "{\"a\":2,\"b\":\"two\"}\n",
+        fs::read_to_string(out_dir.path().join("out_02.jsonl")).unwrap()
+    );
+}
+
+#[test]
+fn write_json_lines_with_converted_values_postgres() {
+    // Given
+    let table_name = "WriteJsonLinesWithConvertedValues";
+    let conn = env()
+        .connect_with_connection_string(POSTGRES, ConnectionOptions::default())
+        .unwrap();
+    setup_empty_table_pg(&conn, table_name, &["TIMESTAMPTZ", "INTEGER[]"]).unwrap();
+    let insert = format!(
+        "INSERT INTO {table_name}
+        (a, b)
+        VALUES
+        ('2022-09-07 16:04:12 +02:00', '{{1,NULL,3}}');"
+    );
+    conn.execute(&insert, (), None).unwrap();
+    let query = format!("SELECT a, b FROM {table_name};");
+
+    // When
+    let output = cargo_bin_cmd!()
+        .args([
+            "-vvvv",
+            "query",
+            "--connection-string",
+            POSTGRES,
+            "--format",
+            "jsonl",
+            "-",
+            &query,
+        ])
+        .assert()
+        .success();
+
+    // Then
+    let expected = "{\"a\":\"2022-09-07 14:04:12+00:00\",\"b\":[1,null,3]}\n";
+    output.stdout(eq(expected));
+}
+
+#[test]
+fn write_arrow_ipc_file() {
+    // Given
+    let table_name = "WriteArrowIpcFile";
+    let mut table = TableMssql::new(table_name, &["SMALLINT", "VARCHAR(10)", "DECIMAL(5,2)"]);
+    table.insert_rows_as_text(&[["1", "one", "1.50"], ["2", "two", "-2.25"]]);
+    let out_dir = tempdir().unwrap();
+    let out_path = out_dir.path().join("out.arrow");
+    let out_str = out_path.to_str().expect("Temporary file path must be utf8");
+    let query = format!("SELECT a, b, c FROM {table_name} ORDER BY id");
+
+    // When
+    cargo_bin_cmd!()
+        .args([
+            "-vvvv",
+            "query",
+            "--connection-string",
+            MSSQL,
+            "--format",
+            "arrow",
+            out_str,
+            &query,
+        ])
+        .assert()
+        .success();
+
+    // Then
+    let reader =
+        arrow_ipc::reader::FileReader::try_new(File::open(&out_path).unwrap(), None).unwrap();
+    let types: Vec<_> = reader
+        .schema()
+        .fields()
+        .iter()
+        .map(|field| field.data_type().clone())
+        .collect();
+    assert_eq!(
+        vec![
+            arrow_schema::DataType::Int16,
+            arrow_schema::DataType::Utf8,
+            arrow_schema::DataType::Decimal128(5, 2)
+        ],
+        types
+    );
+    let num_rows: usize = reader.map(|batch| batch.unwrap().num_rows()).sum();
+    assert_eq!(2, num_rows);
+}
+
+#[test]
+pub fn reject_partition_by_for_text_formats() {
+    cargo_bin_cmd!()
+        .args([
+            "-vvvv",
+            "query",
+            "--connection-string",
+            "FakeConnectionString",
+            "--format",
+            "csv",
+            "--partition-by",
+            "a",
+            "out_dir",
+            "SELECT a, b FROM FakeTableName",
+        ])
+        .assert()
+        .failure()
+        .stderr(contains(
+            "partition-by is only supported for the parquet format.",
+        ));
+}
+
+#[test]
+fn write_each_result_set_into_its_own_file() {
+    // Given
+    let table_name = "WriteEachResultSetIntoItsOwnFile";
+    let mut table = TableMssql::new(table_name, &["VARCHAR(10)"]);
+    table.insert_rows_as_text(&[["a"], ["b"]]);
+    let out_dir = tempdir().unwrap();
+    let out_path = out_dir.path().join("out.par");
+    let out_str = out_path.to_str().expect("Temporary file path must be utf8");
+    // The update in between produces a result set without columns, which is skipped.
+    let query = format!(
+        "SELECT a FROM {table_name} ORDER BY id; \
+        UPDATE {table_name} SET a = 'z'; \
+        SELECT id, a FROM {table_name} ORDER BY id;"
+    );
+
+    // When
+    cargo_bin_cmd!()
+        .args([
+            "-vvvv",
+            "query",
+            "--connection-string",
+            MSSQL,
+            "--all-result-sets",
+            out_str,
+            &query,
+        ])
+        .assert()
+        .success();
+
+    // Then
+    let first = out_dir.path().join("out_01.par");
+    parquet_read_out(first.to_str().unwrap()).stdout(eq("{a: \"a\"}\n{a: \"b\"}\n"));
+    let second = out_dir.path().join("out_02.par");
+    parquet_read_out(second.to_str().unwrap()).stdout(eq("{id: 1, a: \"z\"}\n{id: 2, a: \"z\"}\n"));
+    assert!(!out_dir.path().join("out_03.par").exists());
+}
+
+#[test]
+fn skip_leading_result_sets_without_columns() {
+    // Given
+    let table_name = "SkipLeadingResultSetsWithoutColumns";
+    let mut table = TableMssql::new(table_name, &["VARCHAR(10)"]);
+    table.insert_rows_as_text(&[["a"], ["b"]]);
+    let out_dir = tempdir().unwrap();
+    let out_path = out_dir.path().join("out.par");
+    let out_str = out_path.to_str().expect("Temporary file path must be utf8");
+    // The statement starts with the row count of the update, rather than a result set.
+    let query = format!(
+        "UPDATE {table_name} SET a = 'z'; \
+        SELECT id, a FROM {table_name} ORDER BY id;"
+    );
+
+    // When
+    cargo_bin_cmd!()
+        .args([
+            "-vvvv",
+            "query",
+            "--connection-string",
+            MSSQL,
+            "--all-result-sets",
+            out_str,
+            &query,
+        ])
+        .assert()
+        .success();
+
+    // Then
+    let first = out_dir.path().join("out_01.par");
+    parquet_read_out(first.to_str().unwrap()).stdout(eq("{id: 1, a: \"z\"}\n{id: 2, a: \"z\"}\n"));
+    assert!(!out_dir.path().join("out_02.par").exists());
+}
+
+#[test]
+pub fn reject_writing_all_result_sets_to_stdout() {
+    cargo_bin_cmd!()
+        .args([
+            "-vvvv",
+            "query",
+            "--connection-string",
+            "FakeConnectionString",
+            "--all-result-sets",
+            "-",
+            "SELECT a FROM FakeTableName",
+        ])
+        .assert()
+        .failure()
+        .stderr(contains(
+            "all-result-sets conflicts with specifying stdout ('-') as output.",
+        ));
+}
+
+#[test]
+fn dump_tables_into_directory() {
+    // Given
+    let table_name = "DumpTablesIntoDirectory";
+    let mut table = TableMssql::new(table_name, &["VARCHAR(10)"]);
+    table.insert_rows_as_text(&[["a"], ["b"]]);
+    let out_dir = tempdir().unwrap();
+    let out_str = out_dir
+        .path()
+        .to_str()
+        .expect("Temporary directory path must be utf8");
+
+    // When
+    cargo_bin_cmd!()
+        .args([
+            "-vvvv",
+            "dump",
+            "--connection-string",
+            MSSQL,
+            "--schema",
+            "dbo",
+            "--table",
+            table_name,
+            out_str,
+        ])
+        .assert()
+        .success();
+
+    // Then
+    let file = out_dir
+        .path()
+        .join(format!("master.dbo.{table_name}.parquet"));
+    parquet_read_out(file.to_str().unwrap()).stdout(eq("{id: 1, a: \"a\"}\n{id: 2, a: \"b\"}\n"));
+}
+
+#[test]
+fn dry_run_prints_schema_and_batch_size() {
+    // Given
+    let table_name = "DryRunPrintsSchemaAndBatchSize";
+    TableMssql::new(table_name, &["VARCHAR(10)"]);
     let query = format!("SELECT a FROM {table_name}");
 
-    let assertion = cargo_bin_cmd!()
+    // When
+    let assert = cargo_bin_cmd!()
         .args([
-            "-vvvv",
             "query",
-            out_str,
             "--connection-string",
             MSSQL,
-            "--sequential-fetching",
-            "--column-length-limit",
-            "5",
+            "--batch-size-row",
+            "100",
+            "--dry-run",
+            "-",
             &query,
         ])
-        .assert();
+        .assert()
+        .success();
 
-    #[cfg(target_os = "windows")]
-    let expectation = "A field exceeds the maximum element length of a column buffer. You can \
-        use the `--column-length-limit` option to increase the maximum element size of columns. \
-        Sadly the driver did not return a length indicator for the value, so you will have to \
-        guess its actual length. The error occurred for column a.";
-    #[cfg(not(target_os = "windows"))]
-    let expectation = "A field exceeds the maximum element length of a column buffer. You can \
-        use the `--column-length-limit` option to increase the maximum element size of columns. \
-        The driver indicated an actual length of 10. The error occurred for column a.";
-    assertion.failure().stderr(contains(expectation));
+    // Then
+    let output = String::from_utf8(assert.get_output().stdout.clone()).unwrap();
+    assert!(output.contains("OPTIONAL BYTE_ARRAY a (STRING);"));
+    assert!(output.contains("a: Text { max_str_len: 10 } (19 bytes per row)"));
+    assert!(output.contains("Batch size: 100 rows"));
 }
 
 #[test]
-fn should_allow_specifying_explicit_compression_level() {
-    // Setup table for test
-    let table_name = "ShouldAllowSpecifyingExplicitCompressionLevel";
+fn write_manifest_for_split_output() {
+    // Given
+    let table_name = "WriteManifestForSplitOutput";
     let mut table = TableMssql::new(table_name, &["VARCHAR(10)"]);
-    table.insert_rows_as_text(&[["Hello"], ["World"]]);
-    // A temporary directory, to be removed at the end of the test.
+    table.insert_rows_as_text(&[["a"], ["b"]]);
     let out_dir = tempdir().unwrap();
-    // The name of the output parquet file we are going to write. Since it is in a temporary
-    // directory it will not outlive the end of the test.
     let out_path = out_dir.path().join("out.par");
-    // We need to pass the output path as a string argument.
     let out_str = out_path.to_str().expect("Temporary file path must be utf8");
+    let manifest_path = out_dir.path().join("manifest.json");
+    let query = format!("SELECT a FROM {table_name} ORDER BY id");
 
-    let query = format!("SELECT a FROM {table_name}");
-
+    // When
     cargo_bin_cmd!()
         .args([
             "-vvvv",
             "query",
             "--connection-string",
             MSSQL,
-            "--column-compression-default",
-            "gzip",
-            "--column-compression-level-default",
-            "9",
+            "--batch-size-row",
+            "1",
+            "--row-groups-per-file",
+            "1",
+            "--manifest",
+            manifest_path.to_str().unwrap(),
             out_str,
             &query,
-            "42",
         ])
         .assert()
         .success();
+
+    // Then
+    let manifest: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&manifest_path).unwrap()).unwrap();
+    let first = out_dir.path().join("out_01.par");
+    assert_eq!(first.to_str().unwrap(), manifest["files"][0]["path"]);
+    assert_eq!(1, manifest["files"][0]["rows"]);
+    assert_eq!(1, manifest["files"][0]["row_groups"]);
+    assert_eq!(
+        fs::metadata(&first).unwrap().len(),
+        manifest["files"][0]["bytes"]
+    );
+    assert_eq!(64, manifest["files"][0]["sha256"].as_str().unwrap().len());
+    assert_eq!(2, manifest["total"]["files"]);
+    assert_eq!(2, manifest["total"]["rows"]);
 }
 
 #[test]
@@ -826,6 +1594,66 @@ fn query_timestamp_mssql_precision_7() {
     parquet_schema_out(out_str).stdout(contains("OPTIONAL INT64 a (TIMESTAMP(NANOS,false));"));
 }
 
+#[test]
+fn query_timestamp_mssql_precision_7_as_micros() {
+    // Setup table for test
+    let table_name = "QueryTimestampMssqlPrecision7AsMicros";
+    let mut table = TableMssql::new(table_name, &["DATETIME2(7)"]);
+    table.insert_rows_as_text(&[["2022-09-07 16:04:12.1234567"]]);
+    // A temporary directory, to be removed at the end of the test.
+    let out_dir = tempdir().unwrap();
+    let out_path = out_dir.path().join("out.par");
+    let out_str = out_path.to_str().expect("Temporary file path must be utf8");
+    let query = format!("SELECT a FROM {table_name};");
+
+    cargo_bin_cmd!()
+        .args([
+            "-vvvv",
+            "query",
+            out_str,
+            "--connection-string",
+            MSSQL,
+            "--timestamp-unit",
+            "micros",
+            &query,
+        ])
+        .assert()
+        .success();
+
+    let expected_values = "{a: 1662566652123456}\n";
+    parquet_read_out(out_str).stdout(eq(expected_values));
+
+    parquet_schema_out(out_str).stdout(contains("OPTIONAL INT64 a (TIMESTAMP(MICROS,false));"));
+}
+
+#[test]
+fn query_timestamp_mssql_as_int96() {
+    // Setup table for test
+    let table_name = "QueryTimestampMssqlAsInt96";
+    let mut table = TableMssql::new(table_name, &["DATETIME2(7)"]);
+    table.insert_rows_as_text(&[["2022-09-07 16:04:12.1234567"]]);
+    // A temporary directory, to be removed at the end of the test.
+    let out_dir = tempdir().unwrap();
+    let out_path = out_dir.path().join("out.par");
+    let out_str = out_path.to_str().expect("Temporary file path must be utf8");
+    let query = format!("SELECT a FROM {table_name};");
+
+    cargo_bin_cmd!()
+        .args([
+            "-vvvv",
+            "query",
+            out_str,
+            "--connection-string",
+            MSSQL,
+            "--timestamp-int96",
+            &query,
+        ])
+        .assert()
+        .success();
+
+    parquet_schema_out(out_str).stdout(contains("OPTIONAL INT96 a;"));
+}
+
 #[test]
 fn query_unsigned_tinyint() {
     // Setup table for test
@@ -859,6 +1687,52 @@ fn query_unsigned_tinyint() {
     parquet_schema_out(out_str).stdout(contains("OPTIONAL INT32 a (INTEGER(8,false));"));
 }
 
+#[test]
+fn query_uuid_mssql() {
+    // Setup table for test
+    let table_name = "QueryUuidMssql";
+    let mut table = TableMssql::new(table_name, &["UNIQUEIDENTIFIER"]);
+    table.insert_rows_as_text(&[["6F9619FF-8B86-D011-B42D-00C04FC964FF"]]);
+    // A temporary directory, to be removed at the end of the test.
+    let out_dir = tempdir().unwrap();
+    // The name of the output parquet file we are going to write. Since it is in a temporary
+    // directory it will not outlive the end of the test.
+    let out_path = out_dir.path().join("out.par");
+    // We need to pass the output path as a string argument.
+    let out_str = out_path.to_str().expect("Temporary file path must be utf8");
+    let query = format!("SELECT a FROM {table_name};");
+
+    cargo_bin_cmd!()
+        .args([
+            "-vvvv",
+            "query",
+            out_str,
+            "--connection-string",
+            MSSQL,
+            &query,
+        ])
+        .assert()
+        .success();
+
+    parquet_schema_out(out_str).stdout(contains("OPTIONAL FIXED_LEN_BYTE_ARRAY (16) a (UUID);"));
+
+    let file = File::open(&out_path).unwrap();
+    let reader = SerializedFileReader::new(file).unwrap();
+    let mut row_iter = reader.get_row_iter(None).unwrap();
+    let row = row_iter.next().unwrap().unwrap();
+    let (_name, field) = row.get_column_iter().next().unwrap();
+    let Field::Bytes(bytes) = field else {
+        panic!("UUID must be represented as bytes")
+    };
+    assert_eq!(
+        &[
+            0x6f, 0x96, 0x19, 0xff, 0x8b, 0x86, 0xd0, 0x11, 0xb4, 0x2d, 0x00, 0xc0, 0x4f, 0xc9,
+            0x64, 0xff
+        ],
+        bytes.data()
+    );
+}
+
 #[test]
 fn should_error_if_timestamp_is_out_of_range() {
     // Setup table for test
@@ -1051,14 +1925,148 @@ fn query_time_0_mssql() {
     // Setup table for test
     let table_name = "QueryTime0";
     let conn = env()
-        .connect_with_connection_string(MSSQL, ConnectionOptions::default())
+        .connect_with_connection_string(MSSQL, ConnectionOptions::default())
+        .unwrap();
+    setup_empty_table_mssql(&conn, table_name, &["TIME(0)"]).unwrap();
+    let insert = format!(
+        "INSERT INTO {table_name}
+        (a)
+        VALUES
+        ('16:04:12');"
+    );
+    conn.execute(&insert, (), None).unwrap();
+    // A temporary directory, to be removed at the end of the test.
+    let out_dir = tempdir().unwrap();
+    // The name of the output parquet file we are going to write. Since it is in a temporary
+    // directory it will not outlive the end of the test.
+    let out_path = out_dir.path().join("out.par");
+    // We need to pass the output path as a string argument.
+    let out_str = out_path.to_str().expect("Temporary file path must be utf8");
+    let query = format!("SELECT a FROM {table_name};");
+
+    cargo_bin_cmd!()
+        .args([
+            "-vvvv",
+            "query",
+            out_str,
+            "--connection-string",
+            MSSQL,
+            &query,
+        ])
+        .assert()
+        .success();
+
+    // We can not use parquet read, as it uses the record API, which does not allow for the TIME type.
+    // let expected_values = "{a: 57852000000000}\n";
+    // parquet_read_out(out_str).stdout(eq(expected_values));
+
+    parquet_schema_out(out_str).stdout(contains("OPTIONAL INT32 a (TIME(MILLIS,false));"));
+}
+
+#[test]
+fn query_time_postgres() {
+    // Setup table for test
+    let table_name = "QueryTimePostgres";
+    let conn = env()
+        .connect_with_connection_string(POSTGRES, ConnectionOptions::default())
+        .unwrap();
+    setup_empty_table_pg(&conn, table_name, &["TIME", "TIME(0)"]).unwrap();
+    let insert = format!(
+        "INSERT INTO {table_name}
+        (a, b)
+        VALUES
+        ('13:45:00.123456', '13:45:00');"
+    );
+    conn.execute(&insert, (), None).unwrap();
+    // A temporary directory, to be removed at the end of the test.
+    let out_dir = tempdir().unwrap();
+    // The name of the output parquet file we are going to write. Since it is in a temporary
+    // directory it will not outlive the end of the test.
+    let out_path = out_dir.path().join("out.par");
+    // We need to pass the output path as a string argument.
+    let out_str = out_path.to_str().expect("Temporary file path must be utf8");
+    let query = format!("SELECT a, b FROM {table_name};");
+
+    cargo_bin_cmd!()
+        .args([
+            "-vvvv",
+            "query",
+            out_str,
+            "--connection-string",
+            POSTGRES,
+            &query,
+        ])
+        .assert()
+        .success();
+
+    // We can not use parquet read, as it uses the record API, which does not allow for the TIME type.
+    parquet_schema_out(out_str)
+        .stdout(contains("OPTIONAL INT64 a (TIME(MICROS,false));"))
+        .stdout(contains("OPTIONAL INT32 b (TIME(MILLIS,false));"));
+}
+
+#[test]
+fn query_array_postgres() {
+    // Setup table for test
+    let table_name = "QueryArrayPostgres";
+    let conn = env()
+        .connect_with_connection_string(POSTGRES, ConnectionOptions::default())
+        .unwrap();
+    setup_empty_table_pg(&conn, table_name, &["INTEGER[]", "TEXT[]"]).unwrap();
+    let insert = format!(
+        "INSERT INTO {table_name}
+        (a, b)
+        VALUES
+        ('{{1,NULL,3}}', '{{\"a b\",NULL}}'),
+        (NULL, '{{}}');"
+    );
+    conn.execute(&insert, (), None).unwrap();
+    // A temporary directory, to be removed at the end of the test.
+    let out_dir = tempdir().unwrap();
+    // The name of the output parquet file we are going to write. Since it is in a temporary
+    // directory it will not outlive the end of the test.
+    let out_path = out_dir.path().join("out.par");
+    // We need to pass the output path as a string argument.
+    let out_str = out_path.to_str().expect("Temporary file path must be utf8");
+    let query = format!("SELECT a, b FROM {table_name} ORDER BY id;");
+
+    cargo_bin_cmd!()
+        .args([
+            "-vvvv",
+            "query",
+            out_str,
+            "--connection-string",
+            POSTGRES,
+            &query,
+        ])
+        .assert()
+        .success();
+
+    parquet_schema_out(out_str)
+        .stdout(contains("OPTIONAL group a (LIST) {"))
+        .stdout(contains("OPTIONAL INT32 element (INTEGER(32,true));"))
+        .stdout(contains("OPTIONAL group b (LIST) {"))
+        .stdout(contains("OPTIONAL BYTE_ARRAY element (UTF8);"));
+    let expected = "\
+        {a: [1, null, 3], b: [\"a b\", null]}\n\
+        {a: null, b: []}\n\
+    ";
+    parquet_read_out(out_str).stdout(eq(expected));
+}
+
+#[test]
+fn query_timestamp_with_timezone_postgres() {
+    // Setup table for test
+    let table_name = "QueryTimestampWithTimezone";
+    let conn = env()
+        .connect_with_connection_string(POSTGRES, ConnectionOptions::default())
         .unwrap();
-    setup_empty_table_mssql(&conn, table_name, &["TIME(0)"]).unwrap();
+    setup_empty_table_pg(&conn, table_name, &["TIMESTAMPTZ"]).unwrap();
     let insert = format!(
         "INSERT INTO {table_name}
         (a)
         VALUES
-        ('16:04:12');"
+        ('2022-09-07 16:04:12 +02:00');"
     );
     conn.execute(&insert, (), None).unwrap();
     // A temporary directory, to be removed at the end of the test.
@@ -1076,32 +2084,31 @@ fn query_time_0_mssql() {
             "query",
             out_str,
             "--connection-string",
-            MSSQL,
+            POSTGRES,
             &query,
         ])
         .assert()
         .success();
 
-    // We can not use parquet read, as it uses the record API, which does not allow for the TIME type.
-    // let expected_values = "{a: 57852000000000}\n";
-    // parquet_read_out(out_str).stdout(eq(expected_values));
+    let expected_values = "{a: 2022-09-07 14:04:12.000000 +00:00}\n";
+    parquet_read_out(out_str).stdout(eq(expected_values));
 
-    parquet_schema_out(out_str).stdout(contains("OPTIONAL INT32 a (TIME(MILLIS,false));"));
+    parquet_schema_out(out_str).stdout(contains("OPTIONAL INT64 a (TIMESTAMP(MICROS,true));"));
 }
 
 #[test]
-fn query_timestamp_with_timezone_postgres() {
+fn query_timestamp_postgres() {
     // Setup table for test
-    let table_name = "QueryTimestampWithTimezone";
+    let table_name = "QueryTimestamp";
     let conn = env()
         .connect_with_connection_string(POSTGRES, ConnectionOptions::default())
         .unwrap();
-    setup_empty_table_pg(&conn, table_name, &["TIMESTAMPTZ"]).unwrap();
+    setup_empty_table_pg(&conn, table_name, &["TIMESTAMP"]).unwrap();
     let insert = format!(
         "INSERT INTO {table_name}
         (a)
         VALUES
-        ('2022-09-07 16:04:12 +02:00');"
+        ('2022-09-07 16:04:12');"
     );
     conn.execute(&insert, (), None).unwrap();
     // A temporary directory, to be removed at the end of the test.
@@ -1125,25 +2132,25 @@ fn query_timestamp_with_timezone_postgres() {
         .assert()
         .success();
 
-    let expected_values = "{a: 2022-09-07 14:04:12.000000 +00:00}\n";
+    let expected_values = "{a: 2022-09-07 16:04:12.000000 +00:00}\n";
     parquet_read_out(out_str).stdout(eq(expected_values));
 
     parquet_schema_out(out_str).stdout(contains("OPTIONAL INT64 a (TIMESTAMP(MICROS,false));"));
 }
 
 #[test]
-fn query_timestamp_postgres() {
+fn query_numeric_50_10_postgres() {
     // Setup table for test
-    let table_name = "QueryTimestamp";
+    let table_name = "QueryNumeric50_10";
     let conn = env()
         .connect_with_connection_string(POSTGRES, ConnectionOptions::default())
         .unwrap();
-    setup_empty_table_pg(&conn, table_name, &["TIMESTAMP"]).unwrap();
+    setup_empty_table_pg(&conn, table_name, &["NUMERIC(50,10)"]).unwrap();
     let insert = format!(
         "INSERT INTO {table_name}
         (a)
         VALUES
-        ('2022-09-07 16:04:12');"
+        (-1234567890123456789012345678901234567890.0123456789);"
     );
     conn.execute(&insert, (), None).unwrap();
     // A temporary directory, to be removed at the end of the test.
@@ -1167,10 +2174,12 @@ fn query_timestamp_postgres() {
         .assert()
         .success();
 
-    let expected_values = "{a: 2022-09-07 16:04:12.000000 +00:00}\n";
+    let expected_values = "{a: -1234567890123456789012345678901234567890.0123456789}\n";
     parquet_read_out(out_str).stdout(eq(expected_values));
 
-    parquet_schema_out(out_str).stdout(contains("OPTIONAL INT64 a (TIMESTAMP(MICROS,false));"));
+    parquet_schema_out(out_str).stdout(contains(
+        "OPTIONAL FIXED_LEN_BYTE_ARRAY (21) a (DECIMAL(50,10));",
+    ));
 }
 
 #[test]
@@ -1451,80 +2460,384 @@ fn emit_file_despite_no_empty_file_set() {
             "-vvvv",
             "query",
             "--connection-string",
-            MSSQL,
-            "--no-empty-file",
-            out_str,
-            query,
+            MSSQL,
+            "--no-empty-file",
+            out_str,
+            query,
+        ])
+        .assert()
+        .success();
+
+    let expected = "{a: 42}\n";
+    parquet_read_out(out_str).stdout(eq(expected));
+}
+
+/// Should read query from standard input if "-" is provided as query text.
+#[test]
+fn read_query_from_stdin() {
+    // Setup table for test
+    let table_name = "ReadQueryFromStdin";
+    let conn = env()
+        .connect_with_connection_string(MSSQL, ConnectionOptions::default())
+        .unwrap();
+    setup_empty_table_mssql(&conn, table_name, &["INT"]).unwrap();
+    let insert = format!(
+        "INSERT INTO {table_name}
+        (a)
+        VALUES
+        (54),
+        (54),
+        (34),
+        (12);"
+    );
+    conn.execute(&insert, (), None).unwrap();
+
+    let expected_values = "\
+        {a: 54}\n\
+        {a: 54}\n\
+        {a: 34}\n\
+        {a: 12}\n\
+    ";
+    let query = format!("SELECT a FROM {table_name} ORDER BY id");
+    // A temporary directory, to be removed at the end of the test.
+    let out_dir = tempdir().unwrap();
+    // The name of the output parquet file we are going to write. Since it is in a temporary
+    // directory it will not outlive the end of the test.
+    let out_path = out_dir.path().join("out.par");
+    // We need to pass the output path as a string argument.
+    let out_str = out_path.to_str().expect("Tempfile path must be utf8");
+
+    cargo_bin_cmd!()
+        .args(["-vvvv", "query", out_str, "--connection-string", MSSQL, "-"])
+        .write_stdin(query)
+        .assert()
+        .success();
+
+    parquet_read_out(out_str).stdout(eq(expected_values));
+}
+
+/// Regression test for #869: every file should hold the configured number of
+/// row groups, including the first.
+#[test]
+fn split_files_on_num_row_groups() {
+    // Given
+    let table_name = "SplitFilesOnNumRowGroups";
+    let conn = env()
+        .connect_with_connection_string(MSSQL, ConnectionOptions::default())
+        .unwrap();
+    setup_empty_table_mssql(&conn, table_name, &["INTEGER"]).unwrap();
+    let insert = format!("INSERT INTO {table_name} (A) VALUES(1),(2),(3),(4),(5)");
+    conn.execute(&insert, (), None).unwrap();
+
+    let out_dir = tempdir().unwrap();
+    let out_path = out_dir.path().join("out.par");
+    let out_str = out_path.to_str().expect("Temporary file path must be utf8");
+
+    let query = format!("SELECT a FROM {table_name}");
+
+    // When
+    cargo_bin_cmd!()
+        .args([
+            "-vvvv",
+            "query",
+            out_str,
+            "--connection-string",
+            MSSQL,
+            "--batch-size-row",
+            "1",
+            "--row-groups-per-file",
+            "2",
+            &query,
+        ])
+        .assert()
+        .success();
+
+    // Then
+    let num_row_groups_in = |file_name| {
+        let path = out_dir.path().join(file_name);
+        let file = File::open(path).unwrap();
+        SerializedFileReader::new(file)
+            .unwrap()
+            .metadata()
+            .num_row_groups()
+    };
+    // The first file must hold the configured number of row groups (2).
+    assert_eq!(2, num_row_groups_in("out_01.par"));
+    // The same goes for the second file.
+    assert_eq!(2, num_row_groups_in("out_02.par"));
+    // For the third file, only one row group is left.
+    assert_eq!(1, num_row_groups_in("out_03.par"));
+}
+
+#[test]
+fn partition_by_column_values() {
+    // Given
+    let table_name = "PartitionByColumnValues";
+    let mut table = TableMssql::new(table_name, &["VARCHAR(10)", "INTEGER", "INTEGER"]);
+    table.insert_rows_as_text(&[
+        [Some("EU"), Some("2024"), Some("1")],
+        [Some("US"), Some("2024"), Some("2")],
+        [Some("EU"), Some("2023"), Some("3")],
+        [Some("EU"), Some("2024"), Some("4")],
+        [None, Some("2024"), Some("5")],
+    ]);
+
+    let out_dir = tempdir().unwrap();
+    let out_str = out_dir
+        .path()
+        .to_str()
+        .expect("Temporary file path must be utf8");
+
+    let query = format!("SELECT a AS region, b AS year, c FROM {table_name} ORDER BY id");
+
+    // When
+    cargo_bin_cmd!()
+        .args([
+            "-vvvv",
+            "query",
+            out_str,
+            "--connection-string",
+            MSSQL,
+            "--partition-by",
+            "region,year",
+            &query,
+        ])
+        .assert()
+        .success();
+
+    // Then
+    let read_partition = |dir: &str| {
+        let path = out_dir.path().join(dir).join("part-01.parquet");
+        parquet_read_out(path.to_str().unwrap())
+    };
+    read_partition("region=EU/year=2024").stdout(eq("{c: 1}\n{c: 4}\n"));
+    read_partition("region=EU/year=2023").stdout(eq("{c: 3}\n"));
+    read_partition("region=US/year=2024").stdout(eq("{c: 2}\n"));
+    read_partition("region=__HIVE_DEFAULT_PARTITION__/year=2024").stdout(eq("{c: 5}\n"));
+}
+
+#[test]
+pub fn reject_writing_to_stdout_and_partition_by() {
+    cargo_bin_cmd!()
+        .args([
+            "-vvvv",
+            "query",
+            "--connection-string",
+            "FakeConnectionString",
+            "--partition-by",
+            "a",
+            "-", // Use `-` to explicitly write to stdout
+            "SELECT a, b FROM FakeTableName",
+        ])
+        .assert()
+        .failure()
+        .stderr(contains(
+            "partition-by conflicts with specifying stdout ('-') as output.",
+        ));
+}
+
+#[test]
+fn split_by_integer_column_ranges() {
+    // Given
+    let table_name = "SplitByIntegerColumnRanges";
+    let mut table = TableMssql::new(table_name, &["INTEGER"]);
+    table.insert_rows_as_text(&[
+        [Some("1")],
+        [Some("2")],
+        [Some("3")],
+        [Some("4")],
+        [Some("5")],
+        [Some("6")],
+        [None],
+    ]);
+
+    let out_dir = tempdir().unwrap();
+    let out_path = out_dir.path().join("out.par");
+    let out_str = out_path.to_str().expect("Temporary file path must be utf8");
+
+    let query = format!("SELECT a FROM {table_name}");
+
+    // When
+    cargo_bin_cmd!()
+        .args([
+            "-vvvv",
+            "query",
+            out_str,
+            "--connection-string",
+            MSSQL,
+            "--split-by",
+            "a",
+            "--parallelism",
+            "3",
+            &query,
+        ])
+        .assert()
+        .success();
+
+    // Then
+    let read_range = |name: &str| {
+        let path = out_dir.path().join(name);
+        parquet_read_out(path.to_str().unwrap())
+    };
+    read_range("out_01.par").stdout(eq("{a: 1}\n{a: 2}\n{a: null}\n"));
+    read_range("out_02.par").stdout(eq("{a: 3}\n{a: 4}\n"));
+    read_range("out_03.par").stdout(eq("{a: 5}\n{a: 6}\n"));
+}
+
+#[test]
+fn split_by_column_name_which_needs_quoting() {
+    // Given
+    let table_name = "SplitByColumnNameWhichNeedsQuoting";
+    let mut table = TableMssql::new(table_name, &["INTEGER"]);
+    table.insert_rows_as_text(&[[Some("1")], [Some("2")]]);
+
+    let out_dir = tempdir().unwrap();
+    let out_path = out_dir.path().join("out.par");
+    let out_str = out_path.to_str().expect("Temporary file path must be utf8");
+
+    let query = format!("SELECT a AS \"order id\" FROM {table_name}");
+
+    // When
+    cargo_bin_cmd!()
+        .args([
+            "-vvvv",
+            "query",
+            out_str,
+            "--connection-string",
+            MSSQL,
+            "--split-by",
+            "order id",
+            "--parallelism",
+            "2",
+            &query,
+        ])
+        .assert()
+        .success();
+
+    // Then
+    let read_range = |name: &str| {
+        let path = out_dir.path().join(name);
+        parquet_read_out(path.to_str().unwrap())
+    };
+    read_range("out_01.par").stdout(eq("{order id: 1}\n"));
+    read_range("out_02.par").stdout(eq("{order id: 2}\n"));
+}
+
+#[test]
+fn split_by_rejects_order_by_on_mssql() {
+    let table_name = "SplitByRejectsOrderBy";
+    let mut table = TableMssql::new(table_name, &["INTEGER"]);
+    table.insert_rows_as_text(&[[Some("1")]]);
+
+    let out_dir = tempdir().unwrap();
+    let out_path = out_dir.path().join("out.par");
+    let out_str = out_path.to_str().expect("Temporary file path must be utf8");
+
+    let query = format!("SELECT a FROM {table_name} ORDER BY a");
+
+    cargo_bin_cmd!()
+        .args([
+            "-vvvv",
+            "query",
+            out_str,
+            "--connection-string",
+            MSSQL,
+            "--split-by",
+            "a",
+            &query,
+        ])
+        .assert()
+        .failure()
+        .stderr(contains(
+            "Please remove the `ORDER BY` clause from the query.",
+        ));
+}
+
+#[test]
+pub fn reject_writing_to_stdout_and_split_by() {
+    cargo_bin_cmd!()
+        .args([
+            "-vvvv",
+            "query",
+            "--connection-string",
+            "FakeConnectionString",
+            "--split-by",
+            "a",
+            "-", // Use `-` to explicitly write to stdout
+            "SELECT a, b FROM FakeTableName",
         ])
         .assert()
-        .success();
-
-    let expected = "{a: 42}\n";
-    parquet_read_out(out_str).stdout(eq(expected));
+        .failure()
+        .stderr(contains(
+            "split-by conflicts with specifying stdout ('-') as output.",
+        ));
 }
 
-/// Should read query from standard input if "-" is provided as query text.
 #[test]
-fn read_query_from_stdin() {
-    // Setup table for test
-    let table_name = "ReadQueryFromStdin";
-    let conn = env()
-        .connect_with_connection_string(MSSQL, ConnectionOptions::default())
-        .unwrap();
-    setup_empty_table_mssql(&conn, table_name, &["INT"]).unwrap();
-    let insert = format!(
-        "INSERT INTO {table_name}
-        (a)
-        VALUES
-        (54),
-        (54),
-        (34),
-        (12);"
-    );
-    conn.execute(&insert, (), None).unwrap();
+fn incremental_extraction_with_state_file() {
+    // Given
+    let table_name = "IncrementalExtractionWithStateFile";
+    let mut table = TableMssql::new(table_name, &["INTEGER"]);
+    table.insert_rows_as_text(&[[Some("1")], [Some("2")]]);
 
-    let expected_values = "\
-        {a: 54}\n\
-        {a: 54}\n\
-        {a: 34}\n\
-        {a: 12}\n\
-    ";
-    let query = format!("SELECT a FROM {table_name} ORDER BY id");
-    // A temporary directory, to be removed at the end of the test.
     let out_dir = tempdir().unwrap();
-    // The name of the output parquet file we are going to write. Since it is in a temporary
-    // directory it will not outlive the end of the test.
     let out_path = out_dir.path().join("out.par");
-    // We need to pass the output path as a string argument.
-    let out_str = out_path.to_str().expect("Tempfile path must be utf8");
+    let out_str = out_path.to_str().expect("Temporary file path must be utf8");
+    let state_path = out_dir.path().join("state.json");
+    let state_str = state_path
+        .to_str()
+        .expect("Temporary file path must be utf8");
 
-    cargo_bin_cmd!()
-        .args(["-vvvv", "query", out_str, "--connection-string", MSSQL, "-"])
-        .write_stdin(query)
-        .assert()
-        .success();
+    let query = format!("SELECT a FROM {table_name}");
+    let run = || {
+        cargo_bin_cmd!()
+            .args([
+                "-vvvv",
+                "query",
+                out_str,
+                "--connection-string",
+                MSSQL,
+                "--incremental-column",
+                "a",
+                "--state-file",
+                state_str,
+                &query,
+            ])
+            .assert()
+            .success();
+    };
 
-    parquet_read_out(out_str).stdout(eq(expected_values));
+    // When
+    run();
+    let first_run = parquet_read_out(out_str);
+    table.insert_rows_as_text(&[[Some("3")]]);
+    run();
+
+    // Then
+    first_run.stdout(eq("{a: 1}\n{a: 2}\n"));
+    parquet_read_out(out_str).stdout(eq("{a: 3}\n"));
+    let state = fs::read_to_string(&state_path).unwrap();
+    assert!(state.contains("\"integer\": 3"));
 }
 
-/// Regression test for #869: every file should hold the configured number of
-/// row groups, including the first.
 #[test]
-fn split_files_on_num_row_groups() {
+fn schema_overrides_pin_column_types() {
     // Given
-    let table_name = "SplitFilesOnNumRowGroups";
-    let conn = env()
-        .connect_with_connection_string(MSSQL, ConnectionOptions::default())
-        .unwrap();
-    setup_empty_table_mssql(&conn, table_name, &["INTEGER"]).unwrap();
-    let insert = format!("INSERT INTO {table_name} (A) VALUES(1),(2),(3),(4),(5)");
-    conn.execute(&insert, (), None).unwrap();
+    let table_name = "SchemaOverridesPinColumnTypes";
+    let mut table = TableMssql::new(table_name, &["VARCHAR(10)", "DECIMAL(10,2)"]);
+    table.insert_rows_as_text(&[[Some("42"), Some("1.23")]]);
 
     let out_dir = tempdir().unwrap();
     let out_path = out_dir.path().join("out.par");
     let out_str = out_path.to_str().expect("Temporary file path must be utf8");
+    let overrides_path = out_dir.path().join("overrides.toml");
+    fs::write(
+        &overrides_path,
+        "[a]\ntype = \"int32\"\nnullable = false\n\n[b]\nprecision = 12\nscale = 3\n",
+    )
+    .unwrap();
 
-    let query = format!("SELECT a FROM {table_name}");
+    let query = format!("SELECT a, b FROM {table_name}");
 
     // When
     cargo_bin_cmd!()
@@ -1534,30 +2847,21 @@ fn split_files_on_num_row_groups() {
             out_str,
             "--connection-string",
             MSSQL,
-            "--batch-size-row",
-            "1",
-            "--row-groups-per-file",
-            "2",
+            "--schema-overrides",
+            overrides_path.to_str().unwrap(),
             &query,
         ])
         .assert()
         .success();
 
     // Then
-    let num_row_groups_in = |file_name| {
-        let path = out_dir.path().join(file_name);
-        let file = File::open(path).unwrap();
-        SerializedFileReader::new(file)
-            .unwrap()
-            .metadata()
-            .num_row_groups()
-    };
-    // The first file must hold the configured number of row groups (2).
-    assert_eq!(2, num_row_groups_in("out_01.par"));
-    // The same goes for the second file.
-    assert_eq!(2, num_row_groups_in("out_02.par"));
-    // For the third file, only one row group is left.
-    assert_eq!(1, num_row_groups_in("out_03.par"));
+    parquet_schema_out(out_str).stdout(contains(
+        "message schema {\n  \
+                REQUIRED INT32 a (INTEGER(32,true));\n  \
+                OPTIONAL INT64 b (DECIMAL(12,3));\n\
+            }",
+    ));
+    parquet_read_out(out_str).stdout(eq("{a: 42, b: 1.230}\n"));
 }
 
 /// Verify naming of the files is with successive numbers starting from 1 to 3 with split files and
@@ -1783,6 +3087,56 @@ fn query_varchar_max() {
     parquet_read_out(out_str).stdout(eq("{a: \"Hello, World!\"}\n"));
 }
 
+/// Values larger than the column length limit are fetched row by row without truncation, if the
+/// column exceeds `--lob-threshold`.
+#[test]
+fn query_varchar_max_row_by_row() {
+    let conn = env()
+        .connect_with_connection_string(MSSQL, ConnectionOptions::default())
+        .unwrap();
+    let table_name = "QueryVarcharMaxRowByRow";
+
+    setup_empty_table_mssql(&conn, table_name, &["INTEGER", "VARCHAR(MAX)"]).unwrap();
+    conn.execute(
+        &format!(
+            "INSERT INTO {table_name} (a, b) Values (1, REPLICATE('x', 10)), (2, NULL), \
+            (3, 'Hello, World!');"
+        ),
+        (),
+        None,
+    )
+    .unwrap();
+
+    // A temporary directory, to be removed at the end of the test.
+    let out_dir = tempdir().unwrap();
+    let out_path = out_dir.path().join("out.par");
+    let out_str = out_path.to_str().expect("Temporary file path must be utf8");
+
+    let query = format!("SELECT a, b FROM {table_name} ORDER BY id;");
+
+    // Values are larger than the column length limit, yet not truncated
+    cargo_bin_cmd!()
+        .args([
+            "-vvvv",
+            "query",
+            "--connection-string",
+            MSSQL,
+            "--column-length-limit",
+            "5",
+            "--lob-threshold",
+            "5",
+            out_str,
+            &query,
+        ])
+        .assert()
+        .success();
+
+    let expected = "{a: 1, b: \"xxxxxxxxxx\"}\n\
+        {a: 2, b: null}\n\
+        {a: 3, b: \"Hello, World!\"}\n";
+    parquet_read_out(out_str).stdout(eq(expected));
+}
+
 /// Introduced after discovering a bug, that columns were not ignored on windows.
 ///
 /// Since VARCHARMAX reports a size of 0, it will be ignored, resulting in an output file with no
@@ -3614,6 +4968,162 @@ pub fn insert_fixed_len_binary_optional() {
     );
 }
 
+#[test]
+pub fn insert_uuid() {
+    let table_name = "InsertUuid";
+    // Prepare table
+    let conn = env()
+        .connect_with_connection_string(MSSQL, ConnectionOptions::default())
+        .unwrap();
+    setup_empty_table_mssql(&conn, table_name, &["UNIQUEIDENTIFIER"]).unwrap();
+
+    // Prepare file
+    let message_type = "
+        message schema {
+            OPTIONAL FIXED_LEN_BYTE_ARRAY(16) a (UUID);
+        }
+    ";
+
+    let uuid: ByteArray = vec![
+        0x6f, 0x96, 0x19, 0xff, 0x8b, 0x86, 0xd0, 0x11, 0xb4, 0x2d, 0x00, 0xc0, 0x4f, 0xc9, 0x64,
+        0xff,
+    ]
+    .into();
+    let uuid: FixedLenByteArray = uuid.into();
+    let input = TmpParquetFile::with_1_dim(message_type, &[Some(uuid), None]);
+
+    // Insert file into table
+    cargo_bin_cmd!()
+        .args([
+            "-vvvv",
+            "insert",
+            "--connection-string",
+            MSSQL,
+            input.path_as_str(),
+            table_name,
+        ])
+        .assert()
+        .success();
+
+    // Query table and check for expected result
+    let query = format!("SELECT a FROM {table_name} ORDER BY Id");
+    let cursor = conn.execute(&query, (), None).unwrap().unwrap();
+    let actual = cursor_to_string(cursor);
+
+    assert_eq!("6F9619FF-8B86-D011-B42D-00C04FC964FF\nNULL", actual);
+}
+
+#[test]
+pub fn insert_array_postgres() {
+    let table_name = "InsertArrayPostgres";
+    // Prepare table
+    let conn = env()
+        .connect_with_connection_string(POSTGRES, ConnectionOptions::default())
+        .unwrap();
+    setup_empty_table_pg(&conn, table_name, &["INTEGER[]"]).unwrap();
+
+    // Prepare file
+    let message_type = "
+        message schema {
+            OPTIONAL group a (LIST) {
+                REPEATED group list {
+                    OPTIONAL INT32 element;
+                }
+            }
+        }
+    ";
+    // Rows: [1, NULL, 3], NULL, []
+    let values = ListColumnI32 {
+        values: vec![1, 3],
+        def_levels: vec![3, 2, 3, 0, 1],
+        rep_levels: vec![0, 1, 1, 0, 0],
+    };
+    let input = TmpParquetFile::new(message_type, &[&values]);
+
+    // Insert file into table
+    cargo_bin_cmd!()
+        .args([
+            "-vvvv",
+            "insert",
+            "--connection-string",
+            POSTGRES,
+            input.path_as_str(),
+            table_name,
+        ])
+        .assert()
+        .success();
+
+    // Query table and check for expected result
+    let query = format!("SELECT a FROM {table_name} ORDER BY id");
+    let cursor = conn.execute(&query, (), None).unwrap().unwrap();
+    let actual = cursor_to_string(cursor);
+
+    assert_eq!("{1,NULL,3}\nNULL\n{}", actual);
+}
+
+#[test]
+pub fn insert_decimal_50_10_postgres() {
+    let table_name = "InsertDecimal50_10Postgres";
+    // Prepare table
+    let conn = env()
+        .connect_with_connection_string(POSTGRES, ConnectionOptions::default())
+        .unwrap();
+    setup_empty_table_pg(&conn, table_name, &["NUMERIC(50,10)"]).unwrap();
+
+    // Prepare file
+    let message_type = "
+        message schema {
+            REQUIRED FIXED_LEN_BYTE_ARRAY(21) a (DECIMAL(50,10));
+        }
+    ";
+    // Big endian two's complement, sign extended to 21 bytes
+    let to_fixed_len_byte_array = |unscaled: &str| -> FixedLenByteArray {
+        let n: num_bigint::BigInt = unscaled.parse().unwrap();
+        let bytes = n.to_signed_bytes_be();
+        let fill = if n.sign() == num_bigint::Sign::Minus {
+            255
+        } else {
+            0
+        };
+        let mut input = vec![fill; 21 - bytes.len()];
+        input.extend_from_slice(&bytes);
+        let ba: ByteArray = input.into();
+        ba.into()
+    };
+    let input = TmpParquetFile::with_1_dim(
+        message_type,
+        &[
+            Some(to_fixed_len_byte_array(
+                "12345678901234567890123456789012345678901234567890",
+            )),
+            Some(to_fixed_len_byte_array("-1")),
+        ],
+    );
+
+    // Insert file into table
+    cargo_bin_cmd!()
+        .args([
+            "-vvvv",
+            "insert",
+            "--connection-string",
+            POSTGRES,
+            input.path_as_str(),
+            table_name,
+        ])
+        .assert()
+        .success();
+
+    // Query table and check for expected result
+    let query = format!("SELECT a FROM {table_name} ORDER BY id");
+    let cursor = conn.execute(&query, (), None).unwrap().unwrap();
+    let actual = cursor_to_string(cursor);
+
+    assert_eq!(
+        "1234567890123456789012345678901234567890.1234567890\n-0.0000000001",
+        actual
+    );
+}
+
 #[test]
 pub fn insert_decimal_from_binary() {
     let table_name = "InsertDecimalFromBinary";
@@ -4185,6 +5695,25 @@ where
     }
 }
 
+/// Values of a three level `LIST` column with `INT32` elements, alongside their definition and
+/// repetition levels.
+struct ListColumnI32 {
+    values: Vec<i32>,
+    def_levels: Vec<i16>,
+    rep_levels: Vec<i16>,
+}
+
+impl ColumnData for ListColumnI32 {
+    fn write(&self, column_writer: &mut ColumnWriter) {
+        if let ColumnWriter::Int32ColumnWriter(cw) = column_writer {
+            cw.write_batch(&self.values, Some(&self.def_levels), Some(&self.rep_levels))
+                .unwrap();
+        } else {
+            panic!("Expected INT32 column writer")
+        }
+    }
+}
+
 /// Sets up a table in the mssql database and allows us to fill it with data. Column names are given
 /// automatically a,b,c, etc.
 pub struct TableMssql<'a, const NUM_COLUMNS: usize> {